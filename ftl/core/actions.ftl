@@ -34,6 +34,8 @@ actions-select = Select
 actions-shortcut-key = Shortcut key: { $val }
 actions-suspend-card = Suspend Card
 actions-set-due-date = Set Due Date
+actions-reschedule-for-vacation = Reschedule for Vacation
+actions-reschedule-backlog = Reschedule Backlog
 actions-answer-card = Answer Card
 actions-unbury-unsuspend = Unbury/Unsuspend
 actions-add-deck = Add Deck
@@ -80,6 +80,37 @@ deck-config-leech-action-tooltip =
     
     `Suspend Card`: In addition to tagging the note, hide the card until it is
     manually unsuspended.
+    
+    `Move to Deck`: In addition to tagging the note, move the card to the leech deck.
+    
+    `Set Flag`: In addition to tagging the note, give the card the leech flag.
+    
+    `Reset to New`: In addition to tagging the note, turn the card back into a new card.
+    
+    `Relearn`: In addition to tagging the note, relearn the card with the leech
+    relearning steps.
+deck-config-leech-move-to-deck = Move to Deck
+deck-config-leech-set-flag = Set Flag
+deck-config-leech-reset = Reset to New
+deck-config-leech-relearn = Relearn
+deck-config-leech-deck = Leech deck
+deck-config-leech-deck-default = Leeches
+deck-config-leech-deck-tooltip =
+    The deck leeches are moved to. It is created if it doesn't exist. If no name
+    is provided, leeches are moved to a "Leeches" deck.
+deck-config-leech-flag = Leech flag
+deck-config-leech-no-flag = No Flag
+deck-config-leech-flag-tooltip =
+    The flag leeches are given. If no flag is chosen, leeches are only tagged.
+deck-config-leech-relearning-steps = Leech relearning steps
+deck-config-leech-relearning-steps-tooltip =
+    The delays a leech is relearnt with when it lapses, instead of the relearning
+    steps. If no delays are provided, the relearning steps are used. { -deck-config-delay-hint }
+deck-config-leech-window-reviews = Recent reviews checked for lapses
+deck-config-leech-window-lapses = Lapses in recent reviews
+deck-config-leech-window-tooltip =
+    In addition to the leech threshold, a card is also marked as a leech if it lapses
+    the given number of times within its most recent reviews. 0 reviews disables this.
 
 ## Burying section
 
@@ -144,6 +175,8 @@ deck-config-sort-order-ascending-intervals = Ascending intervals
 deck-config-sort-order-descending-intervals = Descending intervals
 deck-config-sort-order-ascending-ease = Ascending ease
 deck-config-sort-order-descending-ease = Descending ease
+deck-config-sort-order-relative-overdueness = Relative overdueness
+deck-config-sort-order-retrievability-ascending = Lowest predicted recall
 deck-config-display-order-will-use-current-deck =
     Anki will use the display order from the deck you 
     select to study, and not any subdecks it may have.
@@ -172,6 +205,11 @@ deck-config-always-include-question-audio-tooltip =
 ## Advanced section
 
 deck-config-advanced-title = Advanced
+deck-config-reschedule-cards = Reschedule cards on change
+deck-config-reschedule-cards-tooltip =
+    When enabled, saving changes to the intervals or scheduling model will
+    recalculate the due dates of review cards from their review history, as if
+    they had always been studied with the new options.
 deck-config-maximum-interval-tooltip =
     The maximum number of days a review card will wait. When reviews have
     reached the limit, `Hard`, `Good` and `Easy` will all give the same delay.
@@ -8,6 +8,7 @@ exporting-collection-exported = Collection exported.
 exporting-couldnt-save-file = Couldn't save file: { $val }
 exporting-export = Export...
 exporting-export-format = <b>Export format</b>:
+exporting-exporting-file = Exporting file...
 exporting-include = <b>Include</b>:
 exporting-include-html-and-media-references = Include HTML and media references
 exporting-include-media = Include media
@@ -30,3 +31,8 @@ exporting-note-exported =
         [one] { $count } note exported.
        *[other] { $count } notes exported.
     }
+exporting-exported-rows =
+    { $count ->
+        [one] Exported { $count } row
+       *[other] Exported { $count } rows
+    }
@@ -21,6 +21,7 @@ importing-ignore-lines-where-first-field-matches = Ignore lines where first fiel
 importing-ignored = <ignored>
 importing-import-even-if-existing-note-has = Import even if existing note has same first field
 importing-import-options = Import options
+importing-importing-file = Importing file...
 importing-importing-complete = Importing complete.
 importing-invalid-file-please-restore-from-backup = Invalid file. Please restore from backup.
 importing-map-to = Map to { $val }
@@ -77,3 +78,8 @@ importing-processed-media-file =
         [one] Processed { $count } media file
        *[other] Processed { $count } media files
     }
+importing-processed-notes =
+    { $count ->
+        [one] Processed { $count } note...
+       *[other] Processed { $count } notes...
+    }
@@ -27,6 +27,7 @@ search-invalid-whole-number = expected a whole number in "`{ $context }`", but f
 search-invalid-positive-whole-number = expected a positive whole number in "`{ $context }`", but found "`{ $provided }`".
 search-invalid-negative-whole-number = expected a whole number less than or equal to 0 in "`{ $context }`", but found "`{ $provided }`".
 search-invalid-answer-button = expected an answer button between 1-4 in "`{ $context }`", but found "`{ $provided }`".
+search-invalid-date = expected a date like 2026-09-30 or 2026-09, or a range like 2026-01-01..2026-03-31, in "`{ $context }`", but found "`{ $provided }`".
 
 ## Column labels in browse screen
 
@@ -99,10 +99,14 @@ class Collection(DeprecatedNamesMixin):
         backend: RustBackend | None = None,
         server: bool = False,
         log: bool = False,
+        backup_folder: str | None = None,
     ) -> None:
+        """If backup_folder is provided, the collection is backed up into it
+        at the configured interval while it is open."""
         self._backend = backend or RustBackend(server=server)
         self.db: DBProxy | None = None
         self._should_log = log
+        self._backup_folder = backup_folder
         self.server = server
         self.path = os.path.abspath(path)
         self.reopen()
@@ -235,18 +239,49 @@ class Collection(DeprecatedNamesMixin):
         elif time.time() - self._last_checkpoint_at > 300:
             self.save()
 
-    def close(self, save: bool = True, downgrade: bool = False) -> None:
-        "Disconnect from DB."
+    def close(
+        self,
+        save: bool = True,
+        downgrade: bool = False,
+        backup_folder: str | None = None,
+    ) -> None:
+        """Disconnect from DB.
+        If backup_folder is provided, a backup is written to it in the
+        background, unless a recent one exists. Call await_backup_completion()
+        before exiting."""
         if self.db:
             if save:
                 self.save(trx=False)
             else:
                 self.db.rollback()
             self._clear_caches()
-            self._backend.close_collection(downgrade_to_schema11=downgrade)
+            self._backend.close_collection(
+                downgrade_to_schema11=downgrade, backup_folder=backup_folder or ""
+            )
             self.db = None
             self.media.close()
 
+    def create_backup(
+        self, backup_folder: str, force: bool = False, wait_for_completion: bool = False
+    ) -> bool:
+        """Write a backup of the collection into backup_folder, unless a
+        backup was made there within the configured minimum interval and
+        force is False. Returns true if a backup was started."""
+        # the database is copied outside of a transaction
+        self.save(trx=False)
+        try:
+            return self._backend.create_backup(
+                backup_folder=backup_folder,
+                force=force,
+                wait_for_completion=wait_for_completion,
+            )
+        finally:
+            self.db.begin()
+
+    def await_backup_completion(self) -> None:
+        "Wait for a backup started in the background to be written."
+        self._backend.await_backup_completion()
+
     def close_for_full_sync(self) -> None:
         # save and cleanup, but backend will take care of collection close
         if self.db:
@@ -284,6 +319,7 @@ class Collection(DeprecatedNamesMixin):
                 media_folder_path=media_dir,
                 media_db_path=media_db,
                 log_path=log_path,
+                backup_folder=self._backup_folder or "",
             )
         else:
             self.media.connect()
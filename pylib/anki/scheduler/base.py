@@ -180,6 +180,21 @@ select id from cards where did in %s and queue = {QUEUE_TYPE_REV} and due <= ? l
             config_key=key,  # type: ignore
         )
 
+    def reschedule_for_vacation(
+        self, first_day: int, last_day: int
+    ) -> OpChangesWithCount:
+        """Move reviews due from `first_day` to `last_day` days from today
+        (inclusive) to the less busy days around that range."""
+        return self.col._backend.reschedule_for_vacation(
+            first_day=first_day, last_day=last_day
+        )
+
+    def reschedule_backlog(self, days: int, daily_cap: int) -> OpChangesWithCount:
+        """Spread overdue reviews over the coming `days` days, with no more than
+        `daily_cap` reviews a day, starting with the cards most likely to be
+        remembered."""
+        return self.col._backend.reschedule_backlog(days=days, daily_cap=daily_cap)
+
     def reset_cards(self, ids: list[CardId]) -> None:
         "Completely reset cards for export."
         sids = ids2str(ids)
@@ -109,6 +109,10 @@ pub fn write_backend_proto_rs() {
             "HelpPageLinkRequest.HelpPage",
             "#[derive(strum::EnumIter)]",
         )
+        .type_attribute(
+            "Preferences.BackupLimits",
+            "#[derive(serde_derive::Deserialize, serde_derive::Serialize)]",
+        )
         .compile_protos(paths.as_slice(), &[proto_dir])
         .unwrap();
 }
@@ -1,6 +1,8 @@
 // Copyright: Ankitects Pty Ltd and contributors
 // License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
 
+use std::path::Path;
+
 use slog::error;
 
 use super::{progress::Progress, Backend};
@@ -8,7 +10,8 @@ pub(super) use crate::backend_proto::collection_service::Service as CollectionSe
 use crate::{
     backend::progress::progress_to_proto,
     backend_proto as pb,
-    collection::CollectionBuilder,
+    collection::{backup::list_backups, CollectionBuilder},
+    import_export::package::import_colpkg,
     log::{self},
     prelude::*,
 };
@@ -38,6 +41,9 @@ impl CollectionService for Backend {
         if !input.log_path.is_empty() {
             builder.set_log_file(&input.log_path)?;
         }
+        if !input.backup_folder.is_empty() {
+            builder.set_backup_folder(input.backup_folder);
+        }
 
         *col = Some(builder.build()?);
 
@@ -52,7 +58,19 @@ impl CollectionService for Backend {
             return Err(AnkiError::CollectionNotOpen);
         }
 
-        let col_inner = col.take().unwrap();
+        let mut col_inner = col.take().unwrap();
+        if !input.backup_folder.is_empty() {
+            if let Err(e) = col_inner.maybe_backup(input.backup_folder, false) {
+                error!(col_inner.log, "backup failed: {:?}", e);
+            }
+        }
+        // the backup is finished in the background after closing
+        if let Some(task) = col_inner.take_backup_task() {
+            if let Err(e) = self.join_backup_task() {
+                error!(col_inner.log, "backup failed: {:?}", e);
+            }
+            self.state.lock().unwrap().backup_task = Some(task);
+        }
         if input.downgrade_to_schema11 {
             let log = log::terminal();
             if let Err(e) = col_inner.close(input.downgrade_to_schema11) {
@@ -97,4 +115,65 @@ impl CollectionService for Backend {
         self.with_col(|col| col.merge_undoable_ops(starting_from))
             .map(Into::into)
     }
+
+    fn create_backup(&self, input: pb::CreateBackupRequest) -> Result<pb::Bool> {
+        self.join_backup_task()?;
+        self.with_col(|col| {
+            let created = col.maybe_backup(input.backup_folder, input.force)?;
+            if input.wait_for_completion {
+                col.await_backup_completion()?;
+            }
+            Ok(created.into())
+        })
+    }
+
+    fn await_backup_completion(&self, _input: pb::Empty) -> Result<pb::Empty> {
+        self.join_backup_task()?;
+        if let Some(col) = self.col.lock().unwrap().as_mut() {
+            col.await_backup_completion()?;
+        }
+        Ok(().into())
+    }
+
+    fn list_backups(&self, input: pb::String) -> Result<pb::StringList> {
+        let paths: Vec<String> = list_backups(Path::new(&input.val))?
+            .into_iter()
+            .map(|backup| backup.path.to_string_lossy().into_owned())
+            .collect();
+        Ok(paths.into())
+    }
+
+    fn restore_backup(&self, input: pb::RestoreBackupRequest) -> Result<pb::Empty> {
+        let col = self.col.lock().unwrap();
+        if col.is_some() {
+            return Err(AnkiError::CollectionAlreadyOpen);
+        }
+        // don't restore a backup that is still being written
+        self.join_backup_task()?;
+
+        let mut handler = self.new_progress_handler();
+        let progress_fn = move |progress| handler.update(Progress::Import(progress), true);
+        import_colpkg(
+            &input.backup_path,
+            &input.col_path,
+            &input.media_folder,
+            &input.media_db,
+            progress_fn,
+        )
+        .map(Into::into)
+    }
+}
+
+impl Backend {
+    /// Wait for the backup of a closed collection, if any, returning its
+    /// result.
+    fn join_backup_task(&self) -> Result<()> {
+        let task = self.state.lock().unwrap().backup_task.take();
+        match task {
+            Some(task) => task
+                .join()
+                .map_err(|_| AnkiError::IoError("backup thread panicked".into()))?,
+            None => Ok(()),
+        }
+    }
 }
@@ -95,6 +95,7 @@ pub(super) fn db_command_bytes(col: &mut Collection, input: &[u8]) -> Result<Vec
                 col.state.modified_by_dbproxy = false;
             }
             col.storage.commit_trx()?;
+            col.maybe_backup_periodically();
             DbResult::None
         }
         DbRequest::Rollback => {
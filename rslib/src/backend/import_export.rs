@@ -0,0 +1,285 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::str::FromStr;
+
+use super::{progress::Progress, Backend};
+pub(super) use crate::backend_proto::importexport_service::Service as ImportExportService;
+use crate::{
+    backend_proto::{
+        self as pb, csv_metadata::Delimiter as DelimiterProto,
+        export_csv_request::column::Value as ExportColumnProto,
+        export_revlog_request::Format as RevlogFormatProto,
+        import_anki_package_request::UpdateCondition as UpdateConditionProto,
+        import_csv_request::DupeResolution as DupeResolutionProto,
+    },
+    browser_table::Column,
+    import_export::{
+        package::import_colpkg,
+        text::{
+            CsvImportOptions, CsvMetadata, Delimiter, DupeResolution, ExportColumn,
+            RevlogExportFormat,
+        },
+        LogNote, NoteLog, UpdateCondition,
+    },
+    prelude::*,
+};
+
+impl ImportExportService for Backend {
+    fn import_collection_package(
+        &self,
+        input: pb::ImportCollectionPackageRequest,
+    ) -> Result<pb::Empty> {
+        let col = self.col.lock().unwrap();
+        if col.is_some() {
+            return Err(AnkiError::CollectionAlreadyOpen);
+        }
+
+        let mut handler = self.new_progress_handler();
+        let progress_fn = move |progress| handler.update(Progress::Import(progress), true);
+        import_colpkg(
+            &input.backup_path,
+            &input.col_path,
+            &input.media_folder,
+            &input.media_db,
+            progress_fn,
+        )
+        .map(Into::into)
+    }
+
+    fn export_collection_package(
+        &self,
+        input: pb::ExportCollectionPackageRequest,
+    ) -> Result<pb::Empty> {
+        self.abort_media_sync_and_wait();
+
+        let mut col = self.col.lock().unwrap();
+        let col_inner = col.take().ok_or(AnkiError::CollectionNotOpen)?;
+
+        let mut handler = self.new_progress_handler();
+        let progress_fn = move |progress| handler.update(Progress::Export(progress), true);
+        col_inner
+            .export_colpkg(&input.out_path, input.include_media, progress_fn)
+            .map(Into::into)
+    }
+
+    fn import_anki_package(
+        &self,
+        input: pb::ImportAnkiPackageRequest,
+    ) -> Result<pb::ImportResponse> {
+        let mut handler = self.new_progress_handler();
+        let progress_fn = move |progress| handler.update(Progress::Import(progress), true);
+        let update_condition = UpdateConditionProto::from_i32(input.update_notes)
+            .unwrap_or(UpdateConditionProto::IfNewer)
+            .into();
+        self.with_col(|col| col.import_apkg(&input.package_path, update_condition, progress_fn))
+            .map(Into::into)
+    }
+
+    fn export_anki_package(&self, input: pb::ExportAnkiPackageRequest) -> Result<pb::UInt32> {
+        let mut handler = self.new_progress_handler();
+        let progress_fn = move |progress| handler.update(Progress::Export(progress), true);
+        self.with_col(|col| {
+            col.export_apkg(
+                &input.search,
+                input.with_scheduling,
+                input.with_media,
+                &input.out_path,
+                progress_fn,
+            )
+        })
+        .map(Into::into)
+    }
+
+    fn get_csv_metadata(&self, input: pb::CsvMetadataRequest) -> Result<pb::CsvMetadata> {
+        let delimiter = if input.detect_delimiter {
+            None
+        } else {
+            Some(input.delimiter().into())
+        };
+        self.with_col(|col| col.get_csv_metadata(&input.path, delimiter))
+            .map(Into::into)
+    }
+
+    fn preview_csv_import(&self, input: pb::ImportCsvRequest) -> Result<pb::import_response::Log> {
+        let path = input.path.clone();
+        let options = input.into();
+        self.with_col(|col| col.preview_csv_import(&path, &options))
+            .map(Into::into)
+    }
+
+    fn import_csv(&self, input: pb::ImportCsvRequest) -> Result<pb::ImportResponse> {
+        let mut handler = self.new_progress_handler();
+        let progress_fn = move |progress| handler.update(Progress::Import(progress), true);
+        let path = input.path.clone();
+        let options = input.into();
+        self.with_col(|col| col.import_csv(&path, &options, progress_fn))
+            .map(Into::into)
+    }
+
+    fn export_note_csv(&self, input: pb::ExportCsvRequest) -> Result<pb::UInt32> {
+        let mut handler = self.new_progress_handler();
+        let progress_fn = move |progress| handler.update(Progress::Export(progress), true);
+        let columns = export_columns(&input.columns)?;
+        self.with_col(|col| {
+            col.export_note_csv(
+                &input.search,
+                &columns,
+                input.delimiter().into(),
+                input.strip_html,
+                &input.out_path,
+                progress_fn,
+            )
+        })
+        .map(Into::into)
+    }
+
+    fn export_card_csv(&self, input: pb::ExportCsvRequest) -> Result<pb::UInt32> {
+        let mut handler = self.new_progress_handler();
+        let progress_fn = move |progress| handler.update(Progress::Export(progress), true);
+        let columns = export_columns(&input.columns)?;
+        self.with_col(|col| {
+            col.export_card_csv(
+                &input.search,
+                &columns,
+                input.delimiter().into(),
+                input.strip_html,
+                &input.out_path,
+                progress_fn,
+            )
+        })
+        .map(Into::into)
+    }
+
+    fn export_revlog(&self, input: pb::ExportRevlogRequest) -> Result<pb::UInt32> {
+        let mut handler = self.new_progress_handler();
+        let progress_fn = move |progress| handler.update(Progress::Export(progress), true);
+        let format = match input.format() {
+            RevlogFormatProto::Csv => RevlogExportFormat::Csv,
+            RevlogFormatProto::Ndjson => RevlogExportFormat::Ndjson,
+        };
+        self.with_col(|col| col.export_revlog(&input.search, format, &input.out_path, progress_fn))
+            .map(Into::into)
+    }
+}
+
+impl From<UpdateConditionProto> for UpdateCondition {
+    fn from(condition: UpdateConditionProto) -> Self {
+        match condition {
+            UpdateConditionProto::IfNewer => UpdateCondition::IfNewer,
+            UpdateConditionProto::Always => UpdateCondition::Always,
+            UpdateConditionProto::Never => UpdateCondition::Never,
+        }
+    }
+}
+
+impl From<OpOutput<NoteLog>> for pb::ImportResponse {
+    fn from(output: OpOutput<NoteLog>) -> Self {
+        Self {
+            changes: Some(output.changes.into()),
+            log: Some(output.output.into()),
+        }
+    }
+}
+
+impl From<NoteLog> for pb::import_response::Log {
+    fn from(log: NoteLog) -> Self {
+        let convert = |notes: Vec<LogNote>| notes.into_iter().map(Into::into).collect();
+        Self {
+            added: convert(log.added),
+            updated: convert(log.updated),
+            skipped: convert(log.skipped),
+            conflicting: convert(log.conflicting),
+            empty_first_field: convert(log.empty_first_field),
+            missing_notetype: convert(log.missing_notetype),
+            failed_media: log.failed_media,
+        }
+    }
+}
+
+impl From<LogNote> for pb::import_response::Note {
+    fn from(note: LogNote) -> Self {
+        Self {
+            id: Some(pb::NoteId { nid: note.id.0 }),
+            fields: note.fields,
+        }
+    }
+}
+
+impl From<DelimiterProto> for Delimiter {
+    fn from(delimiter: DelimiterProto) -> Self {
+        match delimiter {
+            DelimiterProto::Tab => Delimiter::Tab,
+            DelimiterProto::Comma => Delimiter::Comma,
+            DelimiterProto::Semicolon => Delimiter::Semicolon,
+            DelimiterProto::Pipe => Delimiter::Pipe,
+            DelimiterProto::Colon => Delimiter::Colon,
+            DelimiterProto::Space => Delimiter::Space,
+        }
+    }
+}
+
+impl From<Delimiter> for DelimiterProto {
+    fn from(delimiter: Delimiter) -> Self {
+        match delimiter {
+            Delimiter::Tab => DelimiterProto::Tab,
+            Delimiter::Comma => DelimiterProto::Comma,
+            Delimiter::Semicolon => DelimiterProto::Semicolon,
+            Delimiter::Pipe => DelimiterProto::Pipe,
+            Delimiter::Colon => DelimiterProto::Colon,
+            Delimiter::Space => DelimiterProto::Space,
+        }
+    }
+}
+
+impl From<CsvMetadata> for pb::CsvMetadata {
+    fn from(metadata: CsvMetadata) -> Self {
+        Self {
+            delimiter: DelimiterProto::from(metadata.delimiter) as i32,
+            has_header: metadata.has_header,
+            column_labels: metadata.column_labels,
+            preview: metadata.preview.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<pb::ImportCsvRequest> for CsvImportOptions {
+    fn from(request: pb::ImportCsvRequest) -> Self {
+        Self {
+            delimiter: request.delimiter().into(),
+            has_header: request.has_header,
+            is_html: request.is_html,
+            notetype_id: NotetypeId(request.notetype_id),
+            deck_id: DeckId(request.deck_id),
+            field_columns: request.field_columns.iter().map(|c| column(*c)).collect(),
+            tags_column: column(request.tags_column),
+            deck_column: column(request.deck_column),
+            notetype_column: column(request.notetype_column),
+            guid_column: column(request.guid_column),
+            dupe_resolution: match request.dupe_resolution() {
+                DupeResolutionProto::Skip => DupeResolution::Skip,
+                DupeResolutionProto::Update => DupeResolution::Update,
+                DupeResolutionProto::Add => DupeResolution::Add,
+            },
+            global_tags: request.global_tags,
+        }
+    }
+}
+
+/// Convert a 1-based column number, where 0 means none.
+fn column(number: u32) -> Option<usize> {
+    number.checked_sub(1).map(|idx| idx as usize)
+}
+
+fn export_columns(columns: &[pb::export_csv_request::Column]) -> Result<Vec<ExportColumn>> {
+    columns
+        .iter()
+        .map(|column| match &column.value {
+            Some(ExportColumnProto::BrowserColumn(name)) => Column::from_str(name)
+                .map(ExportColumn::Browser)
+                .map_err(|_| AnkiError::invalid_input(format!("unknown column: {}", name))),
+            Some(ExportColumnProto::FieldIndex(idx)) => Ok(ExportColumn::Field(*idx as usize)),
+            None => Err(AnkiError::invalid_input("missing column")),
+        })
+        .collect()
+}
@@ -15,6 +15,7 @@ mod decks;
 mod error;
 mod generic;
 mod i18n;
+mod import_export;
 mod links;
 mod media;
 mod notes;
@@ -30,6 +31,7 @@ mod tags;
 use std::{
     result,
     sync::{Arc, Mutex},
+    thread::JoinHandle,
 };
 
 use once_cell::sync::OnceCell;
@@ -45,6 +47,7 @@ use self::{
     deckconfig::DeckConfigService,
     decks::DecksService,
     i18n::I18nService,
+    import_export::ImportExportService,
     links::LinksService,
     media::MediaService,
     notes::NotesService,
@@ -77,6 +80,7 @@ pub struct Backend {
 #[derive(Default)]
 struct BackendState {
     sync: SyncState,
+    backup_task: Option<JoinHandle<Result<()>>>,
 }
 
 pub fn init_backend(init_msg: &[u8]) -> std::result::Result<Backend, String> {
@@ -137,6 +141,9 @@ impl Backend {
                 pb::ServiceIndex::Links => LinksService::run_method(self, method, input),
                 pb::ServiceIndex::Collection => CollectionService::run_method(self, method, input),
                 pb::ServiceIndex::Cards => CardsService::run_method(self, method, input),
+                pb::ServiceIndex::ImportExport => {
+                    ImportExportService::run_method(self, method, input)
+                }
             })
             .map_err(|err| {
                 let backend_err = err.into_protobuf(&self.tr);
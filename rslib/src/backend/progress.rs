@@ -10,7 +10,9 @@ use crate::{
     backend_proto as pb,
     dbcheck::DatabaseCheckProgress,
     i18n::I18n,
+    import_export::{ExportProgress, ImportProgress},
     media::sync::MediaSyncProgress,
+    scheduler::optimizer::OptimizerProgress,
     sync::{FullSyncProgress, NormalSyncProgress, SyncStage},
 };
 
@@ -50,6 +52,9 @@ pub(super) enum Progress {
     FullSync(FullSyncProgress),
     NormalSync(NormalSyncProgress),
     DatabaseCheck(DatabaseCheckProgress),
+    Import(ImportProgress),
+    Export(ExportProgress),
+    ComputeParams(OptimizerProgress),
 }
 
 pub(super) fn progress_to_proto(progress: Option<Progress>, tr: &I18n) -> pb::Progress {
@@ -103,6 +108,28 @@ pub(super) fn progress_to_proto(progress: Option<Progress>, tr: &I18n) -> pb::Pr
                     stage_current,
                 })
             }
+            Progress::Import(progress) => pb::progress::Value::Importing(
+                match progress {
+                    ImportProgress::File => tr.importing_importing_file(),
+                    ImportProgress::Media(n) => tr.importing_processed_media_file(n),
+                    ImportProgress::Notes(n) => tr.importing_processed_notes(n),
+                }
+                .into(),
+            ),
+            Progress::Export(progress) => pb::progress::Value::Exporting(
+                match progress {
+                    ExportProgress::File => tr.exporting_exporting_file(),
+                    ExportProgress::Media(n) => tr.exporting_exported_media_file(n),
+                    ExportProgress::Rows(n) => tr.exporting_exported_rows(n),
+                }
+                .into(),
+            ),
+            Progress::ComputeParams(p) => {
+                pb::progress::Value::ComputeParams(pb::progress::ComputeParams {
+                    current: p.current,
+                    total: p.total,
+                })
+            }
         }
     } else {
         pb::progress::Value::None(pb::Empty {})
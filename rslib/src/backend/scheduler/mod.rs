@@ -4,13 +4,15 @@
 mod answering;
 mod states;
 
-use super::Backend;
+use super::{progress::Progress, Backend};
 pub(super) use crate::backend_proto::scheduler_service::Service as SchedulerService;
 use crate::{
     backend_proto::{self as pb},
     prelude::*,
     scheduler::{
         new::NewCardDueOrder,
+        optimizer::{FitMetrics, OptimizedSm2Params},
+        simulator::SimulatedDay,
         states::{CardState, NextCardStates},
     },
     stats::studied_today,
@@ -123,6 +125,26 @@ impl SchedulerService for Backend {
         self.with_col(|col| col.set_due_date(&cids, &days, config).map(Into::into))
     }
 
+    fn reschedule_for_vacation(
+        &self,
+        input: pb::RescheduleForVacationRequest,
+    ) -> Result<pb::OpChangesWithCount> {
+        self.with_col(|col| {
+            col.reschedule_for_vacation(input.first_day, input.last_day)
+                .map(Into::into)
+        })
+    }
+
+    fn reschedule_backlog(
+        &self,
+        input: pb::RescheduleBacklogRequest,
+    ) -> Result<pb::OpChangesWithCount> {
+        self.with_col(|col| {
+            col.reschedule_backlog(input.days, input.daily_cap)
+                .map(Into::into)
+        })
+    }
+
     fn sort_cards(&self, input: pb::SortCardsRequest) -> Result<pb::OpChangesWithCount> {
         let cids = input.card_ids.into_newtype(CardId);
         let (start, step, random, shift) = (
@@ -182,6 +204,31 @@ impl SchedulerService for Backend {
                 .map(Into::into)
         })
     }
+
+    fn optimize_sm2_params(
+        &self,
+        input: pb::OptimizeSm2ParamsRequest,
+    ) -> Result<pb::OptimizeSm2ParamsResponse> {
+        let mut handler = self.new_progress_handler();
+        let progress_fn = move |progress| handler.update(Progress::ComputeParams(progress), true);
+        self.with_col(|col| {
+            col.optimize_sm2_params(&input.search, input.config_id.into(), progress_fn)
+                .map(Into::into)
+        })
+    }
+
+    fn simulate_workload(
+        &self,
+        input: pb::SimulateWorkloadRequest,
+    ) -> Result<pb::SimulateWorkloadResponse> {
+        let config: DeckConfig = input.config.unwrap_or_default().into();
+        self.with_col(|col| {
+            col.simulate_workload(&config, &input.search, input.days, input.iterations)
+                .map(|days| pb::SimulateWorkloadResponse {
+                    days: days.into_iter().map(Into::into).collect(),
+                })
+        })
+    }
 }
 
 impl From<crate::scheduler::timing::SchedTimingToday> for pb::SchedTimingTodayResponse {
@@ -192,3 +239,37 @@ impl From<crate::scheduler::timing::SchedTimingToday> for pb::SchedTimingTodayRe
         }
     }
 }
+
+impl From<OptimizedSm2Params> for pb::OptimizeSm2ParamsResponse {
+    fn from(params: OptimizedSm2Params) -> Self {
+        pb::OptimizeSm2ParamsResponse {
+            initial_ease: params.initial_ease,
+            interval_multiplier: params.interval_multiplier,
+            lapse_multiplier: params.lapse_multiplier,
+            maximum_review_interval: params.maximum_review_interval,
+            review_count: params.review_count,
+            current: Some(params.current.into()),
+            optimized: Some(params.optimized.into()),
+        }
+    }
+}
+
+impl From<FitMetrics> for pb::optimize_sm2_params_response::Fit {
+    fn from(metrics: FitMetrics) -> Self {
+        pb::optimize_sm2_params_response::Fit {
+            log_loss: metrics.log_loss,
+            rmse: metrics.rmse,
+        }
+    }
+}
+
+impl From<SimulatedDay> for pb::simulate_workload_response::Day {
+    fn from(day: SimulatedDay) -> Self {
+        pb::simulate_workload_response::Day {
+            reviews: day.reviews,
+            learning: day.learning,
+            new_cards: day.new_cards,
+            study_secs: day.study_secs,
+        }
+    }
+}
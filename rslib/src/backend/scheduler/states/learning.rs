@@ -8,6 +8,7 @@ impl From<pb::scheduling_state::Learning> for LearnState {
         LearnState {
             remaining_steps: state.remaining_steps,
             scheduled_secs: state.scheduled_secs,
+            memory_state: state.memory_state.map(Into::into),
         }
     }
 }
@@ -17,6 +18,7 @@ impl From<LearnState> for pb::scheduling_state::Learning {
         pb::scheduling_state::Learning {
             remaining_steps: state.remaining_steps,
             scheduled_secs: state.scheduled_secs,
+            memory_state: state.memory_state.map(Into::into),
         }
     }
 }
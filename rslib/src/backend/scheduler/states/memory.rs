@@ -0,0 +1,22 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use crate::{backend_proto as pb, scheduler::states::MemoryState};
+
+impl From<pb::scheduling_state::MemoryState> for MemoryState {
+    fn from(state: pb::scheduling_state::MemoryState) -> Self {
+        MemoryState {
+            stability: state.stability,
+            difficulty: state.difficulty,
+        }
+    }
+}
+
+impl From<MemoryState> for pb::scheduling_state::MemoryState {
+    fn from(state: MemoryState) -> Self {
+        pb::scheduling_state::MemoryState {
+            stability: state.stability,
+            difficulty: state.difficulty,
+        }
+    }
+}
@@ -3,6 +3,7 @@
 
 mod filtered;
 mod learning;
+mod memory;
 mod new;
 mod normal;
 mod preview;
@@ -11,6 +11,7 @@ impl From<pb::scheduling_state::Review> for ReviewState {
             ease_factor: state.ease_factor,
             lapses: state.lapses,
             leeched: state.leeched,
+            memory_state: state.memory_state.map(Into::into),
         }
     }
 }
@@ -23,6 +24,7 @@ impl From<ReviewState> for pb::scheduling_state::Review {
             ease_factor: state.ease_factor,
             lapses: state.lapses,
             leeched: state.leeched,
+            memory_state: state.memory_state.map(Into::into),
         }
     }
 }
@@ -237,6 +237,22 @@ impl Collection {
         RowContext::new(self, id, notes_mode, card_render_required(&columns))?.browser_row(&columns)
     }
 
+    /// The text of the provided columns for a card, or a note in notes mode,
+    /// as it is shown in the browser. The note is returned as well.
+    pub(crate) fn browser_cell_texts(
+        &mut self,
+        id: i64,
+        notes_mode: bool,
+        columns: &[Column],
+    ) -> Result<(Vec<String>, Note)> {
+        let context = RowContext::new(self, id, notes_mode, card_render_required(columns))?;
+        let texts = columns
+            .iter()
+            .map(|&column| context.get_cell_text(column))
+            .collect::<Result<_>>()?;
+        Ok((texts, context.note))
+    }
+
     fn get_note_maybe_with_fields(&self, id: NoteId, _with_fields: bool) -> Result<Note> {
         // todo: After note.sort_field has been modified so it can be displayed in the browser,
         // we can update note_field_str() and only load the note with fields if a card render is
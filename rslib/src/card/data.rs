@@ -0,0 +1,167 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    prelude::*,
+    scheduler::states::{MemoryModel, MemoryState},
+    serde::default_on_invalid,
+};
+
+/// Scheduling properties stored as a JSON object in the card's `data` column.
+/// Keys written by other code are preserved.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct CardData {
+    #[serde(
+        rename = "s",
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "default_on_invalid"
+    )]
+    stability: Option<f32>,
+    #[serde(
+        rename = "d",
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "default_on_invalid"
+    )]
+    difficulty: Option<f32>,
+    /// Set while a leech is being relearnt with the leech relearning steps.
+    #[serde(
+        rename = "lr",
+        default,
+        skip_serializing_if = "is_false",
+        deserialize_with = "default_on_invalid"
+    )]
+    leech_relearning: bool,
+    #[serde(flatten)]
+    other: HashMap<String, Value>,
+}
+
+impl Card {
+    pub(crate) fn memory_state(&self) -> Option<MemoryState> {
+        self.parsed_data()?.memory_state()
+    }
+
+    /// Store the state rounded, so that it doesn't take up more space than
+    /// necessary. Data that is not a JSON object is left untouched.
+    pub(crate) fn set_memory_state(&mut self, state: Option<MemoryState>) {
+        self.update_data(|data| {
+            data.stability = state.map(|s| round_to_places(s.stability, 3));
+            data.difficulty = state.map(|s| round_to_places(s.difficulty, 3));
+        });
+    }
+
+    pub(crate) fn leech_relearning(&self) -> bool {
+        self.parsed_data()
+            .map(|data| data.leech_relearning)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn set_leech_relearning(&mut self, relearning: bool) {
+        self.update_data(|data| data.leech_relearning = relearning);
+    }
+
+    fn parsed_data(&self) -> Option<CardData> {
+        parse_card_data(&self.data)
+    }
+
+    fn update_data(&mut self, update: impl FnOnce(&mut CardData)) {
+        if let Some(mut data) = self.parsed_data() {
+            update(&mut data);
+            self.data = if data == CardData::default() {
+                String::new()
+            } else {
+                serde_json::to_string(&data).unwrap_or_default()
+            };
+        }
+    }
+}
+
+impl CardData {
+    fn memory_state(&self) -> Option<MemoryState> {
+        Some(MemoryState {
+            stability: self.stability?,
+            difficulty: self.difficulty?,
+        })
+    }
+}
+
+fn parse_card_data(data: &str) -> Option<CardData> {
+    if data.trim().is_empty() {
+        Some(CardData::default())
+    } else {
+        serde_json::from_str(data).ok()
+    }
+}
+
+/// The probability of recalling a review card `elapsed_days` after its last
+/// review, using the memory state built from its review history if it has one.
+/// The revlog isn't read, so for other cards the interval and ease factor
+/// stand in for the history.
+pub(crate) fn predicted_recall(
+    data: &str,
+    interval: u32,
+    ease_factor: f32,
+    elapsed_days: f32,
+) -> f32 {
+    let state = parse_card_data(data)
+        .and_then(|data| data.memory_state())
+        .unwrap_or_else(|| MemoryState::from_sm2(interval, ease_factor));
+    MemoryModel::retrievability(state, elapsed_days)
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+fn round_to_places(value: f32, decimal_places: i32) -> f32 {
+    let factor = 10f32.powi(decimal_places);
+    (value * factor).round() / factor
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn memory_state() {
+        let mut card = Card::default();
+        assert_eq!(card.memory_state(), None);
+        let state = MemoryState {
+            stability: 2.5,
+            difficulty: 4.1234,
+        };
+        card.data = r#"{"foo":1}"#.into();
+        card.set_memory_state(Some(state));
+        assert_eq!(
+            card.memory_state(),
+            Some(MemoryState {
+                stability: 2.5,
+                difficulty: 4.123,
+            })
+        );
+        card.set_memory_state(None);
+        assert_eq!(card.data, r#"{"foo":1}"#);
+
+        card.data = "not json".into();
+        card.set_memory_state(Some(state));
+        assert_eq!(card.data, "not json");
+        assert_eq!(card.memory_state(), None);
+    }
+
+    #[test]
+    fn leech_relearning() {
+        let mut card = Card::default();
+        assert!(!card.leech_relearning());
+        card.set_leech_relearning(true);
+        assert!(card.leech_relearning());
+        assert_eq!(card.data, r#"{"lr":true}"#);
+        card.set_leech_relearning(false);
+        assert_eq!(card.data, "");
+    }
+}
@@ -1,8 +1,11 @@
 // Copyright: Ankitects Pty Ltd and contributors
 // License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
 
+mod data;
 pub(crate) mod undo;
 
+pub(crate) use data::predicted_recall;
+
 use std::collections::HashSet;
 
 use num_enum::TryFromPrimitive;
@@ -118,7 +121,7 @@ impl Card {
     }
 
     /// True if flag changed.
-    fn set_flag(&mut self, flag: u8) -> bool {
+    pub(crate) fn set_flag(&mut self, flag: u8) -> bool {
         // The first 3 bits represent one of the 7 supported flags, the rest of
         // the flag byte is preserved.
         let updated_flags = (self.flags & !0b111) | flag;
@@ -130,6 +133,39 @@ impl Card {
         }
     }
 
+    pub fn id(&self) -> CardId {
+        self.id
+    }
+
+    pub fn note_id(&self) -> NoteId {
+        self.note_id
+    }
+
+    pub fn deck_id(&self) -> DeckId {
+        self.deck_id
+    }
+
+    pub fn ctype(&self) -> CardType {
+        self.ctype
+    }
+
+    pub fn queue(&self) -> CardQueue {
+        self.queue
+    }
+
+    /// In days, for review cards.
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    pub fn reps(&self) -> u32 {
+        self.reps
+    }
+
+    pub fn lapses(&self) -> u32 {
+        self.lapses
+    }
+
     /// Return the total number of steps left to do, ignoring the
     /// "steps today" number packed into the DB representation.
     pub fn remaining_steps(&self) -> u32 {
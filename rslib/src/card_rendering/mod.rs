@@ -30,6 +30,18 @@ pub fn prettify_av_tags<S: Into<String> + AsRef<str>>(txt: S) -> String {
         .unwrap_or_else(|| txt.into())
 }
 
+/// The filenames in the sound tags of `txt`, which may be audio or video.
+pub(crate) fn sound_tag_filenames(txt: &str) -> Vec<&str> {
+    CardNodes::parse(txt)
+        .0
+        .into_iter()
+        .filter_map(|node| match node {
+            Node::SoundOrVideo(fname) => Some(fname),
+            _ => None,
+        })
+        .collect()
+}
+
 /// Parse `txt` into [CardNodes] and return the result,
 /// or [None] if it is only a text node.
 fn nodes_or_text_only(txt: &str) -> Option<CardNodes> {
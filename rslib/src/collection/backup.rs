@@ -0,0 +1,368 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use chrono::prelude::*;
+use slog::error;
+use tempfile::NamedTempFile;
+
+use crate::{
+    backend_proto::preferences::BackupLimits, config::SchedulerVersion,
+    import_export::package::write_colpkg_without_media, prelude::*, storage::SqliteStorage,
+};
+
+const BACKUP_FORMAT_STRING: &str = "backup-%Y-%m-%d-%H.%M.%S.colpkg";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub datetime: DateTime<Local>,
+}
+
+/// A backup being written on a background thread.
+#[derive(Debug)]
+pub(crate) struct BackupTask {
+    handle: JoinHandle<Result<()>>,
+    finished: Arc<AtomicBool>,
+}
+
+impl Collection {
+    /// Back up the collection into `backup_folder`, unless `force` is false
+    /// and the newest backup in the folder is younger than the configured
+    /// minimum interval. Returns true if a backup was started.
+    ///
+    /// A copy of the database is taken before returning. Packaging it and
+    /// pruning old backups happens on a background thread; use
+    /// [Collection::await_backup_completion()] to wait for it. Only one backup
+    /// is written at a time, so this first waits for any previous backup.
+    /// Must not be called while a transaction is open.
+    pub fn maybe_backup(&mut self, backup_folder: impl Into<PathBuf>, force: bool) -> Result<bool> {
+        self.await_backup_completion()?;
+        let backup_folder = backup_folder.into();
+        let limits = self.get_backup_limits();
+        let now = Local::now();
+        if !force {
+            if let Some(wait) = time_until_backup_due(&backup_folder, &limits, now)? {
+                self.state.next_backup_due = Some(instant_after(wait));
+                return Ok(false);
+            }
+        }
+
+        fs::create_dir_all(&backup_folder)?;
+        let snapshot = NamedTempFile::new_in(&backup_folder)?;
+        self.storage.copy_into(snapshot.path())?;
+        let scheduler = self.scheduler_version();
+        let tr = self.tr.clone();
+        self.state.next_backup_due = Some(instant_after(minimum_interval(&limits)));
+
+        let finished = Arc::new(AtomicBool::new(false));
+        let finished_in_thread = finished.clone();
+        let handle = thread::spawn(move || {
+            let result = write_backup(snapshot, &backup_folder, now, scheduler, &tr)
+                .and_then(|_| thin_backups(&backup_folder, &limits, now.naive_local().date()));
+            finished_in_thread.store(true, Ordering::Release);
+            result
+        });
+        self.state.backup_task = Some(BackupTask { handle, finished });
+        Ok(true)
+    }
+
+    /// Wait for the running backup, if any, returning its result.
+    pub fn await_backup_completion(&mut self) -> Result<()> {
+        match self.take_backup_task() {
+            Some(task) => task
+                .join()
+                .map_err(|_| AnkiError::IoError("backup thread panicked".into()))?,
+            None => Ok(()),
+        }
+    }
+
+    /// The running backup, if any, so that it can outlive the collection.
+    pub(crate) fn take_backup_task(&mut self) -> Option<JoinHandle<Result<()>>> {
+        self.state.backup_task.take().map(|task| task.handle)
+    }
+
+    fn backup_in_progress(&self) -> bool {
+        self.state
+            .backup_task
+            .as_ref()
+            .map_or(false, |task| !task.finished.load(Ordering::Acquire))
+    }
+
+    /// Called after changes have been committed. If the collection was opened
+    /// with a backup folder, a backup is made once the minimum interval has
+    /// passed since the last one, so long sessions are backed up too. Until
+    /// then, this only compares against the cached due time, and it never
+    /// waits for a running backup. Failures are logged, as they shouldn't undo
+    /// the change that triggered them.
+    pub(crate) fn maybe_backup_periodically(&mut self) {
+        // the database can't be copied while a legacy transaction is open
+        if !self.storage.db.is_autocommit() {
+            return;
+        }
+        let backup_folder = match self.backup_folder.clone() {
+            Some(folder) => folder,
+            None => return,
+        };
+        if matches!(self.state.next_backup_due, Some(due) if Instant::now() < due)
+            || self.backup_in_progress()
+        {
+            return;
+        }
+        if let Err(err) = self.maybe_backup(backup_folder, false) {
+            error!(self.log, "periodic backup failed: {:?}", err);
+            // don't retry on every change
+            let limits = self.get_backup_limits();
+            self.state.next_backup_due = Some(instant_after(minimum_interval(&limits)));
+        }
+    }
+}
+
+/// Backups in the folder, newest first.
+pub fn list_backups(backup_folder: &Path) -> Result<Vec<Backup>> {
+    if !backup_folder.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut backups = vec![];
+    for entry in fs::read_dir(backup_folder)? {
+        let entry = entry?;
+        if let Some(datetime) = entry.file_name().to_str().and_then(parse_backup_name) {
+            backups.push(Backup {
+                path: entry.path(),
+                datetime,
+            });
+        }
+    }
+    backups.sort_unstable_by(|a, b| b.datetime.cmp(&a.datetime));
+    Ok(backups)
+}
+
+fn parse_backup_name(name: &str) -> Option<DateTime<Local>> {
+    NaiveDateTime::parse_from_str(name, BACKUP_FORMAT_STRING)
+        .ok()
+        .and_then(|datetime| Local.from_local_datetime(&datetime).latest())
+}
+
+/// How long until the next backup is due, or [None] if it is due now.
+fn time_until_backup_due(
+    backup_folder: &Path,
+    limits: &BackupLimits,
+    now: DateTime<Local>,
+) -> Result<Option<chrono::Duration>> {
+    Ok(list_backups(backup_folder)?.first().and_then(|newest| {
+        let elapsed = now.signed_duration_since(newest.datetime);
+        // a backup from the future means the clock was changed
+        if elapsed < chrono::Duration::zero() {
+            return None;
+        }
+        let remaining = minimum_interval(limits) - elapsed;
+        (remaining > chrono::Duration::zero()).then(|| remaining)
+    }))
+}
+
+fn minimum_interval(limits: &BackupLimits) -> chrono::Duration {
+    chrono::Duration::minutes(limits.minimum_interval_mins as i64)
+}
+
+fn instant_after(duration: chrono::Duration) -> Instant {
+    Instant::now() + duration.to_std().unwrap_or_default()
+}
+
+/// Convert the copied database into the format used by .colpkg exports, so
+/// that the backup can be restored by older clients as well, and package it.
+fn write_backup(
+    snapshot: NamedTempFile,
+    backup_folder: &Path,
+    now: DateTime<Local>,
+    scheduler: SchedulerVersion,
+    tr: &I18n,
+) -> Result<()> {
+    SqliteStorage::open_or_create(snapshot.path(), tr, false)?.close(true)?;
+    let out_path = backup_folder.join(now.format(BACKUP_FORMAT_STRING).to_string());
+    write_colpkg_without_media(snapshot.path(), scheduler, &out_path)
+}
+
+fn thin_backups(backup_folder: &Path, limits: &BackupLimits, today: NaiveDate) -> Result<()> {
+    for backup in obsolete_backups(list_backups(backup_folder)?, limits, today) {
+        fs::remove_file(backup.path)?;
+    }
+    Ok(())
+}
+
+/// All backups made today are kept. Of the older ones, the newest backup of
+/// each of the last `daily` days is kept, followed by the newest backup of
+/// each of the `weekly` weeks and `monthly` months before that. Days, weeks
+/// and months without backups are skipped, so gaps in usage don't cause
+/// older backups to be removed early.
+fn obsolete_backups(backups: Vec<Backup>, limits: &BackupLimits, today: NaiveDate) -> Vec<Backup> {
+    let mut daily = limits.daily;
+    let mut weekly = limits.weekly;
+    let mut monthly = limits.monthly;
+    let mut last_kept: Option<NaiveDate> = None;
+    let mut obsolete = vec![];
+
+    for backup in backups {
+        let date = backup.datetime.naive_local().date();
+        let new_day = last_kept.map_or(true, |kept| kept != date);
+        let new_week = last_kept.map_or(true, |kept| kept.iso_week() != date.iso_week());
+        let new_month = last_kept.map_or(true, |kept| {
+            (kept.year(), kept.month()) != (date.year(), date.month())
+        });
+
+        let keep = if date == today {
+            true
+        } else if new_day && daily > 0 {
+            daily -= 1;
+            true
+        } else if new_week && weekly > 0 {
+            weekly -= 1;
+            true
+        } else if new_month && monthly > 0 {
+            monthly -= 1;
+            true
+        } else {
+            false
+        };
+
+        if keep {
+            last_kept = Some(date);
+        } else {
+            obsolete.push(backup);
+        }
+    }
+
+    obsolete
+}
+
+#[cfg(test)]
+mod test {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::{collection::CollectionBuilder, import_export::package::import_colpkg};
+
+    fn backup_on(year: i32, month: u32, day: u32, hour: u32) -> Backup {
+        let datetime = Local.ymd(year, month, day).and_hms(hour, 0, 0);
+        Backup {
+            path: PathBuf::from(datetime.format(BACKUP_FORMAT_STRING).to_string()),
+            datetime,
+        }
+    }
+
+    #[test]
+    fn thinning() {
+        let limits = BackupLimits {
+            daily: 2,
+            weekly: 1,
+            monthly: 1,
+            minimum_interval_mins: 0,
+        };
+        let today = NaiveDate::from_ymd(2022, 3, 17);
+        // newest first; the 17th is a Thursday
+        let backups = vec![
+            backup_on(2022, 3, 17, 12),
+            backup_on(2022, 3, 17, 10),
+            backup_on(2022, 3, 16, 12),
+            backup_on(2022, 3, 16, 10),
+            backup_on(2022, 3, 14, 10),
+            backup_on(2022, 3, 13, 10),
+            backup_on(2022, 3, 8, 10),
+            backup_on(2022, 2, 20, 10),
+            backup_on(2022, 1, 20, 10),
+        ];
+        let obsolete = obsolete_backups(backups.clone(), &limits, today);
+        assert_eq!(
+            obsolete,
+            vec![
+                // second backup of a day
+                backups[3].clone(),
+                // same week as the weekly backup
+                backups[6].clone(),
+                // limits exhausted
+                backups[8].clone(),
+            ]
+        );
+    }
+
+    #[test]
+    fn backup_and_restore() -> Result<()> {
+        let dir = tempdir()?;
+        let backup_folder = dir.path().join("backups");
+        let col_path = dir.path().join("col.anki2");
+        let mut col = CollectionBuilder::new(&col_path).build()?;
+        let nt = col.get_notetype_by_name("Basic")?.unwrap();
+        let mut note = nt.new_note();
+        note.set_field(0, "front")?;
+        col.add_note(&mut note, DeckId(1))?;
+
+        assert!(col.maybe_backup(&backup_folder, false)?);
+        col.await_backup_completion()?;
+        // a recent backup exists
+        assert!(!col.maybe_backup(&backup_folder, false)?);
+        let backups = list_backups(&backup_folder)?;
+        assert_eq!(backups.len(), 1);
+
+        // the collection is still usable after the backup
+        col.remove_notes(&[note.id])?;
+        col.close(false)?;
+
+        import_colpkg(
+            backups[0].path.to_str().unwrap(),
+            col_path.to_str().unwrap(),
+            dir.path().join("media").to_str().unwrap(),
+            dir.path().join("media.db").to_str().unwrap(),
+            |_| true,
+        )?;
+        let col = CollectionBuilder::new(&col_path).build()?;
+        assert_eq!(col.storage.get_note(note.id)?.unwrap().fields()[0], "front");
+
+        Ok(())
+    }
+
+    #[test]
+    fn periodic_backups() -> Result<()> {
+        let dir = tempdir()?;
+        let backup_folder = dir.path().join("backups");
+        let mut col = CollectionBuilder::new(dir.path().join("col.anki2"))
+            .set_backup_folder(&backup_folder)
+            .build()?;
+        let nt = col.get_notetype_by_name("Basic")?.unwrap();
+        let add_note = |col: &mut Collection| -> Result<()> {
+            let mut note = nt.new_note();
+            note.set_field(0, "front")?;
+            col.add_note(&mut note, DeckId(1))?;
+            col.await_backup_completion()
+        };
+
+        // the first change is backed up, but not the following ones
+        add_note(&mut col)?;
+        add_note(&mut col)?;
+        assert_eq!(list_backups(&backup_folder)?.len(), 1);
+
+        // until the minimum interval has passed
+        let backup = list_backups(&backup_folder)?.remove(0);
+        let earlier = backup.datetime - chrono::Duration::minutes(31);
+        fs::rename(
+            &backup.path,
+            backup_folder.join(earlier.format(BACKUP_FORMAT_STRING).to_string()),
+        )?;
+        add_note(&mut col)?;
+        // the folder isn't checked again before the cached due time
+        assert_eq!(list_backups(&backup_folder)?.len(), 1);
+        col.state.next_backup_due = Some(Instant::now());
+        add_note(&mut col)?;
+        assert_eq!(list_backups(&backup_folder)?.len(), 2);
+
+        Ok(())
+    }
+}
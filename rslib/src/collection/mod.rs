@@ -1,20 +1,24 @@
 // Copyright: Ankitects Pty Ltd and contributors
 // License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
 
+pub(crate) mod backup;
 pub(crate) mod timestamps;
 mod transact;
 pub(crate) mod undo;
 
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Instant};
 
 use crate::{
     browser_table,
+    collection::backup::BackupTask,
     decks::{Deck, DeckId},
     error::Result,
     i18n::I18n,
     log::{default_logger, Logger},
     notetype::{Notetype, NotetypeId},
-    scheduler::{queue::CardQueues, SchedulerInfo},
+    scheduler::{
+        answering::LoadBalancer, policy::SchedulingPolicies, queue::CardQueues, SchedulerInfo,
+    },
     storage::SqliteStorage,
     types::Usn,
     undo::UndoManager,
@@ -25,6 +29,7 @@ pub struct CollectionBuilder {
     collection_path: Option<PathBuf>,
     media_folder: Option<PathBuf>,
     media_db: Option<PathBuf>,
+    backup_folder: Option<PathBuf>,
     server: Option<bool>,
     tr: Option<I18n>,
     log: Option<Logger>,
@@ -48,6 +53,7 @@ impl CollectionBuilder {
         let server = self.server.unwrap_or_default();
         let media_folder = self.media_folder.clone().unwrap_or_default();
         let media_db = self.media_db.clone().unwrap_or_default();
+        let backup_folder = self.backup_folder.clone();
         let log = self.log.clone().unwrap_or_else(crate::log::terminal);
 
         let storage = SqliteStorage::open_or_create(&col_path, &tr, server)?;
@@ -56,6 +62,7 @@ impl CollectionBuilder {
             col_path,
             media_folder,
             media_db,
+            backup_folder,
             tr,
             log,
             server,
@@ -76,6 +83,13 @@ impl CollectionBuilder {
         self
     }
 
+    /// While the collection is open, back it up into this folder whenever
+    /// the configured minimum interval has passed.
+    pub fn set_backup_folder<P: Into<PathBuf>>(&mut self, backup_folder: P) -> &mut Self {
+        self.backup_folder = Some(backup_folder.into());
+        self
+    }
+
     pub fn set_server(&mut self, server: bool) -> &mut Self {
         self.server = Some(server);
         self
@@ -111,7 +125,12 @@ pub struct CollectionState {
     pub(crate) deck_cache: HashMap<DeckId, Arc<Deck>>,
     pub(crate) scheduler_info: Option<SchedulerInfo>,
     pub(crate) card_queues: Option<CardQueues>,
+    pub(crate) load_balancer: Option<LoadBalancer>,
     pub(crate) active_browser_columns: Option<Arc<Vec<browser_table::Column>>>,
+    pub(crate) scheduling_policies: SchedulingPolicies,
+    pub(crate) backup_task: Option<BackupTask>,
+    /// When the folder should next be checked for a periodic backup.
+    pub(crate) next_backup_due: Option<Instant>,
     /// True if legacy Python code has executed SQL that has modified the
     /// database, requiring modification time to be bumped.
     pub(crate) modified_by_dbproxy: bool,
@@ -123,6 +142,7 @@ pub struct Collection {
     pub(crate) col_path: PathBuf,
     pub(crate) media_folder: PathBuf,
     pub(crate) media_db: PathBuf,
+    pub(crate) backup_folder: Option<PathBuf>,
     pub(crate) tr: I18n,
     pub(crate) log: Logger,
     pub(crate) server: bool,
@@ -137,6 +157,9 @@ impl Collection {
             .set_server(self.server)
             .set_tr(self.tr.clone())
             .set_logger(self.log.clone());
+        if let Some(backup_folder) = &self.backup_folder {
+            builder.set_backup_folder(backup_folder.clone());
+        }
         builder
     }
 
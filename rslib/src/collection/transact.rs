@@ -38,6 +38,7 @@ impl Collection {
                     }
                 };
                 self.end_undoable_operation(skip_undo_queue);
+                self.maybe_backup_periodically();
                 Ok(OpOutput { output, changes })
             })
             // roll back on error
@@ -14,7 +14,7 @@ use slog::warn;
 use strum::IntoStaticStr;
 
 pub use self::{bool::BoolKey, notetype::get_aux_notetype_config_key, string::StringKey};
-use crate::prelude::*;
+use crate::{backend_proto::preferences::BackupLimits, prelude::*};
 
 /// Only used when updating/undoing.
 #[derive(Debug)]
@@ -39,6 +39,7 @@ impl ConfigEntry {
 #[derive(IntoStaticStr)]
 #[strum(serialize_all = "camelCase")]
 pub(crate) enum ConfigKey {
+    Backups,
     CreationOffset,
     FirstDayOfWeek,
     LocalOffset,
@@ -253,6 +254,20 @@ impl Collection {
             .map(|_| ())
     }
 
+    pub(crate) fn get_backup_limits(&self) -> BackupLimits {
+        self.get_config_optional(ConfigKey::Backups)
+            .unwrap_or(BackupLimits {
+                daily: 12,
+                weekly: 10,
+                monthly: 9,
+                minimum_interval_mins: 30,
+            })
+    }
+
+    pub(crate) fn set_backup_limits(&mut self, limits: BackupLimits) -> Result<()> {
+        self.set_config(ConfigKey::Backups, &limits).map(|_| ())
+    }
+
     pub(crate) fn get_last_unburied_day(&self) -> u32 {
         self.get_config_optional(ConfigKey::LastUnburiedDay)
             .unwrap_or_default()
@@ -277,15 +292,43 @@ impl Default for NewReviewMix {
     }
 }
 
-#[derive(PartialEq, Serialize_repr, Deserialize_repr, Clone, Copy)]
+#[derive(Debug, PartialEq, Serialize_repr, Deserialize_repr, Clone, Copy)]
 #[repr(u8)]
 pub(crate) enum Weekday {
     Sunday = 0,
     Monday = 1,
+    Tuesday = 2,
+    Wednesday = 3,
+    Thursday = 4,
     Friday = 5,
     Saturday = 6,
 }
 
+impl Weekday {
+    /// The weekday `days` days later.
+    pub(crate) fn adding_days(self, days: u32) -> Self {
+        Self::from_days_from_sunday((self as u32 + days % 7) % 7)
+    }
+
+    fn from_days_from_sunday(days: u32) -> Self {
+        match days {
+            0 => Weekday::Sunday,
+            1 => Weekday::Monday,
+            2 => Weekday::Tuesday,
+            3 => Weekday::Wednesday,
+            4 => Weekday::Thursday,
+            5 => Weekday::Friday,
+            _ => Weekday::Saturday,
+        }
+    }
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(weekday: chrono::Weekday) -> Self {
+        Self::from_days_from_sunday(weekday.num_days_from_sunday())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{collection::open_test_collection, decks::DeckId};
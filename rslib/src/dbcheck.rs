@@ -124,6 +124,11 @@ impl Collection {
         debug!(self.log, "check notetypes");
         self.check_notetypes(&mut out, &mut progress_fn)?;
 
+        if self.storage.full_text_index_exists()? {
+            debug!(self.log, "rebuild full text index");
+            self.storage.rebuild_full_text_index()?;
+        }
+
         progress_fn(DatabaseCheckProgress::History, false);
 
         debug!(self.log, "check review log");
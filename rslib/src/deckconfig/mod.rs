@@ -10,8 +10,8 @@ pub use update::UpdateDeckConfigsRequest;
 
 pub use crate::backend_proto::deck_config::{
     config::{
-        LeechAction, NewCardGatherPriority, NewCardInsertOrder, NewCardSortOrder, ReviewCardOrder,
-        ReviewMix,
+        EasyDay, LeechAction, NewCardGatherPriority, NewCardInsertOrder, NewCardSortOrder,
+        ReviewCardOrder, ReviewMix, SchedulingModel,
     },
     Config as DeckConfigInner,
 };
@@ -23,7 +23,10 @@ use crate::{
     collection::Collection,
     define_newtype,
     error::{AnkiError, Result},
-    scheduler::states::review::INITIAL_EASE_FACTOR,
+    scheduler::states::{
+        memory::DEFAULT_DESIRED_RETENTION, review::INITIAL_EASE_FACTOR, steps::LearningSteps,
+        MemoryModel, StateContext,
+    },
     timestamp::{TimestampMillis, TimestampSecs},
     types::Usn,
 };
@@ -75,6 +78,17 @@ impl Default for DeckConfig {
                 skip_question_when_replaying_answer: false,
                 bury_new: false,
                 bury_reviews: false,
+                scheduling_model: SchedulingModel::Sm2 as i32,
+                desired_retention: DEFAULT_DESIRED_RETENTION,
+                memory_weights: vec![],
+                easy_days: vec![],
+                sibling_spacing_days: 0,
+                new_sibling_minimum_interval: 0,
+                leech_deck: String::new(),
+                leech_flag: 0,
+                leech_relearn_steps: vec![],
+                leech_window_reviews: 0,
+                leech_window_lapses: 0,
                 other: vec![],
             },
         }
@@ -86,6 +100,42 @@ impl DeckConfig {
         self.mtime_secs = TimestampSecs::now();
         self.usn = usn;
     }
+
+    /// The memory model, if the preset uses it instead of SM-2.
+    pub(crate) fn memory_model(&self) -> Option<MemoryModel> {
+        match self.inner.scheduling_model() {
+            SchedulingModel::Sm2 => None,
+            SchedulingModel::Memory => Some(MemoryModel::new(
+                &self.inner.memory_weights,
+                self.inner.desired_retention,
+            )),
+        }
+    }
+
+    /// The context for state transitions of a card in a normal deck, without
+    /// fuzz, load balancing or easy days.
+    pub(crate) fn state_context(&self) -> StateContext<'_> {
+        StateContext {
+            fuzz_factor: None,
+            load_balancer: None,
+            easy_days: None,
+            steps: LearningSteps::new(&self.inner.learn_steps),
+            graduating_interval_good: self.inner.graduating_interval_good,
+            graduating_interval_easy: self.inner.graduating_interval_easy,
+            initial_ease_factor: self.inner.initial_ease,
+            hard_multiplier: self.inner.hard_multiplier,
+            easy_multiplier: self.inner.easy_multiplier,
+            interval_multiplier: self.inner.interval_multiplier,
+            maximum_review_interval: self.inner.maximum_review_interval,
+            leech_threshold: self.inner.leech_threshold,
+            memory_model: self.memory_model(),
+            relearn_steps: LearningSteps::new(&self.inner.relearn_steps),
+            lapse_multiplier: self.inner.lapse_multiplier,
+            minimum_lapse_interval: self.inner.minimum_lapse_interval,
+            in_filtered_deck: false,
+            preview_step: 0,
+        }
+    }
 }
 
 impl Collection {
@@ -54,6 +54,32 @@ pub struct DeckConfSchema11 {
     new_sort_order: i32,
     #[serde(default)]
     new_gather_priority: i32,
+    #[serde(default)]
+    scheduling_model: i32,
+    #[serde(default)]
+    desired_retention: f32,
+    #[serde(default)]
+    memory_weights: Vec<f32>,
+    #[serde(default)]
+    easy_days: Vec<i32>,
+    #[serde(default)]
+    sibling_spacing_days: u32,
+    #[serde(default)]
+    new_sibling_minimum_interval: u32,
+    /// Leech actions older clients don't know about; they see them as
+    /// TagOnly.
+    #[serde(default)]
+    extended_leech_action: i32,
+    #[serde(default)]
+    leech_deck: String,
+    #[serde(default)]
+    leech_flag: u32,
+    #[serde(default)]
+    leech_relearn_steps: Vec<f32>,
+    #[serde(default)]
+    leech_window_reviews: u32,
+    #[serde(default)]
+    leech_window_lapses: u32,
 
     #[serde(flatten)]
     other: HashMap<String, Value>,
@@ -243,6 +269,18 @@ impl Default for DeckConfSchema11 {
             review_order: 0,
             new_sort_order: 0,
             new_gather_priority: 0,
+            scheduling_model: 0,
+            desired_retention: 0.0,
+            memory_weights: vec![],
+            easy_days: vec![],
+            sibling_spacing_days: 0,
+            new_sibling_minimum_interval: 0,
+            extended_leech_action: 0,
+            leech_deck: String::new(),
+            leech_flag: 0,
+            leech_relearn_steps: vec![],
+            leech_window_reviews: 0,
+            leech_window_lapses: 0,
         }
     }
 }
@@ -302,7 +340,13 @@ impl From<DeckConfSchema11> for DeckConfig {
                 review_order: c.review_order,
                 new_mix: c.new_mix,
                 interday_learning_mix: c.interday_learning_mix,
-                leech_action: c.lapse.leech_action as i32,
+                leech_action: if c.lapse.leech_action == LeechAction::TagOnly
+                    && c.extended_leech_action > LeechAction::TagOnly as i32
+                {
+                    c.extended_leech_action
+                } else {
+                    c.lapse.leech_action as i32
+                },
                 leech_threshold: c.lapse.leech_fails,
                 disable_autoplay: !c.autoplay,
                 cap_answer_time_to_secs: c.max_taken.max(0) as u32,
@@ -310,6 +354,17 @@ impl From<DeckConfSchema11> for DeckConfig {
                 skip_question_when_replaying_answer: !c.replayq,
                 bury_new: c.new.bury,
                 bury_reviews: c.rev.bury,
+                scheduling_model: c.scheduling_model,
+                desired_retention: c.desired_retention,
+                memory_weights: c.memory_weights,
+                easy_days: c.easy_days,
+                sibling_spacing_days: c.sibling_spacing_days,
+                new_sibling_minimum_interval: c.new_sibling_minimum_interval,
+                leech_deck: c.leech_deck,
+                leech_flag: c.leech_flag,
+                leech_relearn_steps: c.leech_relearn_steps,
+                leech_window_reviews: c.leech_window_reviews,
+                leech_window_lapses: c.leech_window_lapses,
                 other: other_bytes,
             },
         }
@@ -381,9 +436,10 @@ impl From<DeckConfig> for DeckConfSchema11 {
             },
             lapse: LapseConfSchema11 {
                 delays: i.relearn_steps,
+                // all other actions tag the card as well
                 leech_action: match i.leech_action {
-                    1 => LeechAction::TagOnly,
-                    _ => LeechAction::Suspend,
+                    0 => LeechAction::Suspend,
+                    _ => LeechAction::TagOnly,
                 },
                 leech_fails: i.leech_threshold,
                 min_int: i.minimum_lapse_interval,
@@ -397,6 +453,22 @@ impl From<DeckConfig> for DeckConfSchema11 {
             review_order: i.review_order,
             new_sort_order: i.new_card_sort_order,
             new_gather_priority: i.new_card_gather_priority,
+            scheduling_model: i.scheduling_model,
+            desired_retention: i.desired_retention,
+            memory_weights: i.memory_weights,
+            easy_days: i.easy_days,
+            sibling_spacing_days: i.sibling_spacing_days,
+            new_sibling_minimum_interval: i.new_sibling_minimum_interval,
+            extended_leech_action: if i.leech_action > LeechAction::TagOnly as i32 {
+                i.leech_action
+            } else {
+                0
+            },
+            leech_deck: i.leech_deck,
+            leech_flag: i.leech_flag,
+            leech_relearn_steps: i.leech_relearn_steps,
+            leech_window_reviews: i.leech_window_reviews,
+            leech_window_lapses: i.leech_window_lapses,
         }
     }
 }
@@ -416,6 +488,18 @@ fn clear_other_duplicates(top_other: &mut HashMap<String, Value>) {
         "reviewOrder",
         "newSortOrder",
         "newGatherPriority",
+        "schedulingModel",
+        "desiredRetention",
+        "memoryWeights",
+        "easyDays",
+        "siblingSpacingDays",
+        "newSiblingMinimumInterval",
+        "extendedLeechAction",
+        "leechDeck",
+        "leechFlag",
+        "leechRelearnSteps",
+        "leechWindowReviews",
+        "leechWindowLapses",
     ] {
         top_other.remove(*key);
     }
@@ -467,4 +551,38 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn leech_options_roundtrip() {
+        // a preset saved by an older client has the same defaults as a new one
+        let upgraded = DeckConfig::from(DeckConfSchema11::default());
+        let default = DeckConfig::default();
+        assert_eq!(upgraded.inner.leech_deck, default.inner.leech_deck);
+        assert_eq!(upgraded.inner.leech_flag, default.inner.leech_flag);
+        assert_eq!(
+            upgraded.inner.leech_relearn_steps,
+            default.inner.leech_relearn_steps
+        );
+        assert_eq!(
+            upgraded.inner.leech_window_reviews,
+            default.inner.leech_window_reviews
+        );
+        assert_eq!(
+            upgraded.inner.leech_window_lapses,
+            default.inner.leech_window_lapses
+        );
+
+        let mut config = DeckConfig::default();
+        config.inner.leech_action = crate::deckconfig::LeechAction::Relearn as i32;
+        config.inner.leech_deck = "Hard Cards".into();
+        config.inner.leech_flag = 4;
+        config.inner.leech_relearn_steps = vec![1.0, 10.0];
+        config.inner.leech_window_reviews = 10;
+        config.inner.leech_window_lapses = 3;
+        let json = serde_json::to_value(DeckConfSchema11::from(config.clone())).unwrap();
+        // older clients see an action they know
+        assert_eq!(json["lapse"]["leechAction"], json!(1));
+        let schema11: DeckConfSchema11 = serde_json::from_value(json).unwrap();
+        assert_eq!(DeckConfig::from(schema11), config);
+    }
 }
@@ -35,7 +35,7 @@ impl Collection {
         Ok(())
     }
 
-    pub(super) fn add_deck_config_undoable(
+    pub(crate) fn add_deck_config_undoable(
         &mut self,
         config: &mut DeckConfig,
     ) -> Result<(), AnkiError> {
@@ -23,6 +23,9 @@ pub struct UpdateDeckConfigsRequest {
     pub removed_config_ids: Vec<DeckConfigId>,
     pub apply_to_children: bool,
     pub card_state_customizer: String,
+    /// Replay the review history of review cards whose scheduling options
+    /// changed.
+    pub reschedule_cards: bool,
 }
 
 impl Collection {
@@ -178,6 +181,20 @@ impl Collection {
                 if previous_order != current_order {
                     self.sort_deck(deck_id, current_order, usn)?;
                 }
+
+                // if scheduling options differ, review cards may need new intervals
+                if input.reschedule_cards {
+                    if let Some(current_config) = configs_after_update.get(&current_config_id) {
+                        let changed = configs_before_update
+                            .get(&previous_config_id)
+                            .map_or(true, |previous| {
+                                scheduling_changed(previous, current_config)
+                            });
+                        if changed {
+                            self.reschedule_cards_in_deck(deck_id, current_config, usn)?;
+                        }
+                    }
+                }
             }
         }
 
@@ -187,6 +204,25 @@ impl Collection {
     }
 }
 
+/// True if review cards may be given different intervals.
+fn scheduling_changed(before: &DeckConfig, after: &DeckConfig) -> bool {
+    let (before, after) = (&before.inner, &after.inner);
+    before.learn_steps != after.learn_steps
+        || before.relearn_steps != after.relearn_steps
+        || before.graduating_interval_good != after.graduating_interval_good
+        || before.graduating_interval_easy != after.graduating_interval_easy
+        || before.initial_ease != after.initial_ease
+        || before.easy_multiplier != after.easy_multiplier
+        || before.hard_multiplier != after.hard_multiplier
+        || before.lapse_multiplier != after.lapse_multiplier
+        || before.interval_multiplier != after.interval_multiplier
+        || before.maximum_review_interval != after.maximum_review_interval
+        || before.minimum_lapse_interval != after.minimum_lapse_interval
+        || before.scheduling_model != after.scheduling_model
+        || before.desired_retention != after.desired_retention
+        || before.memory_weights != after.memory_weights
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -237,6 +273,7 @@ mod test {
             removed_config_ids: vec![],
             apply_to_children: false,
             card_state_customizer: "".to_string(),
+            reschedule_cards: false,
         };
         assert!(!col.update_deck_configs(input.clone())?.changes.had_change());
 
@@ -284,4 +321,65 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn rescheduling() -> Result<()> {
+        let mut col = open_test_collection();
+        if col.timing_today()?.near_cutoff() {
+            return Ok(());
+        }
+        let nt = col.get_notetype_by_name("Basic")?.unwrap();
+        let mut note = nt.new_note();
+        col.add_note(&mut note, DeckId(1))?;
+        let card_id = col.answer_easy().card_id;
+        let today = col.timing_today()?.days_elapsed;
+        let revlog_count = |col: &mut Collection| {
+            col.storage
+                .get_revlog_entries_for_card(card_id)
+                .unwrap()
+                .len()
+        };
+        assert_eq!(revlog_count(&mut col), 1);
+
+        let mut config = col.get_deck_config(DeckConfigId(1), false)?.unwrap();
+        config.inner.graduating_interval_easy = 6;
+        let mut input = UpdateDeckConfigsRequest {
+            target_deck_id: DeckId(1),
+            configs: vec![config],
+            removed_config_ids: vec![],
+            apply_to_children: false,
+            card_state_customizer: "".to_string(),
+            reschedule_cards: false,
+        };
+
+        // cards are only rescheduled if asked to
+        let interval = col.storage.get_card(card_id)?.unwrap().interval;
+        col.update_deck_configs(input.clone())?;
+        assert_eq!(col.storage.get_card(card_id)?.unwrap().interval, interval);
+
+        // the card's history is replayed with the new options
+        input.configs[0].inner.graduating_interval_easy = 7;
+        input.reschedule_cards = true;
+        col.update_deck_configs(input.clone())?;
+        let card = col.storage.get_card(card_id)?.unwrap();
+        assert_eq!(card.interval, 7);
+        assert_eq!(card.due, today as i32 + 7);
+        assert_eq!(card.ease_factor, 2500);
+        assert_eq!(revlog_count(&mut col), 2);
+
+        // and it takes on the replayed ease as well
+        input.configs[0].inner.initial_ease = 2.0;
+        col.update_deck_configs(input.clone())?;
+        let card = col.storage.get_card(card_id)?.unwrap();
+        assert_eq!(card.interval, 7);
+        assert_eq!(card.ease_factor, 2000);
+        assert_eq!(revlog_count(&mut col), 3);
+
+        // unrelated changes leave it alone
+        input.configs[0].inner.new_per_day += 1;
+        col.update_deck_configs(input)?;
+        assert_eq!(revlog_count(&mut col), 3);
+
+        Ok(())
+    }
 }
@@ -3,7 +3,7 @@
 
 use std::collections::HashMap;
 
-use super::Deck;
+use super::{DayLimit, Deck, Limit, NormalDeck};
 use crate::{
     deckconfig::{DeckConfig, DeckConfigId},
     prelude::*,
@@ -24,9 +24,16 @@ impl RemainingLimits {
                     // any reviewed new cards contribute to the review limit
                     rev_today += new_today;
                 }
+                let normal = deck.normal().ok();
+                let review_limit = normal
+                    .and_then(|deck| deck.current_review_limit(today))
+                    .unwrap_or(config.inner.reviews_per_day);
+                let new_limit = normal
+                    .and_then(|deck| deck.current_new_limit(today))
+                    .unwrap_or(config.inner.new_per_day);
                 RemainingLimits {
-                    review: ((config.inner.reviews_per_day as i32) - rev_today).max(0) as u32,
-                    new: ((config.inner.new_per_day as i32) - new_today).max(0) as u32,
+                    review: ((review_limit as i32) - rev_today).max(0) as u32,
+                    new: ((new_limit as i32) - new_today).max(0) as u32,
                 }
             })
             .unwrap_or_default()
@@ -38,6 +45,37 @@ impl RemainingLimits {
     }
 }
 
+impl NormalDeck {
+    /// The deck's own review limit on `today`, if it overrides the preset's.
+    pub(crate) fn current_review_limit(&self, today: u32) -> Option<u32> {
+        limit_override(
+            self.review_limit.as_ref(),
+            self.review_limit_today.as_ref(),
+            today,
+        )
+    }
+
+    /// The deck's own new card limit on `today`, if it overrides the preset's.
+    pub(crate) fn current_new_limit(&self, today: u32) -> Option<u32> {
+        limit_override(
+            self.new_limit.as_ref(),
+            self.new_limit_today.as_ref(),
+            today,
+        )
+    }
+}
+
+fn limit_override(
+    limit: Option<&Limit>,
+    limit_today: Option<&DayLimit>,
+    today: u32,
+) -> Option<u32> {
+    limit_today
+        .filter(|limit| limit.today == today)
+        .map(|limit| limit.limit)
+        .or_else(|| limit.map(|limit| limit.limit))
+}
+
 impl Default for RemainingLimits {
     fn default() -> Self {
         RemainingLimits {
@@ -25,6 +25,7 @@ pub use crate::backend_proto::{
     deck::{
         filtered::{search_term::Order as FilteredSearchOrder, SearchTerm as FilteredSearchTerm},
         kind_container::Kind as DeckKind,
+        normal::{DayLimit, Limit},
         Common as DeckCommon, Filtered as FilteredDeck, KindContainer as DeckKindContainer,
         Normal as NormalDeck,
     },
@@ -7,7 +7,7 @@ use serde_derive::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_tuple::Serialize_tuple;
 
-use super::{DeckCommon, FilteredDeck, FilteredSearchTerm, NormalDeck};
+use super::{DayLimit, DeckCommon, FilteredDeck, FilteredSearchTerm, Limit, NormalDeck};
 use crate::{
     prelude::*,
     serde::{default_on_invalid, deserialize_bool_from_anything, deserialize_number_from_string},
@@ -116,6 +116,20 @@ pub struct NormalDeckSchema11 {
     extend_new: i32,
     #[serde(default, deserialize_with = "default_on_invalid")]
     extend_rev: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    review_limit: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    new_limit: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    review_limit_today: Option<DayLimitSchema11>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    new_limit_today: Option<DayLimitSchema11>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub struct DayLimitSchema11 {
+    limit: u32,
+    today: u32,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -226,6 +240,10 @@ impl Default for NormalDeckSchema11 {
             conf: 1,
             extend_new: 0,
             extend_rev: 0,
+            review_limit: None,
+            new_limit: None,
+            review_limit_today: None,
+            new_limit_today: None,
         }
     }
 }
@@ -298,6 +316,19 @@ impl From<NormalDeckSchema11> for NormalDeck {
             extend_review: deck.extend_rev.max(0) as u32,
             markdown_description: deck.common.markdown_description,
             description: deck.common.desc,
+            review_limit: deck.review_limit.map(|limit| Limit { limit }),
+            new_limit: deck.new_limit.map(|limit| Limit { limit }),
+            review_limit_today: deck.review_limit_today.map(Into::into),
+            new_limit_today: deck.new_limit_today.map(Into::into),
+        }
+    }
+}
+
+impl From<DayLimitSchema11> for DayLimit {
+    fn from(limit: DayLimitSchema11) -> Self {
+        DayLimit {
+            limit: limit.limit,
+            today: limit.today,
         }
     }
 }
@@ -332,6 +363,10 @@ impl From<Deck> for DeckSchema11 {
                 conf: norm.config_id,
                 extend_new: norm.extend_new as i32,
                 extend_rev: norm.extend_review as i32,
+                review_limit: norm.review_limit.as_ref().map(|limit| limit.limit),
+                new_limit: norm.new_limit.as_ref().map(|limit| limit.limit),
+                review_limit_today: norm.review_limit_today.as_ref().map(Into::into),
+                new_limit_today: norm.new_limit_today.as_ref().map(Into::into),
                 common: deck.into(),
             }),
             DeckKind::Filtered(ref filt) => DeckSchema11::Filtered(FilteredDeckSchema11 {
@@ -408,6 +443,15 @@ impl From<&Deck> for DeckTodaySchema11 {
     }
 }
 
+impl From<&DayLimit> for DayLimitSchema11 {
+    fn from(limit: &DayLimit) -> Self {
+        DayLimitSchema11 {
+            limit: limit.limit,
+            today: limit.today,
+        }
+    }
+}
+
 impl From<FilteredSearchTerm> for FilteredSearchTermSchema11 {
     fn from(term: FilteredSearchTerm) -> Self {
         FilteredSearchTermSchema11 {
@@ -393,7 +393,12 @@ impl Collection {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{collection::open_test_collection, deckconfig::DeckConfigId, error::Result};
+    use crate::{
+        collection::open_test_collection,
+        deckconfig::DeckConfigId,
+        decks::{DayLimit, Limit},
+        error::Result,
+    };
 
     #[test]
     fn wellformed() -> Result<()> {
@@ -473,6 +478,30 @@ mod test {
         assert_eq!(tree.children[0].new_count, 3);
         assert_eq!(tree.children[0].children[0].new_count, 3);
 
+        // a deck's own limit takes priority over its preset's
+        child_deck.normal_mut()?.new_limit = Some(Limit { limit: 2 });
+        col.add_or_update_deck(&mut child_deck)?;
+        let tree = col.deck_tree(Some(TimestampSecs::now()), None)?;
+        assert_eq!(tree.children[0].new_count, 1);
+        assert_eq!(tree.children[0].children[0].new_count, 1);
+
+        // and a limit for today takes priority over that
+        let today = col.timing_today()?.days_elapsed;
+        child_deck.normal_mut()?.new_limit_today = Some(DayLimit { limit: 3, today });
+        col.add_or_update_deck(&mut child_deck)?;
+        let tree = col.deck_tree(Some(TimestampSecs::now()), None)?;
+        assert_eq!(tree.children[0].new_count, 2);
+        assert_eq!(tree.children[0].children[0].new_count, 2);
+
+        // but is ignored on other days
+        child_deck.normal_mut()?.new_limit_today = Some(DayLimit {
+            limit: 3,
+            today: today + 1,
+        });
+        col.add_or_update_deck(&mut child_deck)?;
+        let tree = col.deck_tree(Some(TimestampSecs::now()), None)?;
+        assert_eq!(tree.children[0].children[0].new_count, 1);
+
         Ok(())
     }
 }
@@ -12,7 +12,7 @@ pub use db::{DbError, DbErrorKind};
 pub use filtered::FilteredDeckError;
 pub use network::{NetworkError, NetworkErrorKind, SyncError, SyncErrorKind};
 pub use search::{ParseError, SearchErrorKind};
-use tempfile::PathPersistError;
+use tempfile::{PathPersistError, PersistError};
 
 use crate::i18n::I18n;
 
@@ -41,6 +41,7 @@ pub enum AnkiError {
     UndoEmpty,
     MultipleNotetypesSelected,
     DatabaseCheckRequired,
+    ImportError(ImportError),
 }
 
 impl Display for AnkiError {
@@ -94,6 +95,7 @@ impl AnkiError {
             AnkiError::InvalidRegex(err) => format!("<pre>{}</pre>", err),
             AnkiError::MultipleNotetypesSelected => tr.errors_multiple_notetypes_selected().into(),
             AnkiError::DatabaseCheckRequired => tr.errors_please_check_database().into(),
+            AnkiError::ImportError(err) => err.localized_description(tr),
             AnkiError::IoError(_)
             | AnkiError::JsonError(_)
             | AnkiError::ProtoError(_)
@@ -122,6 +124,20 @@ pub enum TemplateError {
     NoSuchConditional(String),
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ImportError {
+    Corrupt,
+}
+
+impl ImportError {
+    fn localized_description(self, tr: &I18n) -> String {
+        match self {
+            ImportError::Corrupt => tr.importing_the_provided_file_is_not_a(),
+        }
+        .into()
+    }
+}
+
 impl From<io::Error> for AnkiError {
     fn from(err: io::Error) -> Self {
         AnkiError::IoError(format!("{:?}", err))
@@ -152,6 +168,12 @@ impl From<PathPersistError> for AnkiError {
     }
 }
 
+impl From<PersistError> for AnkiError {
+    fn from(e: PersistError) -> Self {
+        AnkiError::IoError(e.to_string())
+    }
+}
+
 impl From<regex::Error> for AnkiError {
     fn from(err: regex::Error) -> Self {
         AnkiError::InvalidRegex(err.to_string())
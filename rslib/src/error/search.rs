@@ -26,6 +26,8 @@ pub enum SearchErrorKind {
     MissingKey,
     UnknownEscape(String),
     InvalidState(String),
+    InvalidReviewKind(String),
+    InvalidMediaKind(String),
     InvalidFlag,
     InvalidPropProperty(String),
     InvalidPropOperator(String),
@@ -34,6 +36,7 @@ pub enum SearchErrorKind {
     InvalidPositiveWholeNumber { provided: String, context: String },
     InvalidNegativeWholeNumber { provided: String, context: String },
     InvalidAnswerButton { provided: String, context: String },
+    InvalidDate { provided: String, context: String },
     Other(Option<String>),
 }
 
@@ -87,6 +90,12 @@ impl SearchErrorKind {
             SearchErrorKind::InvalidState(state) => {
                 tr.search_invalid_argument("is:", state.replace('`', "'"))
             }
+            SearchErrorKind::InvalidReviewKind(kind) => {
+                tr.search_invalid_argument("revlog-kind:", kind.replace('`', "'"))
+            }
+            SearchErrorKind::InvalidMediaKind(kind) => {
+                tr.search_invalid_argument("has:", kind.replace('`', "'"))
+            }
 
             SearchErrorKind::InvalidFlag => tr.search_invalid_flag_2(),
             SearchErrorKind::InvalidPropProperty(prop) => {
@@ -121,6 +130,10 @@ impl SearchErrorKind {
                     context.replace('`', "'"),
                     provided.replace('`', "'"),
                 ),
+
+            SearchErrorKind::InvalidDate { provided, context } => {
+                tr.search_invalid_date(context.replace('`', "'"), provided.replace('`', "'"))
+            }
         };
         tr.search_invalid_search(reason).into()
     }
@@ -0,0 +1,223 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use itertools::Itertools;
+
+use crate::{
+    latex::extract_latex_expanding_clozes,
+    prelude::*,
+    revlog::RevlogEntry,
+    search::SortMode,
+    text::{extract_media_refs, normalize_to_nfc, REMOTE_FILENAME},
+};
+
+/// The cards matched by an export search, and everything they depend on.
+#[derive(Debug, Default)]
+pub(super) struct ExchangeData {
+    pub(super) notes: Vec<Note>,
+    pub(super) cards: Vec<Card>,
+    pub(super) decks: Vec<Deck>,
+    pub(super) notetypes: Vec<Notetype>,
+    pub(super) deck_configs: Vec<DeckConfig>,
+    pub(super) revlog: Vec<RevlogEntry>,
+}
+
+impl ExchangeData {
+    pub(super) fn gather(
+        col: &mut Collection,
+        search: impl TryIntoSearch,
+        with_scheduling: bool,
+    ) -> Result<Self> {
+        let mut data = ExchangeData::default();
+
+        col.search_cards_into_table(search, SortMode::NoOrder)?;
+        data.cards = col.storage.all_searched_cards()?;
+        if with_scheduling {
+            data.revlog = col
+                .storage
+                .get_revlog_entries_for_searched_cards_in_card_order()?;
+        }
+        col.storage.clear_searched_cards_table()?;
+
+        data.gather_notes(col)?;
+        data.clear_flags();
+        if !with_scheduling {
+            data.remove_scheduling_information();
+        }
+        data.gather_decks(col, with_scheduling)?;
+        data.gather_notetypes(col)?;
+
+        Ok(data)
+    }
+
+    /// Names of files in the media folder that are referenced by the gathered
+    /// notes, including LaTeX images and static files (starting with an
+    /// underscore) that are referenced by the notetypes.
+    pub(super) fn gather_media_names(&self, media_folder: &Path) -> Result<Vec<String>> {
+        let mut names = HashSet::new();
+        let svg_by_notetype: HashMap<NotetypeId, bool> = self
+            .notetypes
+            .iter()
+            .map(|nt| (nt.id, nt.config.latex_svg))
+            .collect();
+
+        for note in &self.notes {
+            let svg = svg_by_notetype
+                .get(&note.notetype_id)
+                .copied()
+                .unwrap_or_default();
+            for field in note.fields() {
+                for media_ref in extract_media_refs(field) {
+                    if !REMOTE_FILENAME.is_match(media_ref.fname) {
+                        names.insert(normalize_to_nfc(&media_ref.fname_decoded).into_owned());
+                    }
+                }
+                let (_, extracted) = extract_latex_expanding_clozes(field, svg);
+                names.extend(extracted.into_iter().map(|latex| latex.fname));
+            }
+        }
+        self.gather_static_media_names(media_folder, &mut names)?;
+
+        Ok(names
+            .into_iter()
+            .filter(|name| is_file_in_folder(media_folder, name))
+            .sorted()
+            .collect())
+    }
+
+    fn gather_static_media_names(
+        &self,
+        media_folder: &Path,
+        names: &mut HashSet<String>,
+    ) -> Result<()> {
+        if !media_folder.is_dir() {
+            return Ok(());
+        }
+        for entry in fs::read_dir(media_folder)? {
+            let entry = entry?;
+            if let Some(fname) = entry.file_name().to_str() {
+                if fname.starts_with('_')
+                    && self
+                        .notetypes
+                        .iter()
+                        .any(|nt| notetype_references_file(nt, fname))
+                {
+                    names.insert(fname.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn gather_notes(&mut self, col: &mut Collection) -> Result<()> {
+        let nids = self.cards.iter().map(|card| card.note_id).unique().sorted();
+        for nid in nids {
+            if let Some(note) = col.storage.get_note(nid)? {
+                self.notes.push(note);
+            }
+        }
+        Ok(())
+    }
+
+    fn gather_decks(&mut self, col: &mut Collection, with_scheduling: bool) -> Result<()> {
+        let dids: HashSet<DeckId> = self
+            .cards
+            .iter()
+            .flat_map(|card| [card.deck_id, card.original_deck_id])
+            .filter(|did| did.0 != 0)
+            .collect();
+        let mut decks = HashMap::new();
+        for did in dids {
+            if let Some(deck) = col.storage.get_deck(did)? {
+                // parents are required to keep the deck tree intact
+                for parent in col.storage.parent_decks(&deck)? {
+                    decks.insert(parent.id, parent);
+                }
+                decks.insert(deck.id, deck);
+            }
+        }
+        self.decks = decks.into_values().sorted_by_key(|deck| deck.id).collect();
+
+        let mut config_ids = HashSet::new();
+        for deck in &mut self.decks {
+            if let DeckKind::Normal(normal) = &mut deck.kind {
+                if with_scheduling {
+                    config_ids.insert(DeckConfigId(normal.config_id));
+                } else {
+                    // scheduling not included, so reset to the default preset
+                    normal.config_id = 1;
+                }
+            }
+        }
+        for dcid in config_ids.into_iter().sorted() {
+            if let Some(config) = col.storage.get_deck_config(dcid)? {
+                self.deck_configs.push(config);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn gather_notetypes(&mut self, col: &mut Collection) -> Result<()> {
+        let ntids = self
+            .notes
+            .iter()
+            .map(|note| note.notetype_id)
+            .unique()
+            .sorted();
+        for ntid in ntids {
+            if let Some(notetype) = col.get_notetype(ntid)? {
+                self.notetypes.push(notetype.as_ref().clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn clear_flags(&mut self) {
+        for card in &mut self.cards {
+            card.flags = 0;
+        }
+    }
+
+    /// Reset cards to new, preserving the order of their notes, and remove
+    /// tags that only make sense alongside scheduling information.
+    fn remove_scheduling_information(&mut self) {
+        let positions: HashMap<NoteId, u32> = self
+            .notes
+            .iter()
+            .enumerate()
+            .map(|(idx, note)| (note.id, idx as u32 + 1))
+            .collect();
+        for card in &mut self.cards {
+            card.schedule_as_new(positions.get(&card.note_id).copied().unwrap_or_default());
+            card.reps = 0;
+            card.lapses = 0;
+            card.remaining_steps = 0;
+        }
+        for note in &mut self.notes {
+            note.tags.retain(|tag| !is_scheduling_tag(tag));
+        }
+    }
+}
+
+fn is_scheduling_tag(tag: &str) -> bool {
+    tag.eq_ignore_ascii_case("marked") || tag.eq_ignore_ascii_case("leech")
+}
+
+fn notetype_references_file(notetype: &Notetype, fname: &str) -> bool {
+    notetype.config.css.contains(fname)
+        || notetype.templates.iter().any(|tmpl| {
+            tmpl.config.q_format.contains(fname) || tmpl.config.a_format.contains(fname)
+        })
+}
+
+/// False for missing files, and for references into subfolders.
+fn is_file_in_folder(folder: &Path, fname: &str) -> bool {
+    !fname.contains(|c| c == '/' || c == '\\') && folder.join(fname).is_file()
+}
@@ -0,0 +1,94 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+mod gather;
+pub mod package;
+pub mod text;
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportProgress {
+    /// Reading the package.
+    File,
+    /// Number of media files processed so far.
+    Media(usize),
+    /// Number of notes processed so far.
+    Notes(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportProgress {
+    /// Gathering notes and cards, and writing the collection file.
+    File,
+    /// Number of media files written so far.
+    Media(usize),
+    /// Number of rows of a text file written so far.
+    Rows(usize),
+}
+
+/// Controls whether an incoming note replaces the content of an existing note
+/// with the same GUID.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpdateCondition {
+    Never,
+    /// Only if the incoming note was modified more recently.
+    IfNewer,
+    Always,
+}
+
+/// Notes affected by an import, grouped by outcome.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NoteLog {
+    pub added: Vec<LogNote>,
+    pub updated: Vec<LogNote>,
+    /// Notes already in the collection that were left as they were.
+    pub skipped: Vec<LogNote>,
+    /// Notes whose GUID is used by an existing note of a different notetype.
+    pub conflicting: Vec<LogNote>,
+    /// Rows of a text file that were left out because their first field was
+    /// empty.
+    pub empty_first_field: Vec<LogNote>,
+    /// Rows of a text file naming a notetype that does not exist.
+    pub missing_notetype: Vec<LogNote>,
+    /// Media files of a package that could not be copied into the media
+    /// folder.
+    pub failed_media: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogNote {
+    pub id: NoteId,
+    pub fields: Vec<String>,
+}
+
+impl From<&Note> for LogNote {
+    fn from(note: &Note) -> Self {
+        LogNote {
+            id: note.id,
+            fields: note.fields().clone(),
+        }
+    }
+}
+
+/// The parts of an existing note needed to decide how to merge an incoming
+/// note with the same GUID.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct NoteMeta {
+    pub(crate) id: NoteId,
+    pub(crate) notetype_id: NotetypeId,
+    pub(crate) mtime: TimestampSecs,
+}
+
+/// Pass `progress` to `progress_fn`, returning an error if the caller asked
+/// for the operation to be aborted.
+pub(crate) fn update_progress<P, F>(progress_fn: &mut F, progress: P) -> Result<()>
+where
+    F: FnMut(P) -> bool,
+{
+    if progress_fn(progress) {
+        Ok(())
+    } else {
+        Err(AnkiError::Interrupted)
+    }
+}
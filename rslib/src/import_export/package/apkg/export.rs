@@ -0,0 +1,163 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::path::Path;
+
+use itertools::Itertools;
+use tempfile::NamedTempFile;
+
+use crate::{
+    collection::CollectionBuilder,
+    config::SchedulerVersion,
+    import_export::{
+        gather::ExchangeData,
+        package::{write_package, COLLECTION_NAME_V1, COLLECTION_NAME_V2},
+        update_progress, ExportProgress,
+    },
+    prelude::*,
+    tags::Tag,
+};
+
+impl Collection {
+    /// Export the cards matched by `search`, along with their notes, decks
+    /// and notetypes, into an .apkg file at `out_path`.
+    /// Returns the number of exported notes.
+    pub fn export_apkg(
+        &mut self,
+        search: impl TryIntoSearch,
+        with_scheduling: bool,
+        with_media: bool,
+        out_path: impl AsRef<Path>,
+        mut progress_fn: impl FnMut(ExportProgress) -> bool,
+    ) -> Result<usize> {
+        update_progress(&mut progress_fn, ExportProgress::File)?;
+        let data = ExchangeData::gather(self, search, with_scheduling)?;
+        let media_names = if with_media {
+            data.gather_media_names(&self.media_folder)?
+        } else {
+            vec![]
+        };
+        let note_count = data.notes.len();
+
+        // cards with v1 scheduling must not end up in a v2 collection
+        let scheduler = if with_scheduling {
+            self.scheduler_version()
+        } else {
+            SchedulerVersion::V2
+        };
+        let collection_name = if with_scheduling && scheduler == SchedulerVersion::V2 {
+            COLLECTION_NAME_V2
+        } else {
+            COLLECTION_NAME_V1
+        };
+
+        let temp_col_file = NamedTempFile::new()?;
+        let mut temp_col = CollectionBuilder::new(temp_col_file.path())
+            .set_tr(self.tr.clone())
+            .build()?;
+        temp_col.insert_exchange_data(data, self.storage.creation_stamp()?, scheduler)?;
+        temp_col.close(true)?;
+
+        write_package(
+            out_path.as_ref(),
+            collection_name,
+            temp_col_file.path(),
+            &self.media_folder,
+            &media_names,
+            None,
+            &mut progress_fn,
+        )?;
+
+        Ok(note_count)
+    }
+
+    /// Replace the stock notetypes of a freshly-created collection with the
+    /// gathered data.
+    fn insert_exchange_data(
+        &mut self,
+        data: ExchangeData,
+        creation_stamp: TimestampSecs,
+        scheduler: SchedulerVersion,
+    ) -> Result<()> {
+        self.transact_no_undo(|col| {
+            col.storage.set_creation_stamp(creation_stamp)?;
+            col.set_scheduler_version_config_key(scheduler)?;
+            for (ntid, _) in col.storage.get_all_notetype_names()? {
+                col.storage.remove_notetype(ntid)?;
+            }
+            for notetype in &data.notetypes {
+                col.storage
+                    .add_or_update_notetype_with_existing_id(notetype)?;
+            }
+            for deck in &data.decks {
+                col.storage.add_or_update_deck_with_existing_id(deck)?;
+            }
+            for config in &data.deck_configs {
+                col.storage
+                    .add_or_update_deck_config_with_existing_id(config)?;
+            }
+            let usn = col.usn()?;
+            for tag in data.notes.iter().flat_map(|note| &note.tags).unique() {
+                col.storage.register_tag(&Tag::new(tag.clone(), usn))?;
+            }
+            for note in &data.notes {
+                col.storage.add_or_update_note(note)?;
+            }
+            for card in &data.cards {
+                col.storage.add_or_update_card(card)?;
+            }
+            for entry in &data.revlog {
+                col.storage.add_revlog_entry(entry, false)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, fs, fs::File, io::Read};
+
+    use tempfile::tempdir;
+    use zip::ZipArchive;
+
+    use super::*;
+    use crate::import_export::package::media::MEDIA_MAP_NAME;
+
+    #[test]
+    fn export_with_media() -> Result<()> {
+        let dir = tempdir()?;
+        let media_folder = dir.path().join("media");
+        fs::create_dir(&media_folder)?;
+        fs::write(media_folder.join("foo.jpg"), "foo")?;
+        fs::write(media_folder.join("unused.jpg"), "unused")?;
+        let mut col = CollectionBuilder::new(dir.path().join("col.anki2"))
+            .set_media_paths(media_folder, dir.path().join("media.db"))
+            .build()?;
+
+        let nt = col.get_notetype_by_name("Basic")?.unwrap();
+        let mut note = nt.new_note();
+        note.set_field(0, "<img src=\"foo.jpg\"> <img src=\"missing.jpg\">")?;
+        note.tags.push("marked".into());
+        col.add_note(&mut note, DeckId(1))?;
+
+        let apkg_path = dir.path().join("export.apkg");
+        let count = col.export_apkg("", false, true, &apkg_path, |_| true)?;
+        assert_eq!(count, 1);
+
+        let mut archive = ZipArchive::new(File::open(&apkg_path)?)?;
+        assert!(archive.by_name(COLLECTION_NAME_V1).is_ok());
+        let mut contents = String::new();
+        archive.by_name("0")?.read_to_string(&mut contents)?;
+        assert_eq!(contents, "foo");
+        let mut map_json = vec![];
+        archive
+            .by_name(MEDIA_MAP_NAME)?
+            .read_to_end(&mut map_json)?;
+        let map: HashMap<String, String> = serde_json::from_slice(&map_json)?;
+        assert_eq!(map.len(), 1);
+        assert_eq!(map["0"], "foo.jpg");
+
+        Ok(())
+    }
+}
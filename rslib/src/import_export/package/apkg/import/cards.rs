@@ -0,0 +1,75 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::mem;
+
+use super::Context;
+use crate::{
+    card::{CardQueue, CardType},
+    import_export::ImportProgress,
+    prelude::*,
+};
+
+impl<F: FnMut(ImportProgress) -> bool> Context<'_, F> {
+    /// Add the cards of added notes, keeping their scheduling. New cards are
+    /// placed after the existing new cards, and the due dates of review cards
+    /// are shifted so that their remaining intervals are unchanged.
+    pub(super) fn import_cards(&mut self) -> Result<()> {
+        let sched = self.target_col.scheduler_version();
+        let position_offset = self.target_col.get_next_card_position();
+        let mut next_position = position_offset;
+
+        for mut card in mem::take(&mut self.data.cards) {
+            let note_id = match self.added_note_map.get(&card.note_id) {
+                Some(nid) => *nid,
+                None => continue,
+            };
+            let incoming_id = card.id;
+            card.id.0 = 0;
+            card.note_id = note_id;
+            card.remove_from_filtered_deck_restoring_queue(sched);
+            card.deck_id = self
+                .deck_map
+                .get(&card.deck_id)
+                .copied()
+                .unwrap_or(DeckId(1));
+            card.flags = 0;
+            if card.ctype == CardType::New {
+                card.due = card.due.saturating_add(position_offset as i32);
+                next_position = next_position.max(card.due.max(0) as u32 + 1);
+            } else if due_is_day_number(&card) {
+                card.due = card.due.saturating_add(self.days_offset);
+            }
+            self.target_col.add_card(&mut card)?;
+            self.card_map.insert(incoming_id, card.id);
+        }
+
+        if next_position != position_offset {
+            self.target_col.set_next_card_position(next_position)?;
+        }
+
+        Ok(())
+    }
+
+    /// Only the review history of added cards is imported.
+    pub(super) fn import_revlog(&mut self) -> Result<()> {
+        for mut entry in mem::take(&mut self.data.revlog) {
+            if let Some(cid) = self.card_map.get(&entry.cid) {
+                entry.cid = *cid;
+                entry.usn = self.usn;
+                self.target_col.add_revlog_entry_undoable(entry)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn due_is_day_number(card: &Card) -> bool {
+    match card.queue {
+        CardQueue::Review | CardQueue::DayLearn => true,
+        CardQueue::Suspended | CardQueue::SchedBuried | CardQueue::UserBuried => {
+            card.ctype == CardType::Review
+        }
+        _ => false,
+    }
+}
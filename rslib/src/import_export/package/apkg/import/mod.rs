@@ -0,0 +1,384 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+mod cards;
+mod notes;
+
+use std::{
+    collections::HashMap,
+    env,
+    io::{Read, Seek},
+    mem,
+    path::Path,
+};
+
+use zip::ZipArchive;
+
+use crate::{
+    collection::CollectionBuilder,
+    config::SchedulerVersion,
+    error::ImportError,
+    import_export::{
+        gather::ExchangeData,
+        package::{extract_collection, media::extract_media_entries, open_package},
+        update_progress, ImportProgress, NoteLog, UpdateCondition,
+    },
+    media::{
+        files::{sha1_of_data, unique_filename_in_folder},
+        MediaManager,
+    },
+    prelude::*,
+};
+
+/// Everything needed to merge the contents of a package into the target
+/// collection. IDs of incoming objects are mapped to the IDs they were given
+/// in the target collection.
+struct Context<'a, F> {
+    target_col: &'a mut Collection,
+    data: ExchangeData,
+    usn: Usn,
+    normalize_notes: bool,
+    update_condition: UpdateCondition,
+    /// Original filename -> name the file was stored under.
+    media_renames: HashMap<String, String>,
+    /// Number of days to add to the due date of review cards.
+    days_offset: i32,
+    notetype_map: HashMap<NotetypeId, NotetypeId>,
+    deck_map: HashMap<DeckId, DeckId>,
+    deck_config_map: HashMap<DeckConfigId, DeckConfigId>,
+    /// Only contains notes that were added; the cards and revlog of updated
+    /// notes are left alone.
+    added_note_map: HashMap<NoteId, NoteId>,
+    card_map: HashMap<CardId, CardId>,
+    log: NoteLog,
+    progress_fn: &'a mut F,
+}
+
+impl Collection {
+    /// Merge the contents of an .apkg file into the collection. Incoming
+    /// notes are matched with existing notes by GUID, and `update_condition`
+    /// decides whether matched notes get their content replaced. The
+    /// scheduling of existing cards is never altered.
+    pub fn import_apkg(
+        &mut self,
+        path: impl AsRef<Path>,
+        update_condition: UpdateCondition,
+        mut progress_fn: impl FnMut(ImportProgress) -> bool,
+    ) -> Result<OpOutput<NoteLog>> {
+        update_progress(&mut progress_fn, ImportProgress::File)?;
+        let mut archive = open_package(path.as_ref())?;
+        let (data, source_days_elapsed) = self.gather_package_data(&mut archive)?;
+        let media_entries = extract_media_entries(&mut archive)?;
+        let media_renames = self.media_renames(&mut archive, &media_entries)?;
+        let days_offset = self.timing_today()?.days_elapsed as i32 - source_days_elapsed as i32;
+
+        let mut output = self.transact(Op::Import, |col| {
+            let mut ctx = Context {
+                usn: col.usn()?,
+                normalize_notes: col.get_config_bool(BoolKey::NormalizeNoteText),
+                target_col: col,
+                data,
+                update_condition,
+                media_renames,
+                days_offset,
+                notetype_map: HashMap::new(),
+                deck_map: HashMap::new(),
+                deck_config_map: HashMap::new(),
+                added_note_map: HashMap::new(),
+                card_map: HashMap::new(),
+                log: NoteLog::default(),
+                progress_fn: &mut progress_fn,
+            };
+            ctx.import()?;
+            Ok(ctx.log)
+        })?;
+        // only touch the media folder once the notes referencing the files are
+        // in; as the import can't fail anymore, files that can't be copied are
+        // logged instead
+        output.output.failed_media =
+            self.import_media(&mut archive, &media_entries, &mut progress_fn);
+
+        Ok(output)
+    }
+
+    /// Open the collection file inside the package, and gather all of its
+    /// contents. Also returns the source collection's day count, so that due
+    /// dates can be adjusted.
+    fn gather_package_data<R: Read + Seek>(
+        &mut self,
+        archive: &mut ZipArchive<R>,
+    ) -> Result<(ExchangeData, u32)> {
+        let temp_col_file = extract_collection(archive, &env::temp_dir())?;
+        let mut source_col = CollectionBuilder::new(temp_col_file.path())
+            .set_tr(self.tr.clone())
+            .build()
+            .map_err(|_| corrupt())?;
+        if self.scheduler_version() != SchedulerVersion::V1 {
+            source_col.transact_no_undo(|col| col.upgrade_to_v2_scheduler())?;
+        }
+        let data = ExchangeData::gather(&mut source_col, "", true)?;
+        let days_elapsed = source_col.timing_today()?.days_elapsed;
+        source_col.close(false)?;
+
+        Ok((data, days_elapsed))
+    }
+
+    /// The package's media files that will have to be stored under a
+    /// different name, because a different file with the same name already
+    /// exists in the media folder.
+    fn media_renames<R: Read + Seek>(
+        &self,
+        archive: &mut ZipArchive<R>,
+        entries: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>> {
+        let mut renames = HashMap::new();
+        let mut data = vec![];
+        for (entry_name, fname) in entries {
+            data.clear();
+            match archive.by_name(entry_name) {
+                Ok(mut file) => file.read_to_end(&mut data)?,
+                // listed in the map, but not present
+                Err(_) => continue,
+            };
+            let stored_name =
+                unique_filename_in_folder(&self.media_folder, fname, sha1_of_data(&data))?;
+            if stored_name != fname.as_str() {
+                renames.insert(fname.clone(), stored_name.into_owned());
+            }
+        }
+        Ok(renames)
+    }
+
+    /// Copy the package's media files into the media folder, under the names
+    /// [Collection::media_renames()] picked for them. Returns the names of the
+    /// files that could not be copied. The import has already been committed
+    /// at this point, so it can no longer be aborted.
+    fn import_media<R: Read + Seek>(
+        &mut self,
+        archive: &mut ZipArchive<R>,
+        entries: &HashMap<String, String>,
+        progress_fn: &mut impl FnMut(ImportProgress) -> bool,
+    ) -> Vec<String> {
+        if entries.is_empty() {
+            return vec![];
+        }
+        let mgr = match MediaManager::new(&self.media_folder, &self.media_db) {
+            Ok(mgr) => mgr,
+            Err(_) => return entries.values().cloned().collect(),
+        };
+        let mut ctx = mgr.dbctx();
+        let mut failed = vec![];
+        let mut data = vec![];
+        for (idx, (entry_name, fname)) in entries.iter().enumerate() {
+            progress_fn(ImportProgress::Media(idx));
+            data.clear();
+            let copied = match archive.by_name(entry_name) {
+                Ok(mut file) => file
+                    .read_to_end(&mut data)
+                    .map_err(Into::into)
+                    .and_then(|_| mgr.add_file(&mut ctx, fname, &data).map(|_| ())),
+                // listed in the map, but not present
+                Err(_) => continue,
+            };
+            if copied.is_err() {
+                failed.push(fname.clone());
+            }
+        }
+        failed
+    }
+}
+
+impl<F: FnMut(ImportProgress) -> bool> Context<'_, F> {
+    fn import(&mut self) -> Result<()> {
+        self.import_notetypes()?;
+        self.import_deck_configs()?;
+        self.import_decks()?;
+        self.import_notes()?;
+        self.import_cards()?;
+        self.import_revlog()
+    }
+
+    /// Map incoming notetypes to existing ones with the same ID and schema.
+    /// If the ID is taken by an incompatible notetype, the following IDs are
+    /// tried in turn, so that repeated imports of the same package keep
+    /// mapping to the same copy.
+    fn import_notetypes(&mut self) -> Result<()> {
+        for mut notetype in mem::take(&mut self.data.notetypes) {
+            let incoming_id = notetype.id;
+            let mut candidate_id = incoming_id;
+            loop {
+                match self.target_col.get_notetype(candidate_id)? {
+                    Some(existing) if schema_matches(&existing, &notetype) => break,
+                    Some(_) => candidate_id.0 += 1,
+                    None => {
+                        notetype.id = candidate_id;
+                        notetype.usn = self.usn;
+                        self.target_col
+                            .ensure_notetype_name_unique(&mut notetype, self.usn)?;
+                        self.target_col
+                            .add_notetype_with_existing_id_undoable(&notetype)?;
+                        break;
+                    }
+                }
+            }
+            self.notetype_map.insert(incoming_id, candidate_id);
+        }
+        Ok(())
+    }
+
+    /// Presets that already exist are left as they are.
+    fn import_deck_configs(&mut self) -> Result<()> {
+        for mut config in mem::take(&mut self.data.deck_configs) {
+            let incoming_id = config.id;
+            if self
+                .target_col
+                .storage
+                .get_deck_config(incoming_id)?
+                .is_none()
+            {
+                config.usn = self.usn;
+                self.target_col.add_deck_config_undoable(&mut config)?;
+            }
+            self.deck_config_map.insert(incoming_id, config.id);
+        }
+        Ok(())
+    }
+
+    /// Decks are matched by name. Filtered decks are not imported; their
+    /// cards are returned to their home decks instead.
+    fn import_decks(&mut self) -> Result<()> {
+        let mut decks = mem::take(&mut self.data.decks);
+        // ensure parents are handled before their children
+        decks.sort_unstable_by(|a, b| a.name.as_native_str().cmp(b.name.as_native_str()));
+        for mut deck in decks {
+            let incoming_id = deck.id;
+            let normal = match &mut deck.kind {
+                DeckKind::Normal(normal) => normal,
+                DeckKind::Filtered(_) => continue,
+            };
+            if let Some(existing_id) = self
+                .target_col
+                .storage
+                .get_deck_id(deck.name.as_native_str())?
+            {
+                self.deck_map.insert(incoming_id, existing_id);
+                continue;
+            }
+            normal.config_id = self
+                .deck_config_map
+                .get(&DeckConfigId(normal.config_id))
+                .map(|dcid| dcid.0)
+                .unwrap_or(1);
+            deck.id.0 = 0;
+            self.target_col.add_deck_inner(&mut deck, self.usn)?;
+            self.deck_map.insert(incoming_id, deck.id);
+        }
+        Ok(())
+    }
+}
+
+/// True if the notetypes have the same kind, and the same fields and
+/// templates in the same order.
+fn schema_matches(existing: &Notetype, incoming: &Notetype) -> bool {
+    existing.config.kind == incoming.config.kind
+        && existing
+            .fields
+            .iter()
+            .map(|field| &field.name)
+            .eq(incoming.fields.iter().map(|field| &field.name))
+        && existing
+            .templates
+            .iter()
+            .map(|template| &template.name)
+            .eq(incoming.templates.iter().map(|template| &template.name))
+}
+
+fn corrupt() -> AnkiError {
+    AnkiError::ImportError(ImportError::Corrupt)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::card::{CardQueue, CardType};
+
+    fn col_with_media(dir: &Path, name: &str) -> Result<Collection> {
+        let media_folder = dir.join(format!("{}.media", name));
+        fs::create_dir(&media_folder)?;
+        CollectionBuilder::new(dir.join(format!("{}.anki2", name)))
+            .set_media_paths(media_folder, dir.join(format!("{}.mdb", name)))
+            .build()
+    }
+
+    #[test]
+    fn merging_by_guid() -> Result<()> {
+        let dir = tempdir()?;
+        let apkg_path = dir.path().join("shared.apkg");
+        let mut source = col_with_media(dir.path(), "source")?;
+        let mut target = col_with_media(dir.path(), "target")?;
+        fs::write(source.media_folder.join("foo.jpg"), "foo")?;
+
+        let nt = source.get_notetype_by_name("Basic")?.unwrap();
+        let mut note = nt.new_note();
+        note.set_field(0, "<img src=\"foo.jpg\">")?;
+        source.add_note(&mut note, DeckId(1))?;
+        source.export_apkg("", false, true, &apkg_path, |_| true)?;
+
+        // an import that fails while adding notes leaves the media folder alone
+        let aborted = target.import_apkg(&apkg_path, UpdateCondition::IfNewer, |progress| {
+            !matches!(progress, ImportProgress::Notes(_))
+        });
+        assert!(aborted.is_err());
+        assert!(!target.media_folder.join("foo.jpg").exists());
+
+        let log = target
+            .import_apkg(&apkg_path, UpdateCondition::IfNewer, |_| true)?
+            .output;
+        assert_eq!(log.added.len(), 1);
+        assert!(target.media_folder.join("foo.jpg").is_file());
+
+        // study the imported card in the target collection
+        let nid = log.added[0].id;
+        let mut card = target.storage.all_cards_of_note(nid)?.pop().unwrap();
+        card.ctype = CardType::Review;
+        card.queue = CardQueue::Review;
+        card.interval = 10;
+        target.storage.update_card(&card)?;
+
+        // edits to the shared note should flow into the existing note
+        note.set_field(1, "back")?;
+        note.mtime.0 += 10;
+        note.prepare_for_update(&nt, true)?;
+        source.storage.update_note(&note)?;
+        source.export_apkg("", false, true, &apkg_path, |_| true)?;
+
+        let log = target
+            .import_apkg(&apkg_path, UpdateCondition::Never, |_| true)?
+            .output;
+        assert_eq!((log.added.len(), log.skipped.len()), (0, 1));
+        let log = target
+            .import_apkg(&apkg_path, UpdateCondition::IfNewer, |_| true)?
+            .output;
+        assert_eq!((log.added.len(), log.updated.len()), (0, 1));
+        assert_eq!(log.updated[0].id, nid);
+        assert_eq!(target.storage.get_note(nid)?.unwrap().fields()[1], "back");
+        assert_eq!(target.storage.all_cards_of_note(nid)?[0].interval, 10);
+
+        // the merge can be undone in a single step
+        target.undo()?;
+        assert_eq!(target.storage.get_note(nid)?.unwrap().fields()[1], "");
+
+        // media that can't be copied is logged, but doesn't fail the import
+        fs::remove_dir_all(&target.media_folder)?;
+        let log = target
+            .import_apkg(&apkg_path, UpdateCondition::Always, |_| true)?
+            .output;
+        assert_eq!(log.failed_media, vec!["foo.jpg"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::{collections::HashMap, mem, sync::Arc};
+
+use super::Context;
+use crate::{
+    import_export::{update_progress, ImportProgress, LogNote, NoteMeta, UpdateCondition},
+    media::check::rename_media_ref_in_field,
+    notetype::CardGenContext,
+    prelude::*,
+    text::extract_media_refs,
+};
+
+impl<F: FnMut(ImportProgress) -> bool> Context<'_, F> {
+    pub(super) fn import_notes(&mut self) -> Result<()> {
+        let mut existing_by_guid = self.target_col.storage.note_metas_by_guid()?;
+        let mut notetypes = HashMap::new();
+
+        for (idx, mut note) in mem::take(&mut self.data.notes).into_iter().enumerate() {
+            update_progress(self.progress_fn, ImportProgress::Notes(idx))?;
+            if let Some(ntid) = self.notetype_map.get(&note.notetype_id) {
+                note.notetype_id = *ntid;
+            }
+            let notetype = self.get_notetype_cached(&mut notetypes, note.notetype_id)?;
+            self.rename_media_refs(&mut note);
+
+            match existing_by_guid.get(&note.guid) {
+                None => {
+                    let meta = self.add_note(note, &notetype)?;
+                    existing_by_guid.insert(meta.0, meta.1);
+                }
+                Some(existing) if existing.notetype_id != note.notetype_id => {
+                    self.log.conflicting.push(LogNote::from(&note));
+                }
+                Some(existing) => {
+                    let existing = *existing;
+                    self.maybe_update_note(note, existing, &notetype)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_notetype_cached(
+        &mut self,
+        cache: &mut HashMap<NotetypeId, Arc<Notetype>>,
+        ntid: NotetypeId,
+    ) -> Result<Arc<Notetype>> {
+        if let Some(notetype) = cache.get(&ntid) {
+            return Ok(notetype.clone());
+        }
+        let notetype = self
+            .target_col
+            .get_notetype(ntid)?
+            .ok_or(AnkiError::NotFound)?;
+        cache.insert(ntid, notetype.clone());
+        Ok(notetype)
+    }
+
+    /// Returns the GUID and metadata of the added note.
+    fn add_note(&mut self, mut note: Note, notetype: &Notetype) -> Result<(String, NoteMeta)> {
+        let incoming_id = note.id;
+        note.id.0 = 0;
+        note.usn = self.usn;
+        self.target_col.canonify_note_tags(&mut note, self.usn)?;
+        note.prepare_for_update(notetype, self.normalize_notes)?;
+        self.target_col.add_note_only_undoable(&mut note)?;
+
+        self.added_note_map.insert(incoming_id, note.id);
+        self.log.added.push(LogNote::from(&note));
+
+        Ok((
+            note.guid,
+            NoteMeta {
+                id: note.id,
+                notetype_id: note.notetype_id,
+                mtime: note.mtime,
+            },
+        ))
+    }
+
+    fn maybe_update_note(
+        &mut self,
+        note: Note,
+        existing: NoteMeta,
+        notetype: &Notetype,
+    ) -> Result<()> {
+        let update = match self.update_condition {
+            UpdateCondition::Never => false,
+            UpdateCondition::IfNewer => note.mtime > existing.mtime,
+            UpdateCondition::Always => true,
+        };
+        if update {
+            self.update_note(note, existing.id, notetype)
+        } else {
+            self.log.skipped.push(LogNote {
+                id: existing.id,
+                fields: note.fields().clone(),
+            });
+            Ok(())
+        }
+    }
+
+    /// Replace the content of an existing note, keeping its ID, and adding
+    /// any cards the new content requires. The modification time of the
+    /// incoming note is preserved.
+    fn update_note(&mut self, mut note: Note, nid: NoteId, notetype: &Notetype) -> Result<()> {
+        let original = self
+            .target_col
+            .storage
+            .get_note(nid)?
+            .ok_or(AnkiError::NotFound)?;
+        note.id = nid;
+        if note.fields() == original.fields() && note.tags == original.tags {
+            self.log.skipped.push(LogNote::from(&note));
+            return Ok(());
+        }
+
+        note.usn = self.usn;
+        let last_deck = self
+            .target_col
+            .get_last_deck_added_to_for_notetype(note.notetype_id);
+        let ctx = CardGenContext::new(notetype, last_deck, self.usn);
+        self.target_col.update_note_inner_generating_cards(
+            &ctx,
+            &mut note,
+            &original,
+            false,
+            self.normalize_notes,
+            true,
+        )?;
+        self.log.updated.push(LogNote::from(&note));
+
+        Ok(())
+    }
+
+    /// Point references to media files that were stored under a different
+    /// name to the new name.
+    fn rename_media_refs(&self, note: &mut Note) {
+        if self.media_renames.is_empty() {
+            return;
+        }
+        for field in note.fields_mut() {
+            if let Some(updated) = field_with_renamed_media_refs(field, &self.media_renames) {
+                *field = updated;
+            }
+        }
+    }
+}
+
+fn field_with_renamed_media_refs(field: &str, renames: &HashMap<String, String>) -> Option<String> {
+    let mut updated: Option<String> = None;
+    for media_ref in extract_media_refs(field) {
+        if let Some(new_name) = renames.get(media_ref.fname_decoded.as_ref()) {
+            let current = updated.as_deref().unwrap_or(field);
+            updated = Some(rename_media_ref_in_field(current, &media_ref, new_name));
+        }
+    }
+    updated
+}
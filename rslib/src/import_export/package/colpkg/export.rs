@@ -0,0 +1,79 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::path::Path;
+
+use crate::{
+    config::SchedulerVersion,
+    import_export::{
+        package::{
+            media::media_folder_filenames, write_package, COLLECTION_NAME_V1, COLLECTION_NAME_V2,
+        },
+        update_progress, ExportProgress,
+    },
+    prelude::*,
+};
+
+impl Collection {
+    /// Write the entire collection into a .colpkg file, optionally including
+    /// every file in the media folder and the media DB. The collection is
+    /// optimized and closed first, so that the package holds a consistent
+    /// copy of it that older clients can read.
+    pub fn export_colpkg(
+        self,
+        out_path: impl AsRef<Path>,
+        include_media: bool,
+        mut progress_fn: impl FnMut(ExportProgress) -> bool,
+    ) -> Result<()> {
+        update_progress(&mut progress_fn, ExportProgress::File)?;
+        let col_path = self.col_path.clone();
+        let media_folder = self.media_folder.clone();
+        let media_db = self.media_db.clone();
+        let collection_name = collection_name_for(self.scheduler_version());
+        self.storage.optimize()?;
+        self.close(true)?;
+
+        let (media_names, media_db) = if include_media {
+            (
+                media_folder_filenames(&media_folder)?,
+                Some(media_db.as_path()).filter(|path| path.is_file()),
+            )
+        } else {
+            (vec![], None)
+        };
+        write_package(
+            out_path.as_ref(),
+            collection_name,
+            &col_path,
+            &media_folder,
+            &media_names,
+            media_db,
+            &mut progress_fn,
+        )
+    }
+}
+
+/// Package a closed collection file without any media, as is done for
+/// backups.
+pub(crate) fn write_colpkg_without_media(
+    col_path: &Path,
+    scheduler: SchedulerVersion,
+    out_path: &Path,
+) -> Result<()> {
+    write_package(
+        out_path,
+        collection_name_for(scheduler),
+        col_path,
+        Path::new(""),
+        &[],
+        None,
+        &mut |_| true,
+    )
+}
+
+fn collection_name_for(scheduler: SchedulerVersion) -> &'static str {
+    match scheduler {
+        SchedulerVersion::V1 => COLLECTION_NAME_V1,
+        SchedulerVersion::V2 => COLLECTION_NAME_V2,
+    }
+}
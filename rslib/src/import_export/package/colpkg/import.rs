@@ -0,0 +1,208 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use tempfile::{tempdir_in, TempDir};
+
+use crate::{
+    error::ImportError,
+    import_export::{
+        package::{
+            extract_collection,
+            media::{extract_media_db, restore_media_files},
+            open_package,
+        },
+        update_progress, ImportProgress,
+    },
+    prelude::*,
+    storage::open_and_check_sqlite_file,
+};
+
+/// Replace the collection at `target_col_path` with the one inside the
+/// .colpkg file. If the package contains media, the media folder and media DB
+/// are replaced as well; if it holds media but no media DB, the existing DB
+/// is removed, so that it gets rebuilt from the restored folder.
+///
+/// Everything is extracted next to its target first, and the collection is
+/// only replaced once it has passed an integrity check. The existing files,
+/// including any SQLite journals, are then moved aside, and moved back if
+/// any of the replacements fails, so the profile is either fully restored or
+/// left untouched.
+///
+/// The target collection must not be open.
+pub fn import_colpkg(
+    colpkg_path: &str,
+    target_col_path: &str,
+    target_media_folder: &str,
+    target_media_db: &str,
+    mut progress_fn: impl FnMut(ImportProgress) -> bool,
+) -> Result<()> {
+    update_progress(&mut progress_fn, ImportProgress::File)?;
+    let col_path = Path::new(target_col_path);
+    let mut archive = open_package(Path::new(colpkg_path))?;
+    let temp_col_file = extract_collection(&mut archive, parent_dir(col_path)?)?;
+    check_collection(temp_col_file.path())?;
+
+    let media_folder = Path::new(target_media_folder);
+    let media_db = Path::new(target_media_db);
+    let media_parent = parent_dir(media_folder)?;
+    fs::create_dir_all(media_parent)?;
+    let staged_media = tempdir_in(media_parent)?;
+    let restored_media = restore_media_files(&mut archive, staged_media.path(), &mut progress_fn)?;
+    let staged_media_db = extract_media_db(&mut archive, parent_dir(media_db)?)?;
+
+    let mut originals = Originals::default();
+    let result = (|| -> Result<()> {
+        if restored_media || staged_media_db.is_some() {
+            originals.move_aside(media_folder)?;
+            let staged_media = staged_media.into_path();
+            if let Err(err) = fs::rename(&staged_media, media_folder) {
+                fs::remove_dir_all(&staged_media)?;
+                return Err(err.into());
+            }
+            originals.placed.push(media_folder.to_owned());
+            originals.move_aside_sqlite_file(media_db)?;
+            if let Some(file) = staged_media_db {
+                file.persist(media_db)?;
+                originals.placed.push(media_db.to_owned());
+            }
+        }
+        // a stale WAL would otherwise be applied to the restored collection
+        originals.move_aside_sqlite_file(col_path)?;
+        temp_col_file.persist(col_path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        originals.restore()?;
+    }
+
+    result
+}
+
+fn parent_dir(path: &Path) -> Result<&Path> {
+    path.parent()
+        .ok_or_else(|| AnkiError::invalid_input("invalid target path"))
+}
+
+/// Files and folders moved out of the way of their replacements. They are
+/// deleted when dropped, unless [Originals::restore()] puts them back.
+#[derive(Default)]
+struct Originals {
+    /// Original path, and a temp dir next to it holding the original as "old".
+    moved: Vec<(PathBuf, TempDir)>,
+    /// Replacements that have been moved into place.
+    placed: Vec<PathBuf>,
+}
+
+impl Originals {
+    /// Move `path` into a temp dir next to it, if it exists.
+    fn move_aside(&mut self, path: &Path) -> Result<()> {
+        if path.exists() {
+            let holder = tempdir_in(parent_dir(path)?)?;
+            fs::rename(path, holder.path().join("old"))?;
+            self.moved.push((path.to_owned(), holder));
+        }
+        Ok(())
+    }
+
+    /// Move a SQLite file aside along with any journal files.
+    fn move_aside_sqlite_file(&mut self, path: &Path) -> Result<()> {
+        for suffix in ["", "-wal", "-shm"] {
+            let mut journal = path.as_os_str().to_owned();
+            journal.push(suffix);
+            self.move_aside(Path::new(&journal))?;
+        }
+        Ok(())
+    }
+
+    /// Remove the replacements, and move the originals back.
+    fn restore(self) -> Result<()> {
+        for path in self.placed {
+            if path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+        }
+        for (path, holder) in self.moved.into_iter().rev() {
+            fs::rename(holder.path().join("old"), &path)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_collection(col_path: &Path) -> Result<()> {
+    let db = open_and_check_sqlite_file(col_path)
+        .map_err(|_| AnkiError::ImportError(ImportError::Corrupt))?;
+    db.query_row("select ver from col", [], |row| row.get::<_, u8>(0))
+        .map_err(|_| AnkiError::ImportError(ImportError::Corrupt))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::{collection::CollectionBuilder, media::MediaManager};
+
+    #[test]
+    fn roundtrip() -> Result<()> {
+        let dir = tempdir()?;
+        let media_folder = dir.path().join("media");
+        let media_db = dir.path().join("media.db");
+        let col_path = dir.path().join("col.anki2");
+        let colpkg_path = dir.path().join("backup.colpkg");
+        fs::create_dir(&media_folder)?;
+        let mgr = MediaManager::new(&media_folder, &media_db)?;
+        mgr.add_file(&mut mgr.dbctx(), "foo.mp3", b"foo")?;
+
+        let mut col = CollectionBuilder::new(&col_path)
+            .set_media_paths(&media_folder, &media_db)
+            .build()?;
+        let nt = col.get_notetype_by_name("Basic")?.unwrap();
+        let mut note = nt.new_note();
+        note.set_field(0, "front")?;
+        col.add_note(&mut note, DeckId(1))?;
+        col.export_colpkg(&colpkg_path, true, |_| true)?;
+
+        // restore into a fresh profile
+        let restored_dir = dir.path().join("restored");
+        fs::create_dir(&restored_dir)?;
+        let restored_path = restored_dir.join("col.anki2");
+        let restored_media = restored_dir.join("media");
+        let restored_media_db = restored_dir.join("media.db");
+        // files not in the package are replaced along with the folder
+        fs::create_dir(&restored_media)?;
+        fs::write(restored_media.join("stale.mp3"), "stale")?;
+        // as are the journals of a collection that was not closed cleanly
+        let stale_wal = restored_dir.join("col.anki2-wal");
+        fs::write(&stale_wal, "stale")?;
+        import_colpkg(
+            colpkg_path.to_str().unwrap(),
+            restored_path.to_str().unwrap(),
+            restored_media.to_str().unwrap(),
+            restored_media_db.to_str().unwrap(),
+            |_| true,
+        )?;
+
+        assert!(!stale_wal.exists());
+        let restored = CollectionBuilder::new(&restored_path).build()?;
+        assert_eq!(
+            restored.storage.get_note(note.id)?.unwrap().fields()[0],
+            "front"
+        );
+        assert_eq!(fs::read_to_string(restored_media.join("foo.mp3"))?, "foo");
+        assert!(!restored_media.join("stale.mp3").exists());
+        let restored_media_db = rusqlite::Connection::open(&restored_media_db)?;
+        let fname: String =
+            restored_media_db.query_row("select fname from media", [], |row| row.get(0))?;
+        assert_eq!(fname, "foo.mp3");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Read, Seek, Write},
+    path::Path,
+};
+
+use rusqlite::Connection;
+use tempfile::NamedTempFile;
+use zip::{result::ZipError, write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+    import_export::{update_progress, ExportProgress, ImportProgress},
+    media::files::normalize_filename,
+    prelude::*,
+};
+
+/// Name of the zip entry mapping the numbered media entries to their
+/// original filenames.
+pub(super) const MEDIA_MAP_NAME: &str = "media";
+/// Name of the zip entry holding a copy of the media DB.
+pub(super) const MEDIA_DB_NAME: &str = "media.db";
+
+/// Copy the provided files from the media folder into the zip, naming them
+/// by their index, and write the map of entry names to filenames.
+pub(super) fn write_media_files<W, F>(
+    zip: &mut ZipWriter<W>,
+    media_folder: &Path,
+    fnames: &[String],
+    progress_fn: &mut F,
+) -> Result<()>
+where
+    W: Write + Seek,
+    F: FnMut(ExportProgress) -> bool,
+{
+    let mut media_map = HashMap::new();
+    for (idx, fname) in fnames.iter().enumerate() {
+        update_progress(progress_fn, ExportProgress::Media(idx))?;
+        let entry_name = idx.to_string();
+        zip.start_file(entry_name.clone(), file_options_for(fname))?;
+        io::copy(&mut File::open(media_folder.join(fname))?, zip)?;
+        media_map.insert(entry_name, fname);
+    }
+
+    zip.start_file(MEDIA_MAP_NAME, FileOptions::default())?;
+    zip.write_all(&serde_json::to_vec(&media_map)?)?;
+
+    Ok(())
+}
+
+/// Add a consistent copy of the media DB to the zip. As the DB may be open
+/// in WAL mode, it is vacuumed into a temporary file in `temp_dir` first.
+pub(super) fn write_media_db<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    media_db: &Path,
+    temp_dir: &Path,
+) -> Result<()> {
+    let copy = NamedTempFile::new_in(temp_dir)?;
+    let copy_path = copy
+        .path()
+        .to_str()
+        .ok_or_else(|| AnkiError::invalid_input("invalid temp path"))?;
+    Connection::open(media_db)?.execute("vacuum into ?", [copy_path])?;
+    zip.start_file(MEDIA_DB_NAME, FileOptions::default())?;
+    io::copy(&mut File::open(copy.path())?, zip)?;
+    Ok(())
+}
+
+/// Copy the package's media DB into a temporary file in `dir`, if the
+/// package contains one.
+pub(super) fn extract_media_db<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    dir: &Path,
+) -> Result<Option<NamedTempFile>> {
+    let mut file = match archive.by_name(MEDIA_DB_NAME) {
+        Ok(file) => file,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let mut temp_file = NamedTempFile::new_in(dir)?;
+    io::copy(&mut file, temp_file.as_file_mut())?;
+    Ok(Some(temp_file))
+}
+
+/// Return the map of entry names to filenames, which is empty if the package
+/// was exported without media.
+pub(super) fn extract_media_entries<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+) -> Result<HashMap<String, String>> {
+    let mut data = vec![];
+    match archive.by_name(MEDIA_MAP_NAME) {
+        Ok(mut file) => file.read_to_end(&mut data)?,
+        Err(ZipError::FileNotFound) => return Ok(HashMap::new()),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Write the package's media files into `media_folder`, replacing any
+/// existing files with the same names. Returns false if the package
+/// contained no media.
+pub(super) fn restore_media_files<R, F>(
+    archive: &mut ZipArchive<R>,
+    media_folder: &Path,
+    progress_fn: &mut F,
+) -> Result<bool>
+where
+    R: Read + Seek,
+    F: FnMut(ImportProgress) -> bool,
+{
+    let entries = extract_media_entries(archive)?;
+    if entries.is_empty() {
+        return Ok(false);
+    }
+    fs::create_dir_all(media_folder)?;
+    for (idx, (entry_name, fname)) in entries.iter().enumerate() {
+        update_progress(progress_fn, ImportProgress::Media(idx))?;
+        let mut file = match archive.by_name(entry_name) {
+            Ok(file) => file,
+            // listed in the map, but not present
+            Err(_) => continue,
+        };
+        let fname = normalize_filename(fname);
+        io::copy(
+            &mut file,
+            &mut File::create(media_folder.join(fname.as_ref()))?,
+        )?;
+    }
+    Ok(true)
+}
+
+/// Names of all files in the media folder, or none if it does not exist.
+pub(super) fn media_folder_filenames(media_folder: &Path) -> Result<Vec<String>> {
+    if !media_folder.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut names = vec![];
+    for entry in fs::read_dir(media_folder)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        if let Some(name) = entry.file_name().to_str() {
+            names.push(name.to_string());
+        }
+    }
+    names.sort_unstable();
+    Ok(names)
+}
+
+fn file_options_for(fname: &str) -> FileOptions {
+    // most media formats are compressed already
+    let method = if fname.to_ascii_lowercase().ends_with(".svg") {
+        CompressionMethod::Deflated
+    } else {
+        CompressionMethod::Stored
+    };
+    FileOptions::default().compression_method(method)
+}
@@ -0,0 +1,78 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+mod apkg;
+mod colpkg;
+mod media;
+
+use std::{
+    fs::File,
+    io::{self, Read, Seek},
+    path::Path,
+};
+
+pub(crate) use colpkg::export::write_colpkg_without_media;
+pub use colpkg::import::import_colpkg;
+use tempfile::NamedTempFile;
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use self::media::{write_media_db, write_media_files};
+use crate::{error::ImportError, import_export::ExportProgress, prelude::*};
+
+/// Collection file name used by clients that support the v2 scheduler.
+const COLLECTION_NAME_V2: &str = "collection.anki21";
+/// Collection file name understood by all clients.
+const COLLECTION_NAME_V1: &str = "collection.anki2";
+
+/// Zip the collection file at `col_path`, the listed media files and the
+/// media DB, if provided, into `out_path`, which is only replaced once the
+/// package is complete.
+fn write_package<F>(
+    out_path: &Path,
+    collection_name: &str,
+    col_path: &Path,
+    media_folder: &Path,
+    media_names: &[String],
+    media_db: Option<&Path>,
+    progress_fn: &mut F,
+) -> Result<()>
+where
+    F: FnMut(ExportProgress) -> bool,
+{
+    let out_dir = out_path
+        .parent()
+        .ok_or_else(|| AnkiError::invalid_input("invalid export path"))?;
+    let mut zip = ZipWriter::new(NamedTempFile::new_in(out_dir)?);
+    zip.start_file(collection_name, FileOptions::default())?;
+    io::copy(&mut File::open(col_path)?, &mut zip)?;
+    write_media_files(&mut zip, media_folder, media_names, progress_fn)?;
+    if let Some(media_db) = media_db {
+        write_media_db(&mut zip, media_db, out_dir)?;
+    }
+    zip.finish()?.persist(out_path)?;
+    Ok(())
+}
+
+fn open_package(path: &Path) -> Result<ZipArchive<File>> {
+    ZipArchive::new(File::open(path)?).map_err(|_| AnkiError::ImportError(ImportError::Corrupt))
+}
+
+/// Copy the package's collection file into a temporary file in `dir`.
+fn extract_collection<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    dir: &Path,
+) -> Result<NamedTempFile> {
+    let collection_name = if archive.by_name(COLLECTION_NAME_V2).is_ok() {
+        COLLECTION_NAME_V2
+    } else {
+        COLLECTION_NAME_V1
+    };
+    let mut temp_file = NamedTempFile::new_in(dir)?;
+    io::copy(
+        &mut archive
+            .by_name(collection_name)
+            .map_err(|_| AnkiError::ImportError(ImportError::Corrupt))?,
+        temp_file.as_file_mut(),
+    )?;
+    Ok(temp_file)
+}
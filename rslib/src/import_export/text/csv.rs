@@ -0,0 +1,213 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::{collections::HashSet, fs, mem, path::Path};
+
+use crate::prelude::*;
+
+/// Number of rows used to detect the delimiter and header.
+const SNIFF_ROWS: usize = 10;
+/// Number of data rows included in [CsvMetadata].
+const PREVIEW_ROWS: usize = 5;
+/// Header labels that refer to something other than a field.
+const META_COLUMN_NAMES: [&str; 4] = ["tags", "deck", "notetype", "guid"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Delimiter {
+    Tab,
+    Comma,
+    Semicolon,
+    Pipe,
+    Colon,
+    Space,
+}
+
+impl Delimiter {
+    /// In the order they are tried when detecting the delimiter.
+    const ALL: [Delimiter; 6] = [
+        Delimiter::Tab,
+        Delimiter::Comma,
+        Delimiter::Semicolon,
+        Delimiter::Pipe,
+        Delimiter::Colon,
+        Delimiter::Space,
+    ];
+
+    pub fn char(self) -> char {
+        match self {
+            Delimiter::Tab => '\t',
+            Delimiter::Comma => ',',
+            Delimiter::Semicolon => ';',
+            Delimiter::Pipe => '|',
+            Delimiter::Colon => ':',
+            Delimiter::Space => ' ',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvMetadata {
+    pub delimiter: Delimiter,
+    pub has_header: bool,
+    /// The contents of the header row, if there is one.
+    pub column_labels: Vec<String>,
+    /// The first few rows after the header.
+    pub preview: Vec<Vec<String>>,
+}
+
+impl Collection {
+    /// Detect the delimiter of the file at `path` unless one is provided, and
+    /// whether its first row is a header, and return its first rows.
+    pub fn get_csv_metadata(
+        &mut self,
+        path: impl AsRef<Path>,
+        delimiter: Option<Delimiter>,
+    ) -> Result<CsvMetadata> {
+        let text = read_text_file(path.as_ref())?;
+        let delimiter = delimiter.unwrap_or_else(|| sniff_delimiter(&text));
+        let mut rows = parse_records(&text, delimiter.char(), Some(SNIFF_ROWS));
+        let has_header = looks_like_header(&rows, &self.known_column_names()?);
+        let column_labels = if has_header { rows.remove(0) } else { vec![] };
+        rows.truncate(PREVIEW_ROWS);
+
+        Ok(CsvMetadata {
+            delimiter,
+            has_header,
+            column_labels,
+            preview: rows,
+        })
+    }
+
+    /// Lowercased names of all notetype fields, and of the other things a
+    /// column can be mapped to.
+    fn known_column_names(&mut self) -> Result<HashSet<String>> {
+        let mut names: HashSet<String> = META_COLUMN_NAMES.iter().map(|s| s.to_string()).collect();
+        for notetype in self.get_all_notetypes()?.values() {
+            names.extend(notetype.fields.iter().map(|f| f.name.to_lowercase()));
+        }
+        Ok(names)
+    }
+}
+
+pub(super) fn read_text_file(path: &Path) -> Result<String> {
+    let mut text = fs::read_to_string(path)?;
+    if text.starts_with('\u{feff}') {
+        text.remove(0);
+    }
+    Ok(text)
+}
+
+/// Split `text` into records. Quoted fields may contain the delimiter, line
+/// breaks, and quotes written twice. Empty lines are skipped.
+pub(super) fn parse_records(text: &str, delimiter: char, limit: Option<usize>) -> Vec<Vec<String>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c != '"' {
+                field.push(c);
+            } else if chars.peek() == Some(&'"') {
+                chars.next();
+                field.push('"');
+            } else {
+                in_quotes = false;
+            }
+        } else if c == '"' && field.is_empty() {
+            in_quotes = true;
+        } else if c == delimiter {
+            record.push(mem::take(&mut field));
+        } else if c == '\n' || c == '\r' {
+            if c == '\r' && chars.peek() == Some(&'\n') {
+                chars.next();
+            }
+            if record.is_empty() && field.is_empty() {
+                continue;
+            }
+            record.push(mem::take(&mut field));
+            records.push(mem::take(&mut record));
+            if limit == Some(records.len()) {
+                return records;
+            }
+        } else {
+            field.push(c);
+        }
+    }
+    if !record.is_empty() || !field.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records
+}
+
+/// The first delimiter that splits the first rows into the same number of
+/// columns. As most text contains spaces, a space only counts if there are
+/// several rows. If there is none, the text is a single column, which is
+/// represented by a tab.
+fn sniff_delimiter(text: &str) -> Delimiter {
+    Delimiter::ALL
+        .into_iter()
+        .find(|&delimiter| {
+            let rows = parse_records(text, delimiter.char(), Some(SNIFF_ROWS));
+            let columns = rows.first().map(Vec::len).unwrap_or_default();
+            columns > 1
+                && rows.iter().all(|row| row.len() == columns)
+                && (delimiter != Delimiter::Space || rows.len() > 1)
+        })
+        .unwrap_or(Delimiter::Tab)
+}
+
+/// True if there are further rows, and the cells of the first one are unique
+/// short labels, at least one of which names a field or other column type.
+fn looks_like_header(rows: &[Vec<String>], known_names: &HashSet<String>) -> bool {
+    let first = match rows.first() {
+        Some(first) if rows.len() > 1 => first,
+        _ => return false,
+    };
+    let mut seen = HashSet::new();
+    let plain_labels = first.iter().all(|cell| {
+        let label = cell.trim().to_lowercase();
+        !label.is_empty() && label.len() <= 50 && !label.contains('<') && seen.insert(label)
+    });
+    plain_labels && seen.iter().any(|label| known_names.contains(label))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        assert_eq!(
+            parse_records("a,\"b,\"\"c\"\"\nd\"\r\n\n e,f", ',', None),
+            vec![vec!["a", "b,\"c\"\nd"], vec![" e", "f"]]
+        );
+        assert_eq!(parse_records("a\t\n", '\t', None), vec![vec!["a", ""]]);
+    }
+
+    #[test]
+    fn sniffing() {
+        assert_eq!(sniff_delimiter("a\tb, c\nd\te"), Delimiter::Tab);
+        assert_eq!(sniff_delimiter("a;b, c\nd;e"), Delimiter::Semicolon);
+        assert_eq!(sniff_delimiter("a b\nc d"), Delimiter::Space);
+        // without a consistent delimiter, the text is a single column
+        assert_eq!(sniff_delimiter("a,b,c\nd,e"), Delimiter::Tab);
+        assert_eq!(sniff_delimiter("single column"), Delimiter::Tab);
+        assert_eq!(
+            sniff_delimiter("one column\nof text, with spaces"),
+            Delimiter::Tab
+        );
+        assert_eq!(sniff_delimiter("single"), Delimiter::Tab);
+
+        let known: HashSet<String> = ["front".to_string()].into_iter().collect();
+        let rows = |text| parse_records(text, ',', None);
+        assert!(looks_like_header(&rows("Front,Back\nfoo,bar"), &known));
+        assert!(!looks_like_header(&rows("Front,Back"), &known));
+        assert!(!looks_like_header(&rows("Front,\nfoo,bar"), &known));
+        assert!(!looks_like_header(&rows("foo,bar\nbaz,qux"), &known));
+    }
+}
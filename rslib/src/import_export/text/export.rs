@@ -0,0 +1,208 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::{
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use itertools::Itertools;
+use tempfile::NamedTempFile;
+
+use super::csv::Delimiter;
+use crate::{
+    browser_table::Column,
+    import_export::{update_progress, ExportProgress},
+    prelude::*,
+    search::SortMode,
+    text::html_to_text_line,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportColumn {
+    /// Rendered the way the browser shows it.
+    Browser(Column),
+    /// The field at this index, which is empty for notes with fewer fields.
+    Field(usize),
+}
+
+impl Collection {
+    /// Write a row for each note matching `search` into `out_path`,
+    /// returning the number of rows. If `strip_html` is true, HTML is removed
+    /// from field content.
+    pub fn export_note_csv(
+        &mut self,
+        search: &str,
+        columns: &[ExportColumn],
+        delimiter: Delimiter,
+        strip_html: bool,
+        out_path: impl AsRef<Path>,
+        progress_fn: impl FnMut(ExportProgress) -> bool,
+    ) -> Result<usize> {
+        let ids = self
+            .search_notes_unordered(search)?
+            .into_iter()
+            .map(|nid| nid.0)
+            .collect();
+        self.write_csv(
+            ids,
+            true,
+            columns,
+            delimiter,
+            strip_html,
+            out_path.as_ref(),
+            progress_fn,
+        )
+    }
+
+    /// Like [Collection::export_note_csv], with a row for each matching card.
+    pub fn export_card_csv(
+        &mut self,
+        search: &str,
+        columns: &[ExportColumn],
+        delimiter: Delimiter,
+        strip_html: bool,
+        out_path: impl AsRef<Path>,
+        progress_fn: impl FnMut(ExportProgress) -> bool,
+    ) -> Result<usize> {
+        let ids = self
+            .search_cards(search, SortMode::NoOrder)?
+            .into_iter()
+            .map(|cid| cid.0)
+            .collect();
+        self.write_csv(
+            ids,
+            false,
+            columns,
+            delimiter,
+            strip_html,
+            out_path.as_ref(),
+            progress_fn,
+        )
+    }
+
+    /// The file is only replaced once all rows have been written.
+    #[allow(clippy::too_many_arguments)]
+    fn write_csv(
+        &mut self,
+        ids: Vec<i64>,
+        notes_mode: bool,
+        columns: &[ExportColumn],
+        delimiter: Delimiter,
+        strip_html: bool,
+        out_path: &Path,
+        mut progress_fn: impl FnMut(ExportProgress) -> bool,
+    ) -> Result<usize> {
+        let out_dir = out_path
+            .parent()
+            .ok_or_else(|| AnkiError::invalid_input("invalid export path"))?;
+        let browser_columns: Vec<Column> = columns
+            .iter()
+            .filter_map(|column| match column {
+                ExportColumn::Browser(column) => Some(*column),
+                ExportColumn::Field(_) => None,
+            })
+            .collect();
+        let mut writer = BufWriter::new(NamedTempFile::new_in(out_dir)?);
+
+        for (idx, id) in ids.iter().enumerate() {
+            update_progress(&mut progress_fn, ExportProgress::Rows(idx))?;
+            let (texts, note) = self.browser_cell_texts(*id, notes_mode, &browser_columns)?;
+            let mut texts = texts.into_iter();
+            let row = columns.iter().map(|column| match column {
+                ExportColumn::Browser(_) => texts.next().unwrap_or_default(),
+                ExportColumn::Field(idx) => field_text(&note, *idx, strip_html),
+            });
+            write_row(&mut writer, row, delimiter.char())?;
+        }
+
+        writer
+            .into_inner()
+            .map_err(|err| err.into_error())?
+            .persist(out_path)?;
+        Ok(ids.len())
+    }
+}
+
+fn field_text(note: &Note, idx: usize, strip_html: bool) -> String {
+    let text = note
+        .fields()
+        .get(idx)
+        .map(String::as_str)
+        .unwrap_or_default();
+    if strip_html {
+        html_to_text_line(text).into()
+    } else {
+        text.to_string()
+    }
+}
+
+pub(super) fn write_row(
+    writer: &mut impl Write,
+    row: impl Iterator<Item = String>,
+    delimiter: char,
+) -> Result<()> {
+    let line = row
+        .map(|text| quote_if_needed(text, delimiter))
+        .join(&delimiter.to_string());
+    writeln!(writer, "{}", line)?;
+    Ok(())
+}
+
+/// Quote text that would otherwise be split up or changed on import.
+fn quote_if_needed(text: String, delimiter: char) -> String {
+    if text.contains(|c| c == delimiter || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::{collection::open_test_collection, import_export::text::csv::parse_records};
+
+    #[test]
+    fn exporting() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("notes.csv");
+        let mut col = open_test_collection();
+        let nt = col
+            .get_notetype_by_name("Basic (and reversed card)")?
+            .unwrap();
+        let mut note = nt.new_note();
+        note.set_field(0, "<b>front</b>")?;
+        note.set_field(1, "back, \"quoted\"\nline")?;
+        note.tags.push("foo".into());
+        col.add_note(&mut note, DeckId(1))?;
+
+        let columns = [
+            ExportColumn::Field(0),
+            ExportColumn::Field(1),
+            ExportColumn::Field(5),
+            ExportColumn::Browser(Column::Tags),
+            ExportColumn::Browser(Column::Cards),
+        ];
+        let count = col.export_note_csv("", &columns, Delimiter::Comma, true, &path, |_| true)?;
+        assert_eq!(count, 1);
+        let text = fs::read_to_string(&path)?;
+        assert_eq!(
+            parse_records(&text, ',', None),
+            vec![vec!["front", "back, \"quoted\" line", "", "foo", "2"]]
+        );
+
+        let count = col.export_card_csv("", &columns, Delimiter::Tab, false, &path, |_| true)?;
+        assert_eq!(count, 2);
+        let text = fs::read_to_string(&path)?;
+        let rows = parse_records(&text, '\t', None);
+        assert_eq!(rows[0][0], "<b>front</b>");
+        assert_eq!(rows[0][1], "back, \"quoted\"\nline");
+
+        Ok(())
+    }
+}
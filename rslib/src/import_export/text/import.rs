@@ -0,0 +1,500 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::Arc,
+};
+
+use super::csv::{parse_records, read_text_file, Delimiter};
+use crate::{
+    import_export::{update_progress, ImportProgress, LogNote, NoteLog, NoteMeta},
+    notetype::CardGenContext,
+    prelude::*,
+    text::{normalize_to_nfc, strip_html_preserving_media_filenames},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvImportOptions {
+    pub delimiter: Delimiter,
+    pub has_header: bool,
+    /// If false, field content is escaped, and line breaks are converted to
+    /// `<br>`.
+    pub is_html: bool,
+    /// Used for rows without a notetype column, or with an empty one.
+    pub notetype_id: NotetypeId,
+    /// Used for rows without a deck column, or with an empty one.
+    pub deck_id: DeckId,
+    /// The column of each field of the default notetype, if any. If empty,
+    /// and for rows using a different notetype, the columns not used for
+    /// anything else fill the fields in order.
+    pub field_columns: Vec<Option<usize>>,
+    /// A column of space-separated tags.
+    pub tags_column: Option<usize>,
+    /// A column of deck names. Missing decks are created.
+    pub deck_column: Option<usize>,
+    /// A column of notetype names or IDs.
+    pub notetype_column: Option<usize>,
+    pub guid_column: Option<usize>,
+    /// Added to all imported and updated notes.
+    pub global_tags: Vec<String>,
+    pub dupe_resolution: DupeResolution,
+}
+
+/// What to do with a row whose first field matches the first field of an
+/// existing note of the same notetype. Rows with the GUID of an existing note
+/// are never added.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DupeResolution {
+    Add,
+    Skip,
+    /// Replace the fields of the existing note, and add the row's tags.
+    Update,
+}
+
+#[derive(Debug)]
+enum DeckTarget {
+    Id(DeckId),
+    Name(String),
+}
+
+#[derive(Debug)]
+enum Action {
+    Add,
+    Update(NoteId),
+}
+
+/// A row that is going to be added or update an existing note.
+#[derive(Debug)]
+struct PlannedNote {
+    note: Note,
+    deck: DeckTarget,
+    action: Action,
+}
+
+/// The notes that importing a file would add or update, and a log of the
+/// rows that would be left out.
+struct Plan {
+    notes: Vec<PlannedNote>,
+    notetypes: HashMap<NotetypeId, Arc<Notetype>>,
+    log: NoteLog,
+}
+
+impl Collection {
+    /// Determine what importing the file would do, without changing the
+    /// collection. The notes that would be added have an ID of 0.
+    pub fn preview_csv_import(
+        &mut self,
+        path: impl AsRef<Path>,
+        options: &CsvImportOptions,
+    ) -> Result<NoteLog> {
+        let plan = self.plan_csv_import(path.as_ref(), options)?;
+        let mut log = plan.log;
+        for planned in plan.notes {
+            let log_note = LogNote::from(&planned.note);
+            match planned.action {
+                Action::Add => log.added.push(log_note),
+                Action::Update(nid) => log.updated.push(LogNote {
+                    id: nid,
+                    ..log_note
+                }),
+            }
+        }
+        Ok(log)
+    }
+
+    /// Add the rows of a CSV/TSV file as notes in a single undoable step.
+    pub fn import_csv(
+        &mut self,
+        path: impl AsRef<Path>,
+        options: &CsvImportOptions,
+        mut progress_fn: impl FnMut(ImportProgress) -> bool,
+    ) -> Result<OpOutput<NoteLog>> {
+        update_progress(&mut progress_fn, ImportProgress::File)?;
+        let plan = self.plan_csv_import(path.as_ref(), options)?;
+        self.transact(Op::Import, |col| col.apply_plan(plan, &mut progress_fn))
+    }
+
+    fn plan_csv_import(&mut self, path: &Path, options: &CsvImportOptions) -> Result<Plan> {
+        let text = read_text_file(path)?;
+        let mut rows = parse_records(&text, options.delimiter.char(), None);
+        if options.has_header && !rows.is_empty() {
+            rows.remove(0);
+        }
+
+        let mut planner = Planner::new(self, options)?;
+        for row in rows {
+            planner.plan_row(row)?;
+        }
+
+        Ok(Plan {
+            notes: planner.notes,
+            notetypes: planner
+                .notetypes_by_column
+                .into_values()
+                .flatten()
+                .chain([planner.default_notetype])
+                .map(|nt| (nt.id, nt))
+                .collect(),
+            log: planner.log,
+        })
+    }
+
+    fn apply_plan(
+        &mut self,
+        plan: Plan,
+        progress_fn: &mut impl FnMut(ImportProgress) -> bool,
+    ) -> Result<NoteLog> {
+        let usn = self.usn()?;
+        let normalize = self.get_config_bool(BoolKey::NormalizeNoteText);
+        let contexts: HashMap<NotetypeId, CardGenContext> = plan
+            .notetypes
+            .iter()
+            .map(|(ntid, nt)| {
+                let last_deck = self.get_last_deck_added_to_for_notetype(*ntid);
+                (*ntid, CardGenContext::new(nt, last_deck, usn))
+            })
+            .collect();
+        let mut deck_ids: HashMap<String, DeckId> = HashMap::new();
+        let mut log = plan.log;
+
+        for (idx, planned) in plan.notes.into_iter().enumerate() {
+            update_progress(progress_fn, ImportProgress::Notes(idx))?;
+            let ctx = contexts
+                .get(&planned.note.notetype_id)
+                .ok_or(AnkiError::NotFound)?;
+            let mut note = planned.note;
+            match planned.action {
+                Action::Add => {
+                    let did = match planned.deck {
+                        DeckTarget::Id(did) => did,
+                        DeckTarget::Name(name) => {
+                            self.deck_id_for_name(name, usn, &mut deck_ids)?
+                        }
+                    };
+                    self.add_note_inner(ctx, &mut note, did, normalize)?;
+                    log.added.push(LogNote::from(&note));
+                }
+                Action::Update(nid) => {
+                    let original = self.storage.get_note(nid)?.ok_or(AnkiError::NotFound)?;
+                    note.id = nid;
+                    note.guid = original.guid.clone();
+                    note.tags.extend(original.tags.iter().cloned());
+                    self.canonify_note_tags(&mut note, usn)?;
+                    if note.fields() == original.fields() && note.tags == original.tags {
+                        log.skipped.push(LogNote::from(&note));
+                        continue;
+                    }
+                    self.update_note_inner_generating_cards(
+                        ctx, &mut note, &original, true, normalize, true,
+                    )?;
+                    log.updated.push(LogNote::from(&note));
+                }
+            }
+        }
+
+        Ok(log)
+    }
+
+    /// Look up or create a normal deck. Falls back on the default deck if the
+    /// name belongs to a filtered deck.
+    fn deck_id_for_name(
+        &mut self,
+        name: String,
+        usn: Usn,
+        cache: &mut HashMap<String, DeckId>,
+    ) -> Result<DeckId> {
+        if let Some(did) = cache.get(&name) {
+            return Ok(*did);
+        }
+        let did = match self.get_deck_id(&name)? {
+            Some(did) if self.get_deck(did)?.map_or(false, |deck| deck.is_filtered()) => DeckId(1),
+            Some(did) => did,
+            None => {
+                let mut deck = Deck::new_normal();
+                deck.name = NativeDeckName::from_human_name(&name);
+                self.add_deck_inner(&mut deck, usn)?;
+                deck.id
+            }
+        };
+        cache.insert(name, did);
+        Ok(did)
+    }
+}
+
+struct Planner<'a> {
+    col: &'a mut Collection,
+    options: &'a CsvImportOptions,
+    default_notetype: Arc<Notetype>,
+    /// Notetype column content -> notetype, if one exists.
+    notetypes_by_column: HashMap<String, Option<Arc<Notetype>>>,
+    /// Columns that don't hold field content.
+    meta_columns: HashSet<usize>,
+    normalize: bool,
+    existing_by_guid: HashMap<String, NoteMeta>,
+    /// Notetype and stripped first field of the rows that will be added, to
+    /// catch duplicates inside the file.
+    added_first_fields: HashSet<(NotetypeId, String)>,
+    added_guids: HashSet<String>,
+    notes: Vec<PlannedNote>,
+    log: NoteLog,
+}
+
+impl<'a> Planner<'a> {
+    fn new(col: &'a mut Collection, options: &'a CsvImportOptions) -> Result<Self> {
+        let default_notetype = col
+            .get_notetype(options.notetype_id)?
+            .ok_or(AnkiError::NotFound)?;
+        let meta_columns = [
+            options.tags_column,
+            options.deck_column,
+            options.notetype_column,
+            options.guid_column,
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        Ok(Planner {
+            normalize: col.get_config_bool(BoolKey::NormalizeNoteText),
+            existing_by_guid: col.storage.note_metas_by_guid()?,
+            col,
+            options,
+            default_notetype,
+            notetypes_by_column: HashMap::new(),
+            meta_columns,
+            added_first_fields: HashSet::new(),
+            added_guids: HashSet::new(),
+            notes: vec![],
+            log: NoteLog::default(),
+        })
+    }
+
+    fn plan_row(&mut self, row: Vec<String>) -> Result<()> {
+        let notetype = match self.notetype_for_row(&row)? {
+            Some(notetype) => notetype,
+            None => {
+                self.log.missing_notetype.push(log_row(row));
+                return Ok(());
+            }
+        };
+        let mut note = notetype.new_note();
+        *note.fields_mut() = self.fields_for_row(&row, &notetype);
+        note.tags = self.tags_for_row(&row);
+        let guid = column_text(&row, self.options.guid_column);
+        if !guid.is_empty() {
+            note.guid = guid.to_string();
+        }
+        let deck = match column_text(&row, self.options.deck_column) {
+            "" => DeckTarget::Id(self.options.deck_id),
+            name => DeckTarget::Name(name.to_string()),
+        };
+
+        if let Some(action) = self.action_for_note(&note)? {
+            self.notes.push(PlannedNote { note, deck, action });
+        }
+        Ok(())
+    }
+
+    fn notetype_for_row(&mut self, row: &[String]) -> Result<Option<Arc<Notetype>>> {
+        let text = column_text(row, self.options.notetype_column);
+        if text.is_empty() {
+            return Ok(Some(self.default_notetype.clone()));
+        }
+        if let Some(notetype) = self.notetypes_by_column.get(text) {
+            return Ok(notetype.clone());
+        }
+        let mut notetype = self.col.get_notetype_by_name(text)?;
+        if notetype.is_none() {
+            if let Ok(ntid) = text.parse() {
+                notetype = self.col.get_notetype(NotetypeId(ntid))?;
+            }
+        }
+        self.notetypes_by_column
+            .insert(text.to_string(), notetype.clone());
+        Ok(notetype)
+    }
+
+    fn fields_for_row(&self, row: &[String], notetype: &Notetype) -> Vec<String> {
+        let columns: Vec<Option<usize>> =
+            if notetype.id == self.options.notetype_id && !self.options.field_columns.is_empty() {
+                self.options.field_columns.clone()
+            } else {
+                (0..row.len())
+                    .filter(|column| !self.meta_columns.contains(column))
+                    .map(Some)
+                    .collect()
+            };
+        (0..notetype.fields.len())
+            .map(|idx| {
+                let text = column_text(row, columns.get(idx).copied().flatten());
+                if self.options.is_html {
+                    text.to_string()
+                } else {
+                    htmlescape::encode_minimal(text).replace('\n', "<br>")
+                }
+            })
+            .collect()
+    }
+
+    fn tags_for_row(&self, row: &[String]) -> Vec<String> {
+        column_text(row, self.options.tags_column)
+            .split_whitespace()
+            .map(ToString::to_string)
+            .chain(self.options.global_tags.iter().cloned())
+            .collect()
+    }
+
+    /// Decide whether the note should be added or update an existing note,
+    /// logging it if it is left out.
+    fn action_for_note(&mut self, note: &Note) -> Result<Option<Action>> {
+        if let Some(existing) = self.existing_by_guid.get(&note.guid) {
+            if existing.notetype_id != note.notetype_id {
+                self.log.conflicting.push(LogNote::from(note));
+                return Ok(None);
+            }
+            if self.options.dupe_resolution == DupeResolution::Update {
+                return Ok(Some(Action::Update(existing.id)));
+            }
+            self.log.skipped.push(LogNote {
+                id: existing.id,
+                fields: note.fields().clone(),
+            });
+            return Ok(None);
+        }
+
+        let first_field = if self.normalize {
+            normalize_to_nfc(&note.fields()[0])
+        } else {
+            note.fields()[0].as_str().into()
+        };
+        let stripped = strip_html_preserving_media_filenames(&first_field);
+        if stripped.trim().is_empty() {
+            self.log.empty_first_field.push(LogNote::from(note));
+            return Ok(None);
+        }
+
+        let duplicate = self
+            .col
+            .duplicate_note_ids(&stripped, note)?
+            .into_iter()
+            .next();
+        let key = (note.notetype_id, stripped.into_owned());
+        let action = match (self.options.dupe_resolution, duplicate) {
+            (DupeResolution::Update, Some(nid)) => Some(Action::Update(nid)),
+            (DupeResolution::Skip, Some(nid)) => {
+                self.log.skipped.push(LogNote {
+                    id: nid,
+                    fields: note.fields().clone(),
+                });
+                None
+            }
+            (DupeResolution::Add, _) => Some(Action::Add),
+            (_, None) if self.added_first_fields.contains(&key) => {
+                self.log.skipped.push(LogNote::from(note));
+                None
+            }
+            (_, None) => Some(Action::Add),
+        };
+
+        if let Some(Action::Add) = action {
+            if !self.added_guids.insert(note.guid.clone()) {
+                self.log.skipped.push(LogNote::from(note));
+                return Ok(None);
+            }
+            self.added_first_fields.insert(key);
+        }
+        Ok(action)
+    }
+}
+
+fn column_text(row: &[String], column: Option<usize>) -> &str {
+    column
+        .and_then(|column| row.get(column))
+        .map(|text| text.as_str())
+        .unwrap_or_default()
+}
+
+fn log_row(row: Vec<String>) -> LogNote {
+    LogNote {
+        id: NoteId(0),
+        fields: row,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::collection::open_test_collection;
+
+    fn options(col: &mut Collection) -> CsvImportOptions {
+        CsvImportOptions {
+            delimiter: Delimiter::Comma,
+            has_header: true,
+            is_html: false,
+            notetype_id: col.get_notetype_by_name("Basic").unwrap().unwrap().id,
+            deck_id: DeckId(1),
+            field_columns: vec![],
+            tags_column: Some(2),
+            deck_column: Some(3),
+            notetype_column: None,
+            guid_column: None,
+            global_tags: vec!["imported".into()],
+            dupe_resolution: DupeResolution::Skip,
+        }
+    }
+
+    #[test]
+    fn importing() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("notes.csv");
+        fs::write(
+            &path,
+            "Front,Back,Tags,Deck\n\
+             one,1 < 2,foo,Numbers\n\
+             two,\"line\nbreak\",,\n\
+             one,again,,\n\
+             ,empty,,\n",
+        )?;
+        let mut col = open_test_collection();
+        let mut options = options(&mut col);
+
+        let preview = col.preview_csv_import(&path, &options)?;
+        assert_eq!(preview.added.len(), 2);
+        assert_eq!(preview.skipped.len(), 1);
+        assert_eq!(preview.empty_first_field.len(), 1);
+        assert_eq!(col.storage.total_notes()?, 0);
+
+        let log = col.import_csv(&path, &options, |_| true)?.output;
+        assert_eq!(log.added.len(), 2);
+        let note = col.storage.get_note(log.added[0].id)?.unwrap();
+        assert_eq!(note.fields()[1], "1 &lt; 2");
+        assert_eq!(note.tags, vec!["foo", "imported"]);
+        let card = &col.storage.all_cards_of_note(note.id)?[0];
+        assert_eq!(col.get_deck_id("Numbers")?, Some(card.deck_id));
+        let note = col.storage.get_note(log.added[1].id)?.unwrap();
+        assert_eq!(note.fields()[1], "line<br>break");
+
+        // matching rows update existing notes when requested; unchanged
+        // notes are skipped
+        options.dupe_resolution = DupeResolution::Update;
+        let log = col.import_csv(&path, &options, |_| true)?.output;
+        assert_eq!(
+            (log.added.len(), log.updated.len(), log.skipped.len()),
+            (0, 1, 2)
+        );
+        let note = col.storage.get_note(log.updated[0].id)?.unwrap();
+        assert_eq!(note.fields()[1], "again");
+
+        // the import is a single undoable step
+        col.undo()?;
+        col.undo()?;
+        assert_eq!(col.storage.total_notes()?, 0);
+
+        Ok(())
+    }
+}
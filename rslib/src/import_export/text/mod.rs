@@ -0,0 +1,14 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+mod csv;
+mod export;
+mod import;
+mod revlog;
+
+pub use self::{
+    csv::{CsvMetadata, Delimiter},
+    export::ExportColumn,
+    import::{CsvImportOptions, DupeResolution},
+    revlog::RevlogExportFormat,
+};
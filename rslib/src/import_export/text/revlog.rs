@@ -0,0 +1,270 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::{
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use chrono::{FixedOffset, NaiveDate, SecondsFormat};
+use serde::Serialize;
+use tempfile::NamedTempFile;
+
+use super::export::write_row;
+use crate::{
+    import_export::{update_progress, ExportProgress},
+    prelude::*,
+    revlog::{RevlogEntry, RevlogReviewKind},
+    scheduler::timing::SchedTimingToday,
+    search::SortMode,
+    storage::RevlogCardIds,
+};
+
+const SECS_PER_DAY: i64 = 86_400;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RevlogExportFormat {
+    /// Comma-separated, with a header row.
+    Csv,
+    /// One JSON object per line.
+    Ndjson,
+}
+
+/// A review with decoded values, and the day it counts towards.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct RevlogExportRow {
+    revlog_id: i64,
+    card_id: i64,
+    note_id: i64,
+    deck_id: i64,
+    notetype_id: i64,
+    template_idx: u16,
+    /// Local time of the review, in RFC 3339 format.
+    reviewed_at: String,
+    /// The scheduler's day number, as used by the due dates of review cards.
+    /// Reviews before the rollover hour count towards the previous day.
+    day: i64,
+    /// The local date of `day`, in YYYY-MM-DD format.
+    date: String,
+    /// 0 for manual rescheduling, otherwise 1 (again) to 4 (easy).
+    button: u8,
+    kind: &'static str,
+    interval_secs: u32,
+    last_interval_secs: u32,
+    /// The ease as a ratio, eg 2.5, or 0 if not applicable.
+    ease: f32,
+    taken_millis: u32,
+}
+
+const CSV_HEADER: [&str; 15] = [
+    "revlog_id",
+    "card_id",
+    "note_id",
+    "deck_id",
+    "notetype_id",
+    "template_idx",
+    "reviewed_at",
+    "day",
+    "date",
+    "button",
+    "kind",
+    "interval_secs",
+    "last_interval_secs",
+    "ease",
+    "taken_millis",
+];
+
+impl Collection {
+    /// Write the review history of the cards matching `search` into
+    /// `out_path` in chronological order, returning the number of entries.
+    pub fn export_revlog(
+        &mut self,
+        search: &str,
+        format: RevlogExportFormat,
+        out_path: impl AsRef<Path>,
+        mut progress_fn: impl FnMut(ExportProgress) -> bool,
+    ) -> Result<usize> {
+        let out_path = out_path.as_ref();
+        let out_dir = out_path
+            .parent()
+            .ok_or_else(|| AnkiError::invalid_input("invalid export path"))?;
+        let days = DayConverter::new(self)?;
+        self.search_cards_into_table(search, SortMode::NoOrder)?;
+        let entries = self
+            .storage
+            .get_revlog_entries_with_card_ids_for_searched_cards();
+        self.storage.clear_searched_cards_table()?;
+        let entries = entries?;
+
+        let mut writer = BufWriter::new(NamedTempFile::new_in(out_dir)?);
+        if format == RevlogExportFormat::Csv {
+            write_row(&mut writer, CSV_HEADER.iter().map(ToString::to_string), ',')?;
+        }
+        for (idx, (entry, ids)) in entries.iter().enumerate() {
+            update_progress(&mut progress_fn, ExportProgress::Rows(idx))?;
+            let row = RevlogExportRow::new(entry, ids, &days);
+            match format {
+                RevlogExportFormat::Csv => write_row(&mut writer, row.csv_values(), ',')?,
+                RevlogExportFormat::Ndjson => {
+                    serde_json::to_writer(&mut writer, &row)?;
+                    writer.write_all(b"\n")?;
+                }
+            }
+        }
+        writer
+            .into_inner()
+            .map_err(|err| err.into_error())?
+            .persist(out_path)?;
+
+        Ok(entries.len())
+    }
+}
+
+/// Maps review timestamps to the scheduler's days, using the current timing
+/// and UTC offset.
+struct DayConverter {
+    timing: SchedTimingToday,
+    utc_offset: FixedOffset,
+    rollover_secs: i64,
+}
+
+impl DayConverter {
+    fn new(col: &mut Collection) -> Result<Self> {
+        Ok(Self {
+            timing: col.timing_today()?,
+            utc_offset: col.local_utc_offset_for_user()?,
+            rollover_secs: col.rollover_for_current_scheduler()? as i64 * 3600,
+        })
+    }
+
+    fn day(&self, stamp: TimestampSecs) -> i64 {
+        let days_ago = (self.timing.next_day_at.0 - 1 - stamp.0).div_euclid(SECS_PER_DAY);
+        self.timing.days_elapsed as i64 - days_ago
+    }
+
+    fn date(&self, stamp: TimestampSecs) -> NaiveDate {
+        stamp
+            .adding_secs(-self.rollover_secs)
+            .datetime(self.utc_offset)
+            .date()
+            .naive_local()
+    }
+}
+
+impl RevlogExportRow {
+    fn new(entry: &RevlogEntry, ids: &RevlogCardIds, days: &DayConverter) -> Self {
+        let stamp = entry.id.as_secs();
+        Self {
+            revlog_id: entry.id.0,
+            card_id: entry.cid.0,
+            note_id: ids.note_id.0,
+            deck_id: ids.deck_id.0,
+            notetype_id: ids.notetype_id.0,
+            template_idx: ids.template_idx,
+            reviewed_at: stamp
+                .datetime(days.utc_offset)
+                .to_rfc3339_opts(SecondsFormat::Secs, false),
+            day: days.day(stamp),
+            date: days.date(stamp).to_string(),
+            button: entry.button_chosen,
+            kind: kind_name(entry.review_kind),
+            interval_secs: entry.interval_secs(),
+            last_interval_secs: entry.last_interval_secs(),
+            ease: entry.ease_factor as f32 / 1000.0,
+            taken_millis: entry.taken_millis,
+        }
+    }
+
+    /// In the order of [CSV_HEADER].
+    fn csv_values(&self) -> impl Iterator<Item = String> {
+        [
+            self.revlog_id.to_string(),
+            self.card_id.to_string(),
+            self.note_id.to_string(),
+            self.deck_id.to_string(),
+            self.notetype_id.to_string(),
+            self.template_idx.to_string(),
+            self.reviewed_at.clone(),
+            self.day.to_string(),
+            self.date.clone(),
+            self.button.to_string(),
+            self.kind.to_string(),
+            self.interval_secs.to_string(),
+            self.last_interval_secs.to_string(),
+            self.ease.to_string(),
+            self.taken_millis.to_string(),
+        ]
+        .into_iter()
+    }
+}
+
+fn kind_name(kind: RevlogReviewKind) -> &'static str {
+    match kind {
+        RevlogReviewKind::Learning => "learning",
+        RevlogReviewKind::Review => "review",
+        RevlogReviewKind::Relearning => "relearning",
+        RevlogReviewKind::Filtered => "filtered",
+        RevlogReviewKind::Manual => "manual",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::{collection::open_test_collection, revlog::RevlogId};
+
+    #[test]
+    fn exporting() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("revlog.ndjson");
+        let mut col = open_test_collection();
+        let nt = col.get_notetype_by_name("Basic")?.unwrap();
+        let mut note = nt.new_note();
+        note.set_field(0, "front")?;
+        col.add_note(&mut note, DeckId(1))?;
+        let card = col.storage.all_cards_of_note(note.id)?.remove(0);
+        let now = TimestampMillis::now().0;
+        for id in [now - SECS_PER_DAY * 1000, now] {
+            col.storage.add_revlog_entry(
+                &RevlogEntry {
+                    id: RevlogId(id),
+                    cid: card.id,
+                    button_chosen: 3,
+                    interval: 3,
+                    last_interval: -600,
+                    ease_factor: 2500,
+                    review_kind: RevlogReviewKind::Review,
+                    ..Default::default()
+                },
+                false,
+            )?;
+        }
+        let today = col.timing_today()?.days_elapsed as i64;
+
+        let count = col.export_revlog("", RevlogExportFormat::Ndjson, &path, |_| true)?;
+        assert_eq!(count, 2);
+        let rows: Vec<serde_json::Value> = fs::read_to_string(&path)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<std::result::Result<_, _>>()?;
+        assert_eq!(rows[0]["day"], today - 1);
+        assert_eq!(rows[1]["day"], today);
+        assert_eq!(rows[1]["note_id"], note.id.0);
+        assert_eq!(rows[1]["interval_secs"], 3 * SECS_PER_DAY);
+        assert_eq!(rows[1]["last_interval_secs"], 600);
+        assert_eq!(rows[1]["ease"], 2.5);
+        assert_eq!(rows[1]["kind"], "review");
+
+        let count = col.export_revlog("is:new", RevlogExportFormat::Csv, &path, |_| true)?;
+        assert_eq!(count, 2);
+        let text = fs::read_to_string(&path)?;
+        assert_eq!(text.lines().count(), 3);
+        assert!(text.starts_with("revlog_id,card_id,"));
+
+        Ok(())
+    }
+}
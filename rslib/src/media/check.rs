@@ -469,7 +469,11 @@ fn normalize_and_maybe_rename_files<'a>(
     field
 }
 
-fn rename_media_ref_in_field(field: &str, media_ref: &MediaRef, new_name: &str) -> String {
+pub(crate) fn rename_media_ref_in_field(
+    field: &str,
+    media_ref: &MediaRef,
+    new_name: &str,
+) -> String {
     let new_name = if matches!(media_ref.fname_decoded, Cow::Owned(_)) {
         // filename had quoted characters like &amp; - need to re-encode
         htmlescape::encode_minimal(new_name)
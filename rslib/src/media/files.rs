@@ -169,28 +169,29 @@ pub fn add_data_to_folder_uniquely<'a, P>(
 where
     P: AsRef<Path>,
 {
-    let normalized_name = normalize_filename(desired_name);
-
-    let mut target_path = folder.as_ref().join(normalized_name.as_ref());
-
-    let existing_file_hash = existing_file_sha1(&target_path)?;
-    if existing_file_hash.is_none() {
-        // no file with that name exists yet
+    let chosen_name = unique_filename_in_folder(folder.as_ref(), desired_name, sha1)?;
+    let target_path = folder.as_ref().join(chosen_name.as_ref());
+    if !target_path.exists() {
         fs::write(&target_path, data)?;
-        return Ok(normalized_name);
     }
+    Ok(chosen_name)
+}
 
-    if existing_file_hash.unwrap() == sha1 {
-        // existing file has same checksum, nothing to do
-        return Ok(normalized_name);
+/// The filename [add_data_to_folder_uniquely()] would use for data with the
+/// given checksum, without writing anything.
+pub(crate) fn unique_filename_in_folder<'a>(
+    folder: &Path,
+    desired_name: &'a str,
+    sha1: [u8; 20],
+) -> io::Result<Cow<'a, str>> {
+    let normalized_name = normalize_filename(desired_name);
+    match existing_file_sha1(&folder.join(normalized_name.as_ref()))? {
+        // no file with that name exists yet, or it has the same checksum
+        None => Ok(normalized_name),
+        Some(existing) if existing == sha1 => Ok(normalized_name),
+        // give it a unique name based on its hash
+        Some(_) => Ok(add_hash_suffix_to_file_stem(normalized_name.as_ref(), &sha1).into()),
     }
-
-    // give it a unique name based on its hash
-    let hashed_name = add_hash_suffix_to_file_stem(normalized_name.as_ref(), &sha1);
-    target_path.set_file_name(&hashed_name);
-
-    fs::write(&target_path, data)?;
-    Ok(hashed_name.into())
 }
 
 /// Convert foo.jpg into foo-abcde12345679.jpg
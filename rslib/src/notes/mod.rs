@@ -320,7 +320,7 @@ fn invalid_char_for_field(c: char) -> bool {
 }
 
 impl Collection {
-    fn canonify_note_tags(&mut self, note: &mut Note, usn: Usn) -> Result<()> {
+    pub(crate) fn canonify_note_tags(&mut self, note: &mut Note, usn: Usn) -> Result<()> {
         if !note.tags.is_empty() {
             let tags = std::mem::take(&mut note.tags);
             note.tags = self.canonify_tags(tags, usn)?.0;
@@ -565,14 +565,22 @@ impl Collection {
     }
 
     fn is_duplicate(&self, first_field: &str, note: &Note) -> Result<bool> {
+        Ok(!self.duplicate_note_ids(first_field, note)?.is_empty())
+    }
+
+    /// Other notes of the same notetype whose first field matches
+    /// `first_field`, which must have had its HTML stripped.
+    pub(crate) fn duplicate_note_ids(&self, first_field: &str, note: &Note) -> Result<Vec<NoteId>> {
         let csum = field_checksum(first_field);
         Ok(self
             .storage
             .note_fields_by_checksum(note.notetype_id, csum)?
             .into_iter()
-            .any(|(nid, field)| {
-                nid != note.id && strip_html_preserving_media_filenames(&field) == first_field
-            }))
+            .filter(|(nid, field)| {
+                *nid != note.id && strip_html_preserving_media_filenames(field) == first_field
+            })
+            .map(|(nid, _)| nid)
+            .collect())
     }
 
     fn field_cloze_check(&mut self, note: &Note) -> Result<NoteFieldsState> {
@@ -16,6 +16,7 @@ pub enum Op {
     ClearUnusedTags,
     EmptyFilteredDeck,
     FindAndReplace,
+    Import,
     RebuildFilteredDeck,
     RemoveDeck,
     RemoveNote,
@@ -24,6 +25,8 @@ pub enum Op {
     RenameDeck,
     ReparentDeck,
     RenameTag,
+    RescheduleBacklog,
+    RescheduleForVacation,
     ReparentTag,
     ScheduleAsNew,
     SetCardDeck,
@@ -58,6 +61,8 @@ impl Op {
             Op::RenameDeck => tr.actions_rename_deck(),
             Op::ScheduleAsNew => tr.actions_forget_card(),
             Op::SetDueDate => tr.actions_set_due_date(),
+            Op::RescheduleBacklog => tr.actions_reschedule_backlog(),
+            Op::RescheduleForVacation => tr.actions_reschedule_for_vacation(),
             Op::Suspend => tr.studying_suspend(),
             Op::UnburyUnsuspend => tr.actions_unbury_unsuspend(),
             Op::UpdateCard => tr.actions_update_card(),
@@ -68,6 +73,7 @@ impl Op {
             Op::SetCardDeck => tr.browsing_change_deck(),
             Op::SetFlag => tr.actions_set_flag(),
             Op::FindAndReplace => tr.browsing_find_and_replace(),
+            Op::Import => tr.actions_import(),
             Op::ClearUnusedTags => tr.browsing_clear_unused_tags(),
             Op::SortCards => tr.browsing_reschedule(),
             Op::RenameTag => tr.actions_rename_tag(),
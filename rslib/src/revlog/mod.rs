@@ -78,13 +78,23 @@ impl Default for RevlogReviewKind {
 
 impl RevlogEntry {
     pub(crate) fn interval_secs(&self) -> u32 {
-        u32::try_from(if self.interval > 0 {
-            self.interval.saturating_mul(86_400)
-        } else {
-            self.interval.saturating_mul(-1)
-        })
-        .unwrap()
+        interval_to_secs(self.interval)
     }
+
+    pub(crate) fn last_interval_secs(&self) -> u32 {
+        interval_to_secs(self.last_interval)
+    }
+}
+
+/// Convert a revlog interval, which is in days if positive and in seconds if
+/// negative.
+fn interval_to_secs(interval: i32) -> u32 {
+    u32::try_from(if interval > 0 {
+        interval.saturating_mul(86_400)
+    } else {
+        interval.saturating_mul(-1)
+    })
+    .unwrap()
 }
 
 impl Collection {
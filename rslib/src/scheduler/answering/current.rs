@@ -61,6 +61,11 @@ impl CardStateUpdater {
         let lapses = self.card.lapses;
         let ease_factor = self.card.ease_factor();
         let remaining_steps = self.card.remaining_steps();
+        let memory_state = if self.config.memory_model().is_some() {
+            self.card.memory_state()
+        } else {
+            None
+        };
 
         match self.card.ctype {
             CardType::New => NormalState::New(NewState {
@@ -70,6 +75,7 @@ impl CardStateUpdater {
                 LearnState {
                     scheduled_secs: self.learn_steps().current_delay_secs(remaining_steps),
                     remaining_steps,
+                    memory_state,
                 }
             }
             .into(),
@@ -80,12 +86,14 @@ impl CardStateUpdater {
                 ease_factor,
                 lapses,
                 leeched: false,
+                memory_state,
             }
             .into(),
             CardType::Relearn => RelearnState {
                 learning: LearnState {
                     scheduled_secs: self.relearn_steps().current_delay_secs(remaining_steps),
                     remaining_steps,
+                    memory_state: None,
                 },
                 review: ReviewState {
                     scheduled_days: interval,
@@ -93,6 +101,7 @@ impl CardStateUpdater {
                     ease_factor,
                     lapses,
                     leeched: false,
+                    memory_state,
                 },
             }
             .into(),
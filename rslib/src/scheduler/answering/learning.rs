@@ -19,6 +19,7 @@ impl CardStateUpdater {
         self.card.ctype = CardType::New;
         self.card.queue = CardQueue::New;
         self.card.due = next.position as i32;
+        self.card.set_memory_state(None);
 
         RevlogEntryPartial::new(current, next.into(), 0.0, self.secs_until_rollover())
     }
@@ -30,6 +31,7 @@ impl CardStateUpdater {
     ) -> RevlogEntryPartial {
         self.card.remaining_steps = next.remaining_steps;
         self.card.ctype = CardType::Learn;
+        self.card.set_memory_state(next.memory_state);
 
         let interval = next
             .interval_kind()
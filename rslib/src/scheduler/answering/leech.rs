@@ -0,0 +1,270 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use super::{CardStateUpdater, Rating};
+use crate::{
+    card::{CardQueue, CardType},
+    deckconfig::{DeckConfig, LeechAction},
+    decks::NativeDeckName,
+    prelude::*,
+    revlog::RevlogReviewKind,
+    scheduler::states::{
+        steps::LearningSteps, CardState, FilteredState, LearnState, NormalState, RelearnState,
+    },
+};
+
+/// Used when the preset doesn't name a deck to move leeches to.
+const DEFAULT_LEECH_DECK: &str = "Leeches";
+
+impl CardStateUpdater {
+    /// If the preset has leech relearning steps, turn the state a leech lapsed
+    /// into into the first of them, and mark the card so that the following
+    /// steps are taken from them too.
+    pub(super) fn relearning_leech_state(&mut self, state: CardState) -> CardState {
+        let steps = LearningSteps::new(&self.config.inner.leech_relearn_steps);
+        let delay = match steps.again_delay_secs_relearn() {
+            Some(delay) => delay,
+            None => return state,
+        };
+        let relearn = |normal: NormalState| -> NormalState {
+            let review = match normal {
+                NormalState::Review(review) => review,
+                NormalState::Relearning(relearn) => relearn.review,
+                other => return other,
+            };
+            RelearnState {
+                learning: LearnState {
+                    remaining_steps: steps.remaining_for_failed(),
+                    scheduled_secs: delay,
+                    memory_state: None,
+                },
+                review,
+            }
+            .into()
+        };
+        self.card.set_leech_relearning(true);
+        match state {
+            CardState::Normal(normal) => relearn(normal).into(),
+            CardState::Filtered(FilteredState::Rescheduling(mut filtered)) => {
+                filtered.original_state = relearn(filtered.original_state);
+                filtered.into()
+            }
+            CardState::Filtered(FilteredState::Preview(_)) => state,
+        }
+    }
+}
+
+impl Collection {
+    /// True if answering the card with `rating` is a lapse that makes it lapse
+    /// at least `leech_window_lapses` times within its last
+    /// `leech_window_reviews` reviews. Reviews before the card was last reset
+    /// are not counted. Must be called before the answer is logged.
+    pub(super) fn lapsed_too_often(
+        &self,
+        card: &Card,
+        rating: Rating,
+        config: &DeckConfig,
+    ) -> Result<bool> {
+        let reviews = config.inner.leech_window_reviews as usize;
+        let lapses = config.inner.leech_window_lapses as usize;
+        if reviews == 0
+            || lapses == 0
+            || card.ctype != CardType::Review
+            || !matches!(rating, Rating::Again)
+        {
+            return Ok(false);
+        }
+        let mut entries = self.storage.get_revlog_entries_for_card(card.id)?;
+        entries.sort_unstable_by_key(|entry| entry.id);
+        let earlier_lapses = entries
+            .iter()
+            .rev()
+            .take_while(|entry| {
+                !(entry.review_kind == RevlogReviewKind::Manual && entry.interval == 0)
+            })
+            .filter(|entry| entry.review_kind == RevlogReviewKind::Review)
+            .take(reviews - 1)
+            .filter(|entry| entry.button_chosen == 1)
+            .count();
+
+        Ok(earlier_lapses + 1 >= lapses)
+    }
+
+    /// Tag the leech's note, and apply the preset's leech action to the card.
+    /// Relearning leeches is taken care of when their new state is applied.
+    pub(super) fn apply_leech_action(
+        &mut self,
+        card: &mut Card,
+        config: &DeckConfig,
+        usn: Usn,
+    ) -> Result<()> {
+        match config.inner.leech_action() {
+            LeechAction::Suspend => card.queue = CardQueue::Suspended,
+            LeechAction::TagOnly | LeechAction::Relearn => (),
+            LeechAction::MoveToDeck => {
+                let name = match config.inner.leech_deck.trim() {
+                    "" => DEFAULT_LEECH_DECK,
+                    name => name,
+                };
+                let deck = self.get_or_add_leech_deck(name, usn)?;
+                if !deck.is_filtered() {
+                    if card.original_deck_id.0 != 0 {
+                        card.original_deck_id = deck.id;
+                    } else {
+                        card.deck_id = deck.id;
+                    }
+                    self.clear_study_queues();
+                }
+            }
+            LeechAction::SetFlag => {
+                // 0 means no flag was chosen
+                if (1..=7).contains(&config.inner.leech_flag) {
+                    card.set_flag(config.inner.leech_flag as u8);
+                }
+            }
+            LeechAction::Reset => {
+                let original = card.clone();
+                card.schedule_as_new(self.get_and_update_next_card_position()?);
+                card.set_memory_state(None);
+                card.set_leech_relearning(false);
+                self.log_manually_scheduled_review(card, &original, usn)?;
+            }
+        }
+
+        self.add_leech_tag(card.note_id)
+    }
+
+    /// Like [Collection::get_or_create_normal_deck()], but part of the
+    /// current undoable operation.
+    fn get_or_add_leech_deck(&mut self, human_name: &str, usn: Usn) -> Result<Deck> {
+        let name = NativeDeckName::from_human_name(human_name);
+        if let Some(did) = self.storage.get_deck_id(name.as_native_str())? {
+            self.storage.get_deck(did).map(|opt| opt.unwrap())
+        } else {
+            let mut deck = Deck::new_normal();
+            deck.name = name;
+            self.add_deck_inner(&mut deck, usn)?;
+            Ok(deck)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        collection::open_test_collection,
+        deckconfig::DeckConfigId,
+        revlog::{RevlogEntry, RevlogId},
+    };
+
+    #[test]
+    fn rolling_lapses() -> Result<()> {
+        let mut col = open_test_collection();
+        let card = col.add_review_card(0, 10)?;
+        let mut config = DeckConfig::default();
+        config.inner.leech_window_reviews = 4;
+        config.inner.leech_window_lapses = 2;
+
+        let log = |col: &mut Collection, button_chosen: u8, review_kind: RevlogReviewKind| {
+            col.storage
+                .add_revlog_entry(
+                    &RevlogEntry {
+                        id: RevlogId::new(),
+                        cid: card.id,
+                        button_chosen,
+                        review_kind,
+                        ..Default::default()
+                    },
+                    true,
+                )
+                .unwrap();
+        };
+        // a lapse three reviews ago is within the window
+        log(&mut col, 1, RevlogReviewKind::Review);
+        log(&mut col, 3, RevlogReviewKind::Review);
+        log(&mut col, 3, RevlogReviewKind::Review);
+        assert!(col.lapsed_too_often(&card, Rating::Again, &config)?);
+        assert!(!col.lapsed_too_often(&card, Rating::Good, &config)?);
+        // relearning steps don't count, so the lapse is now four reviews ago
+        log(&mut col, 1, RevlogReviewKind::Relearning);
+        log(&mut col, 3, RevlogReviewKind::Review);
+        assert!(!col.lapsed_too_often(&card, Rating::Again, &config)?);
+
+        // lapses before a reset are forgotten
+        config.inner.leech_window_reviews = 10;
+        assert!(col.lapsed_too_often(&card, Rating::Again, &config)?);
+        log(&mut col, 0, RevlogReviewKind::Manual);
+        assert!(!col.lapsed_too_often(&card, Rating::Again, &config)?);
+
+        config.inner.leech_window_reviews = 0;
+        config.inner.leech_window_lapses = 1;
+        assert!(!col.lapsed_too_often(&card, Rating::Again, &config)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn leech_actions() -> Result<()> {
+        let mut col = open_test_collection();
+        let mut conf = col.get_deck_config(DeckConfigId(1), false)?.unwrap();
+        // lapsed cards go straight back to review, unless relearnt as leeches
+        conf.inner.relearn_steps = vec![];
+        conf.inner.leech_threshold = 1;
+        conf.inner.leech_action = LeechAction::MoveToDeck as i32;
+        conf.inner.leech_deck = "Hard Cards".into();
+        col.add_or_update_deck_config(&mut conf)?;
+
+        let card = col.add_review_card(0, 10)?;
+        col.answer_again();
+        let card = col.storage.get_card(card.id)?.unwrap();
+        let deck = col.get_deck(card.deck_id)?.unwrap();
+        assert_eq!(deck.human_name(), "Hard Cards");
+        let note = col.storage.get_note(card.note_id)?.unwrap();
+        assert_eq!(note.tags, vec!["leech"]);
+
+        // without a chosen flag, leeches are only tagged
+        conf.inner.leech_action = LeechAction::SetFlag as i32;
+        col.add_or_update_deck_config(&mut conf)?;
+        let card = col.add_review_card(0, 10)?;
+        col.answer_again();
+        assert_eq!(col.storage.get_card(card.id)?.unwrap().flags, 0);
+
+        conf.inner.leech_flag = 4;
+        conf.inner.leech_relearn_steps = vec![1.0, 10.0];
+        col.add_or_update_deck_config(&mut conf)?;
+        let card = col.add_review_card(0, 10)?;
+        col.answer_again();
+        let card = col.storage.get_card(card.id)?.unwrap();
+        assert_eq!(card.flags, 4);
+        assert_eq!(card.queue, CardQueue::Review);
+        assert!(!card.leech_relearning());
+
+        conf.inner.leech_action = LeechAction::Relearn as i32;
+        col.add_or_update_deck_config(&mut conf)?;
+        let card = col.add_review_card(0, 10)?;
+        col.answer_again();
+        let mut card = col.storage.get_card(card.id)?.unwrap();
+        assert_eq!(card.ctype, CardType::Relearn);
+        assert!(card.leech_relearning());
+        assert_eq!(card.remaining_steps, 2);
+        // the leech steps are used until the card graduates
+        col.answer_good();
+        card = col.storage.get_card(card.id)?.unwrap();
+        assert_eq!(card.remaining_steps, 1);
+        col.answer_good();
+        card = col.storage.get_card(card.id)?.unwrap();
+        assert_eq!(card.ctype, CardType::Review);
+        assert!(!card.leech_relearning());
+
+        conf.inner.leech_action = LeechAction::Reset as i32;
+        col.add_or_update_deck_config(&mut conf)?;
+        let card = col.add_review_card(0, 10)?;
+        col.answer_again();
+        let card = col.storage.get_card(card.id)?.unwrap();
+        assert_eq!(card.ctype, CardType::New);
+        assert_eq!(card.queue, CardQueue::New);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::collections::HashMap;
+
+use crate::{card::CardQueue, prelude::*, scheduler::states::DueCounts};
+
+/// The number of review cards due on each upcoming day, by home deck.
+/// Gathered when first needed, and kept up to date as cards are answered.
+/// Other operations that change cards discard it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LoadBalancer {
+    /// The day the counts were gathered on.
+    today: u32,
+    decks: HashMap<DeckId, HashMap<u32, u32>>,
+}
+
+impl LoadBalancer {
+    fn due_counts(&self, deck_id: DeckId) -> DueCounts {
+        DueCounts::new(
+            self.decks
+                .get(&deck_id)
+                .map(|days| {
+                    days.iter()
+                        .filter_map(|(&day, &count)| {
+                            day.checked_sub(self.today)
+                                .map(|interval| (interval, count))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        )
+    }
+
+    fn adjust(&mut self, card: &Card, delta: i32) {
+        if let Some((deck_id, day)) = review_due_day(card) {
+            let count = self
+                .decks
+                .entry(deck_id)
+                .or_default()
+                .entry(day)
+                .or_default();
+            *count = (*count as i32 + delta).max(0) as u32;
+        }
+    }
+}
+
+/// The home deck and due day of a card in the review queue.
+fn review_due_day(card: &Card) -> Option<(DeckId, u32)> {
+    if card.queue != CardQueue::Review {
+        return None;
+    }
+    let due = if card.original_deck_id.0 != 0 {
+        card.original_due
+    } else {
+        card.due
+    };
+    Some((card.original_or_current_deck_id(), due.max(0) as u32))
+}
+
+impl Collection {
+    /// The upcoming review counts of the card's home deck.
+    pub(super) fn due_counts_for_card(&mut self, card: &Card, today: u32) -> Result<DueCounts> {
+        let deck_id = card.original_or_current_deck_id();
+        if let Some(balancer) = &self.state.load_balancer {
+            if balancer.today == today {
+                return Ok(balancer.due_counts(deck_id));
+            }
+        }
+        let balancer = LoadBalancer {
+            today,
+            decks: self.storage.review_due_counts_by_deck(today)?,
+        };
+        let counts = balancer.due_counts(deck_id);
+        self.state.load_balancer = Some(balancer);
+        Ok(counts)
+    }
+
+    /// Move the card from its old due day to its new one.
+    pub(super) fn update_load_balancer(&mut self, original: &Card, card: &Card) {
+        if let Some(balancer) = &mut self.state.load_balancer {
+            balancer.adjust(original, -1);
+            balancer.adjust(card, 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::collection::open_test_collection;
+
+    #[test]
+    fn due_counts() -> Result<()> {
+        let mut col = open_test_collection();
+        let mut cards = vec![];
+        for due in [5, 5, 7] {
+            cards.push(col.add_review_card(due, 1)?);
+        }
+
+        let counts = col.due_counts_for_card(&cards[0], 2)?;
+        assert_eq!(
+            counts,
+            DueCounts::new([(3, 2), (5, 1)].into_iter().collect())
+        );
+
+        // answering moves the card to its new day
+        let original = cards[0].clone();
+        cards[0].due = 7;
+        col.update_load_balancer(&original, &cards[0]);
+        let counts = col.due_counts_for_card(&cards[0], 2)?;
+        assert_eq!(
+            counts,
+            DueCounts::new([(3, 1), (5, 2)].into_iter().collect())
+        );
+
+        // other decks don't count, and counts are gathered again on a new day
+        cards[0].deck_id = DeckId(2);
+        assert_eq!(col.due_counts_for_card(&cards[0], 2)?, DueCounts::default());
+        let counts = col.due_counts_for_card(&cards[1], 6)?;
+        assert_eq!(counts, DueCounts::new([(1, 1)].into_iter().collect()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn answering_and_undoing() -> Result<()> {
+        let mut col = open_test_collection();
+        let today = col.timing_today()?.days_elapsed;
+        let card = col.add_review_card(today as i32, 1)?;
+        col.clear_study_queues();
+        col.get_next_card()?;
+
+        // cards due today aren't counted, and answering keeps the gathered
+        // counts up to date
+        assert_eq!(col.due_counts_for_card(&card, today)?, DueCounts::default());
+        col.answer_good();
+        assert!(col.state.load_balancer.is_some());
+        assert_ne!(col.due_counts_for_card(&card, today)?, DueCounts::default());
+        // undoing the answer moves the card back
+        col.undo()?;
+        assert_eq!(col.due_counts_for_card(&card, today)?, DueCounts::default());
+
+        Ok(())
+    }
+}
@@ -3,18 +3,24 @@
 
 mod current;
 mod learning;
+mod leech;
+mod load_balancer;
 mod preview;
 mod relearning;
 mod review;
 mod revlog;
+mod siblings;
 
 use rand::{prelude::*, rngs::StdRng};
 
+pub(crate) use load_balancer::LoadBalancer;
 use revlog::RevlogEntryPartial;
 
 use super::{
+    policy::PolicyInput,
     states::{
-        steps::LearningSteps, CardState, FilteredState, NextCardStates, NormalState, StateContext,
+        steps::LearningSteps, CardState, DueCounts, EasyDays, FilteredState, NextCardStates,
+        NormalState, StateContext,
     },
     timespan::answer_button_time_collapsible,
     timing::SchedTimingToday,
@@ -53,6 +59,9 @@ struct CardStateUpdater {
     timing: SchedTimingToday,
     now: TimestampSecs,
     fuzz_seed: Option<u64>,
+    /// Only gathered if fuzz is applied.
+    due_counts: Option<DueCounts>,
+    easy_days: Option<EasyDays>,
 }
 
 impl CardStateUpdater {
@@ -62,24 +71,16 @@ impl CardStateUpdater {
     pub(crate) fn state_context(&self) -> StateContext<'_> {
         StateContext {
             fuzz_factor: get_fuzz_factor(self.fuzz_seed),
-            steps: self.learn_steps(),
-            graduating_interval_good: self.config.inner.graduating_interval_good,
-            graduating_interval_easy: self.config.inner.graduating_interval_easy,
-            initial_ease_factor: self.config.inner.initial_ease,
-            hard_multiplier: self.config.inner.hard_multiplier,
-            easy_multiplier: self.config.inner.easy_multiplier,
-            interval_multiplier: self.config.inner.interval_multiplier,
-            maximum_review_interval: self.config.inner.maximum_review_interval,
-            leech_threshold: self.config.inner.leech_threshold,
-            relearn_steps: self.relearn_steps(),
-            lapse_multiplier: self.config.inner.lapse_multiplier,
-            minimum_lapse_interval: self.config.inner.minimum_lapse_interval,
+            load_balancer: self.due_counts.as_ref(),
+            easy_days: self.easy_days,
             in_filtered_deck: self.deck.is_filtered(),
             preview_step: if let DeckKind::Filtered(deck) = &self.deck.kind {
                 deck.preview_delay
             } else {
                 0
             },
+            relearn_steps: self.relearn_steps(),
+            ..self.config.state_context()
         }
     }
 
@@ -87,8 +88,14 @@ impl CardStateUpdater {
         LearningSteps::new(&self.config.inner.learn_steps)
     }
 
+    /// Leeches being relearnt keep using the leech relearning steps until
+    /// they graduate.
     fn relearn_steps(&self) -> LearningSteps<'_> {
-        LearningSteps::new(&self.config.inner.relearn_steps)
+        if self.card.leech_relearning() && !self.config.inner.leech_relearn_steps.is_empty() {
+            LearningSteps::new(&self.config.inner.leech_relearn_steps)
+        } else {
+            LearningSteps::new(&self.config.inner.relearn_steps)
+        }
     }
 
     fn secs_until_rollover(&self) -> u32 {
@@ -152,8 +159,8 @@ impl CardStateUpdater {
             NormalState::Relearning(next) => self.apply_relearning_state(current, next),
         };
 
-        if next.leeched() && self.config.inner.leech_action() == LeechAction::Suspend {
-            self.card.queue = CardQueue::Suspended;
+        if !matches!(next, NormalState::Relearning(_)) && self.card.leech_relearning() {
+            self.card.set_leech_relearning(false);
         }
 
         revlog
@@ -188,7 +195,25 @@ impl Collection {
         let ctx = self.card_state_updater(card)?;
         let current = ctx.current_card_state();
         let state_ctx = ctx.state_context();
-        Ok(current.next_states(&state_ctx))
+        let states = current.next_states(&state_ctx);
+        if let Some(policy) = self.state.scheduling_policies.get(ctx.config.id) {
+            let mut revlog = self.storage.get_revlog_entries_for_card(ctx.card.id)?;
+            revlog.sort_unstable_by_key(|entry| entry.id);
+            let input = PolicyInput {
+                card: &ctx.card,
+                revlog: &revlog,
+                current,
+                memory_state: ctx.card.memory_state(),
+                options: (&state_ctx).into(),
+                days_elapsed: ctx.timing.days_elapsed,
+            };
+            Ok(NextCardStates {
+                current,
+                ..policy.next_states(&input, states)
+            })
+        } else {
+            Ok(states)
+        }
     }
 
     /// Describe the next intervals, to display on the answer buttons.
@@ -259,16 +284,29 @@ impl Collection {
                 current_state, answer.current_state,
             )));
         }
-        let revlog_partial = updater.apply_study_state(current_state, answer.new_state)?;
+        let leeched = answer.new_state.leeched()
+            || self.lapsed_too_often(&updater.card, answer.rating, &updater.config)?;
+        let new_state = if leeched && updater.config.inner.leech_action() == LeechAction::Relearn {
+            updater.relearning_leech_state(answer.new_state)
+        } else {
+            answer.new_state
+        };
+        let revlog_partial = updater.apply_study_state(current_state, new_state)?;
         self.add_partial_revlog(revlog_partial, usn, answer)?;
 
         self.update_deck_stats_from_answer(usn, answer, &updater, original.queue)?;
         self.maybe_bury_siblings(&original, &updater.config)?;
         let timing = updater.timing;
+        let sibling_spacing = updater.config.inner.sibling_spacing_days;
+        let leech_config = leeched.then(|| updater.config.clone());
         let mut card = updater.into_card();
-        self.update_card_inner(&mut card, original, usn)?;
-        if answer.new_state.leeched() {
-            self.add_leech_tag(card.note_id)?;
+        if let Some(config) = leech_config {
+            self.apply_leech_action(&mut card, &config, usn)?;
+        }
+        self.update_card_inner(&mut card, original.clone(), usn)?;
+        self.update_load_balancer(&original, &card);
+        if sibling_spacing > 0 {
+            self.space_siblings(&card, sibling_spacing, timing.days_elapsed, usn)?;
         }
 
         self.update_queues_after_answering_card(&card, timing)
@@ -337,8 +375,21 @@ impl Collection {
             .get_deck(card.deck_id)?
             .ok_or(AnkiError::NotFound)?;
         let config = self.home_deck_config(deck.config_id(), card.original_deck_id)?;
+        let fuzz_seed = get_fuzz_seed(&card);
+        let due_counts = if fuzz_seed.is_some() {
+            Some(self.due_counts_for_card(&card, timing.days_elapsed)?)
+        } else {
+            None
+        };
+        let easy_days = if config.inner.easy_days.is_empty() {
+            None
+        } else {
+            EasyDays::new(&config.inner.easy_days, self.weekday_today()?)
+        };
         Ok(CardStateUpdater {
-            fuzz_seed: get_fuzz_seed(&card),
+            fuzz_seed,
+            due_counts,
+            easy_days,
             card,
             deck,
             config,
@@ -374,6 +425,7 @@ impl Collection {
 #[cfg(test)]
 pub mod test_helpers {
     use super::*;
+    use crate::card::CardType;
 
     pub struct PostAnswerState {
         pub card_id: CardId,
@@ -381,6 +433,32 @@ pub mod test_helpers {
     }
 
     impl Collection {
+        /// Add a Basic note, and make its card a review card.
+        pub(crate) fn add_review_card(&mut self, due: i32, interval: u32) -> Result<Card> {
+            let nt = self.get_notetype_by_name("Basic")?.unwrap();
+            let mut note = nt.new_note();
+            note.set_field(0, "front")?;
+            self.add_note(&mut note, DeckId(1))?;
+            let mut card = self.storage.all_cards_of_note(note.id)?.remove(0);
+            self.make_review_card(&mut card, due, interval)?;
+            Ok(card)
+        }
+
+        /// Save `card` as a review card due on day `due`, with the default ease.
+        pub(crate) fn make_review_card(
+            &mut self,
+            card: &mut Card,
+            due: i32,
+            interval: u32,
+        ) -> Result<()> {
+            card.ctype = CardType::Review;
+            card.queue = CardQueue::Review;
+            card.due = due;
+            card.interval = interval;
+            card.ease_factor = 2500;
+            self.storage.update_card(card)
+        }
+
         pub(crate) fn answer_again(&mut self) -> PostAnswerState {
             self.answer(|states| states.again, Rating::Again).unwrap()
         }
@@ -422,7 +500,7 @@ pub mod test_helpers {
 
 /// Return a consistent seed for a given card at a given number of reps.
 /// If in test environment, disable fuzzing.
-fn get_fuzz_seed(card: &Card) -> Option<u64> {
+pub(super) fn get_fuzz_seed(card: &Card) -> Option<u64> {
     if *crate::PYTHON_UNIT_TESTS || cfg!(test) {
         None
     } else {
@@ -432,7 +510,7 @@ fn get_fuzz_seed(card: &Card) -> Option<u64> {
 
 /// Return a fuzz factor from the range `0.0..1.0`, using the provided seed.
 /// None if seed is None.
-fn get_fuzz_factor(seed: Option<u64>) -> Option<f32> {
+pub(super) fn get_fuzz_factor(seed: Option<u64>) -> Option<f32> {
     seed.map(|s| StdRng::seed_from_u64(s).gen_range(0.0..1.0))
 }
 
@@ -440,7 +518,11 @@ fn get_fuzz_factor(seed: Option<u64>) -> Option<f32> {
 mod test {
     use super::*;
     use crate::{
-        card::CardType, collection::open_test_collection, deckconfig::ReviewMix, search::SortMode,
+        card::CardType,
+        collection::open_test_collection,
+        deckconfig::{ReviewMix, SchedulingModel},
+        scheduler::states::memory::DEFAULT_MEMORY_WEIGHTS,
+        search::SortMode,
     };
 
     fn current_state(col: &mut Collection, card_id: CardId) -> CardState {
@@ -565,6 +647,54 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn memory_model() -> Result<()> {
+        let mut col = open_test_collection();
+        if col.timing_today()?.near_cutoff() {
+            return Ok(());
+        }
+        let mut conf = col.get_deck_config(DeckConfigId(1), false)?.unwrap();
+        conf.inner.set_scheduling_model(SchedulingModel::Memory);
+        col.storage.update_deck_conf(&conf)?;
+        let nt = col.get_notetype_by_name("Basic")?.unwrap();
+        let mut note = nt.new_note();
+        col.add_note(&mut note, DeckId(1))?;
+
+        // graduating with easy; the interval matches the initial stability
+        let post_answer = col.answer_easy();
+        let card = col.storage.get_card(post_answer.card_id)?.unwrap();
+        let state = card.memory_state().unwrap();
+        assert_eq!(state.stability, DEFAULT_MEMORY_WEIGHTS[3]);
+        assert_eq!(card.interval, 6);
+        assert_eq!(
+            post_answer.new_state.review_state().unwrap().scheduled_days,
+            current_state(&mut col, post_answer.card_id)
+                .review_state()
+                .unwrap()
+                .scheduled_days
+        );
+
+        // a successful review on the due date increases the stability
+        col.storage.db.execute_batch("update cards set due=0")?;
+        col.clear_study_queues();
+        let post_answer = col.answer_good();
+        let card = col.storage.get_card(post_answer.card_id)?.unwrap();
+        let next_state = card.memory_state().unwrap();
+        assert!(next_state.stability > state.stability);
+        assert!((card.interval as f32 - next_state.stability).abs() <= 1.0);
+
+        // switching back to SM-2 clears the memory state on the next answer
+        conf.inner.set_scheduling_model(SchedulingModel::Sm2);
+        col.storage.update_deck_conf(&conf)?;
+        col.storage.db.execute_batch("update cards set due=0")?;
+        col.clear_study_queues();
+        let post_answer = col.answer_good();
+        let card = col.storage.get_card(post_answer.card_id)?.unwrap();
+        assert_eq!(card.memory_state(), None);
+
+        Ok(())
+    }
+
     fn v3_test_collection(cards: usize) -> Result<(Collection, Vec<CardId>)> {
         let mut col = open_test_collection();
         let nt = col.get_notetype_by_name("Basic")?.unwrap();
@@ -18,6 +18,7 @@ impl CardStateUpdater {
         self.card.ctype = CardType::Relearn;
         self.card.lapses = next.review.lapses;
         self.card.ease_factor = (next.review.ease_factor * 1000.0).round() as u16;
+        self.card.set_memory_state(next.review.memory_state);
 
         let interval = next
             .interval_kind()
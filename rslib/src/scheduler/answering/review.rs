@@ -20,6 +20,7 @@ impl CardStateUpdater {
         self.card.ease_factor = (next.ease_factor * 1000.0).round() as u16;
         self.card.lapses = next.lapses;
         self.card.remaining_steps = 0;
+        self.card.set_memory_state(next.memory_state);
 
         RevlogEntryPartial::new(
             current,
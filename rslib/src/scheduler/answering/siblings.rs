@@ -0,0 +1,131 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use crate::{
+    card::{CardQueue, CardType},
+    prelude::*,
+};
+
+impl Collection {
+    /// Move future reviews of the card's siblings that fall within `spacing`
+    /// days of the card's next review, so that seeing one card does not give
+    /// away the answer to another. Cards not in the review queue are treated
+    /// as due today. Siblings due today are left to burying.
+    pub(super) fn space_siblings(
+        &mut self,
+        card: &Card,
+        spacing: u32,
+        today: u32,
+        usn: Usn,
+    ) -> Result<()> {
+        let due = if card.queue == CardQueue::Review {
+            card.due
+        } else {
+            today as i32
+        };
+        for mut sibling in self.storage.all_cards_of_note(card.note_id)? {
+            if sibling.id == card.id
+                || sibling.ctype != CardType::Review
+                || sibling.queue == CardQueue::Suspended
+                || sibling.original_deck_id.0 != 0
+                || sibling.due <= today as i32
+            {
+                continue;
+            }
+            if let Some(spaced_due) = spaced_due(sibling.due, due, spacing as i32, today as i32) {
+                let original = sibling.clone();
+                sibling.due = spaced_due;
+                self.update_card_inner(&mut sibling, original.clone(), usn)?;
+                self.update_load_balancer(&original, &sibling);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// If `sibling_due` is less than `spacing` days away from `due`, the day it
+/// should be moved to instead. It goes to whichever side is closer, but never
+/// earlier than tomorrow.
+fn spaced_due(sibling_due: i32, due: i32, spacing: i32, today: i32) -> Option<i32> {
+    if (sibling_due - due).abs() >= spacing {
+        return None;
+    }
+    let earlier = due - spacing;
+    let later = due + spacing;
+    Some(
+        if earlier > today && sibling_due - earlier <= later - sibling_due {
+            earlier
+        } else {
+            later
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::collection::open_test_collection;
+
+    #[test]
+    fn spaced_days() {
+        // far enough apart
+        assert_eq!(spaced_due(20, 10, 5, 0), None);
+        assert_eq!(spaced_due(5, 10, 5, 0), None);
+        // moved to the closer side
+        assert_eq!(spaced_due(12, 10, 5, 0), Some(15));
+        assert_eq!(spaced_due(7, 10, 5, 0), Some(5));
+        // but not into the past
+        assert_eq!(spaced_due(7, 10, 5, 5), Some(15));
+    }
+
+    fn add_note_with_siblings(col: &mut Collection) -> Result<Vec<Card>> {
+        let nt = col
+            .get_notetype_by_name("Basic (and reversed card)")?
+            .unwrap();
+        let mut note = nt.new_note();
+        note.set_field(0, "front")?;
+        note.set_field(1, "back")?;
+        col.add_note(&mut note, DeckId(1))?;
+        col.storage.all_cards_of_note(note.id)
+    }
+
+    #[test]
+    fn spacing_siblings() -> Result<()> {
+        let mut col = open_test_collection();
+        let mut cards = add_note_with_siblings(&mut col)?;
+        for (card, due) in cards.iter_mut().zip([10, 12]) {
+            col.make_review_card(card, due, 1)?;
+        }
+
+        col.transact_no_undo(|col| col.space_siblings(&cards[0], 5, 0, Usn(-1)))?;
+        assert_eq!(col.storage.get_card(cards[1].id)?.unwrap().due, 15);
+
+        // suspended siblings are left alone
+        cards[1].queue = CardQueue::Suspended;
+        cards[1].due = 12;
+        col.storage.update_card(&cards[1])?;
+        col.transact_no_undo(|col| col.space_siblings(&cards[0], 5, 0, Usn(-1)))?;
+        assert_eq!(col.storage.get_card(cards[1].id)?.unwrap().due, 12);
+
+        Ok(())
+    }
+
+    #[test]
+    fn holding_back_new_siblings() -> Result<()> {
+        let mut col = open_test_collection();
+        let mut conf = col.storage.get_deck_config(DeckConfigId(1))?.unwrap();
+        conf.inner.new_sibling_minimum_interval = 7;
+        col.storage.update_deck_conf(&conf)?;
+        let mut cards = add_note_with_siblings(&mut col)?;
+        col.make_review_card(&mut cards[0], 100, 3)?;
+        assert!(col.get_next_card()?.is_none());
+
+        cards[0].interval = 7;
+        col.storage.update_card(&cards[0])?;
+        col.clear_study_queues();
+        assert_eq!(col.get_next_card()?.unwrap().card.id, cards[1].id);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use crate::{card::predicted_recall, prelude::*};
+
+impl Collection {
+    /// Spread overdue reviews over the coming days, starting today. Cards that
+    /// are most likely still remembered come first. The backlog is shared
+    /// evenly across `days` days, but no day gets more than `daily_cap`
+    /// reviews, including the ones already due on it; cards that don't fit
+    /// overflow onto the following days at the same cap. Intervals are left
+    /// alone. Returns the number of cards rescheduled.
+    pub fn reschedule_backlog(&mut self, days: u32, daily_cap: u32) -> Result<OpOutput<usize>> {
+        if days == 0 || daily_cap == 0 {
+            return Err(AnkiError::invalid_input(
+                "recovery needs at least one day and review",
+            ));
+        }
+        let usn = self.usn()?;
+        let today = self.timing_today()?.days_elapsed;
+        self.transact(Op::RescheduleBacklog, |col| {
+            let cards = match today.checked_sub(1) {
+                Some(yesterday) => col.storage.review_cards_due_between(0, yesterday)?,
+                None => vec![],
+            };
+            let mut due_counts: HashMap<u32, u32> = HashMap::new();
+            for days in col
+                .storage
+                .review_due_counts_by_deck(today.saturating_sub(1))?
+                .into_values()
+            {
+                for (day, count) in days {
+                    *due_counts.entry(day).or_default() += count;
+                }
+            }
+
+            let mut cards: Vec<_> = cards
+                .into_iter()
+                .map(|card| {
+                    let elapsed_days = today as i32 - (card.due - card.interval as i32);
+                    let recall = predicted_recall(
+                        &card.data,
+                        card.interval,
+                        card.ease_factor(),
+                        elapsed_days as f32,
+                    );
+                    (recall, card)
+                })
+                .collect();
+            cards.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+
+            let even_share = (cards.len() as u32 + days - 1) / days;
+            let mut plan = BacklogPlan {
+                day: 0,
+                assigned: 0,
+                days,
+                daily_cap,
+                even_share,
+                due_counts,
+                today,
+            };
+            let count = cards.len();
+            for (_, mut card) in cards {
+                let days_from_today = plan.next_day();
+                let original = card.clone();
+                card.set_due_date(today, days_from_today, card.ease_factor(), false);
+                col.log_manually_scheduled_review(&card, &original, usn)?;
+                col.update_card_inner(&mut card, original, usn)?;
+            }
+
+            Ok(count)
+        })
+    }
+}
+
+struct BacklogPlan {
+    /// Relative to today.
+    day: u32,
+    /// Overdue cards placed on `day` so far.
+    assigned: u32,
+    days: u32,
+    daily_cap: u32,
+    even_share: u32,
+    /// Reviews already due on each day.
+    due_counts: HashMap<u32, u32>,
+    today: u32,
+}
+
+impl BacklogPlan {
+    /// The day the next card should be due on, in days from today.
+    fn next_day(&mut self) -> u32 {
+        while self.assigned >= self.capacity(self.day) {
+            self.day += 1;
+            self.assigned = 0;
+        }
+        self.assigned += 1;
+        self.day
+    }
+
+    fn capacity(&self, day: u32) -> u32 {
+        let already_due = self
+            .due_counts
+            .get(&(self.today + day))
+            .copied()
+            .unwrap_or_default();
+        let capacity = self.daily_cap.saturating_sub(already_due);
+        if day < self.days {
+            capacity.min(self.even_share)
+        } else {
+            capacity
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::collection::open_test_collection;
+
+    #[test]
+    fn plan() {
+        let mut plan = BacklogPlan {
+            day: 0,
+            assigned: 0,
+            days: 3,
+            daily_cap: 3,
+            even_share: 2,
+            due_counts: [(11, 2), (13, 4)].into_iter().collect(),
+            today: 10,
+        };
+        let days: Vec<_> = (0..8).map(|_| plan.next_day()).collect();
+        // an even share of two on the first three days, except for the busy
+        // second day, and the cap after them, except for the full fourth day
+        assert_eq!(days, [0, 0, 1, 2, 2, 4, 4, 4]);
+    }
+
+    #[test]
+    fn backlog() -> Result<()> {
+        let mut col = open_test_collection();
+        // an overdue card can't be due before the collection was created
+        col.set_creation_stamp(TimestampSecs(TimestampSecs::now().0 - 100 * 86_400))?;
+        let today = col.timing_today()?.days_elapsed;
+        let mut cards = vec![];
+        // the longer a card is overdue, the less likely it is remembered
+        for overdue in [50, 5, 20] {
+            cards.push(col.add_review_card(today as i32 - overdue, 10)?.id);
+        }
+
+        assert_eq!(col.reschedule_backlog(2, 1)?.output, 3);
+        let due: Vec<_> = cards
+            .iter()
+            .map(|&cid| col.storage.get_card(cid).unwrap().unwrap())
+            .map(|card| (card.due - today as i32, card.interval))
+            .collect();
+        assert_eq!(due, [(2, 10), (0, 10), (1, 10)]);
+
+        // nothing is overdue any more
+        assert_eq!(col.reschedule_backlog(1, 10)?.output, 0);
+        assert!(col.reschedule_backlog(0, 10).is_err());
+
+        Ok(())
+    }
+}
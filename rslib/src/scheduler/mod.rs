@@ -1,22 +1,33 @@
 // Copyright: Ankitects Pty Ltd and contributors
 // License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
 
-use crate::{collection::Collection, config::SchedulerVersion, error::Result, prelude::*};
+use crate::{
+    collection::Collection,
+    config::{SchedulerVersion, Weekday},
+    error::Result,
+    prelude::*,
+};
 
 pub mod answering;
+mod backlog;
 pub mod bury_and_suspend;
 pub(crate) mod congrats;
 pub(crate) mod filtered;
 mod learning;
 pub mod new;
+pub mod optimizer;
+pub mod policy;
 pub(crate) mod queue;
+mod reschedule;
 mod reviews;
+pub mod simulator;
 pub mod states;
 pub mod timespan;
 pub mod timing;
 mod upgrade;
+mod vacation;
 
-use chrono::FixedOffset;
+use chrono::{Datelike, FixedOffset};
 pub use reviews::parse_due_date_str;
 use timing::{
     sched_timing_today, v1_creation_date_adjusted_to_hour, v1_rollover_from_creation_stamp,
@@ -48,6 +59,13 @@ impl Collection {
         self.scheduler_info().map(|info| info.timing)
     }
 
+    /// The weekday the current scheduler day started on.
+    pub(crate) fn weekday_today(&mut self) -> Result<Weekday> {
+        let day_start = self.timing_today()?.next_day_at.adding_secs(-86_400);
+        let utc_offset = self.local_utc_offset_for_user()?;
+        Ok(day_start.datetime(utc_offset).weekday().into())
+    }
+
     pub fn current_due_day(&mut self, delta: i32) -> Result<u32> {
         Ok(((self.timing_today()?.days_elapsed as i32) + delta).max(0) as u32)
     }
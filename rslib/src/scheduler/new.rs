@@ -14,7 +14,7 @@ use crate::{
 };
 
 impl Card {
-    fn schedule_as_new(&mut self, position: u32) {
+    pub(crate) fn schedule_as_new(&mut self, position: u32) {
         self.remove_from_filtered_deck_before_reschedule();
         self.due = position as i32;
         self.ctype = CardType::New;
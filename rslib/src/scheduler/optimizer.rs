@@ -0,0 +1,408 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+//! Recommends SM-2 preset values by fitting a forgetting curve to the review
+//! history. Recall is modelled as `TARGET_RETENTION ^ (elapsed / stability)`,
+//! where stability is estimated in two ways:
+//! - as a multiple of the interval the card was scheduled for, which tells us
+//!   whether the current intervals are too short or too long, and
+//! - from the number of passed and failed reviews, which tells us how much
+//!   stability grows with each pass and shrinks with each lapse.
+
+use crate::{
+    import_export::update_progress,
+    prelude::*,
+    revlog::{RevlogEntry, RevlogId, RevlogReviewKind},
+    search::SortMode,
+};
+
+/// The retention SM-2 presets are assumed to aim for.
+const TARGET_RETENTION: f32 = 0.9;
+const MINIMUM_REVIEWS: usize = 50;
+const FITTING_ROUNDS: u32 = 8;
+/// Intervals are grouped into buckets doubling in size to determine the
+/// maximum interval. A bucket is only considered if it has this many reviews.
+const MINIMUM_BUCKET_REVIEWS: usize = 20;
+const MINIMUM_BUCKET_RETENTION: f32 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptimizerProgress {
+    pub current: u32,
+    pub total: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FitMetrics {
+    pub log_loss: f32,
+    /// Root mean square error of the predicted probability of recall.
+    pub rmse: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptimizedSm2Params {
+    pub initial_ease: f32,
+    pub interval_multiplier: f32,
+    pub lapse_multiplier: f32,
+    pub maximum_review_interval: u32,
+    /// The number of reviews the parameters were fitted to.
+    pub review_count: u32,
+    /// How well the intervals of the current settings predict recall.
+    pub current: FitMetrics,
+    /// How well the intervals of the recommended settings predict recall.
+    pub optimized: FitMetrics,
+}
+
+/// A review of a card in the review queue.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ReviewItem {
+    elapsed_days: f32,
+    scheduled_days: f32,
+    /// Passed reviews since the card graduated.
+    passes: u32,
+    lapses: u32,
+    recalled: bool,
+}
+
+/// Stability depending on the review count.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GrowthModel {
+    initial_stability: f32,
+    pass_factor: f32,
+    lapse_factor: f32,
+}
+
+impl Collection {
+    /// Fit the review history of the cards matching `search`, and recommend
+    /// replacements for the values of the given preset.
+    pub fn optimize_sm2_params(
+        &mut self,
+        search: &str,
+        config_id: DeckConfigId,
+        mut progress_fn: impl FnMut(OptimizerProgress) -> bool,
+    ) -> Result<OptimizedSm2Params> {
+        let config = self
+            .get_deck_config(config_id, false)?
+            .ok_or(AnkiError::NotFound)?;
+        self.search_cards_into_table(search, SortMode::NoOrder)?;
+        let entries = self
+            .storage
+            .get_revlog_entries_for_searched_cards_in_card_order();
+        self.storage.clear_searched_cards_table()?;
+        let items = review_items(&entries?);
+        if items.len() < MINIMUM_REVIEWS {
+            return Err(AnkiError::invalid_input(format!(
+                "at least {} reviews are required",
+                MINIMUM_REVIEWS
+            )));
+        }
+
+        let total = FITTING_ROUNDS + 1;
+        update_progress(&mut progress_fn, OptimizerProgress { current: 0, total })?;
+        let interval_scale = fit_interval_scale(&items);
+        let mut growth = GrowthModel::default();
+        for round in 0..FITTING_ROUNDS {
+            update_progress(
+                &mut progress_fn,
+                OptimizerProgress {
+                    current: round + 1,
+                    total,
+                },
+            )?;
+            growth.improve(&items);
+        }
+
+        let interval_multiplier =
+            (config.inner.interval_multiplier * interval_scale).clamp(0.5, 2.0);
+        // SM-2 multiplies the interval by the ease and interval multiplier
+        // after each pass
+        let initial_ease = (growth.pass_factor / interval_multiplier).clamp(1.31, 5.0);
+        Ok(OptimizedSm2Params {
+            initial_ease,
+            interval_multiplier,
+            lapse_multiplier: growth.lapse_factor.clamp(0.0, 1.0),
+            maximum_review_interval: maximum_interval(&items)
+                .unwrap_or(config.inner.maximum_review_interval)
+                .min(config.inner.maximum_review_interval),
+            review_count: items.len() as u32,
+            current: FitMetrics::new(&items, |item| item.scheduled_days),
+            optimized: FitMetrics::new(&items, |item| item.scheduled_days * interval_scale),
+        })
+    }
+}
+
+/// Extract the reviews of cards that had a day-based interval. `entries`
+/// must be sorted by card, then time.
+fn review_items(entries: &[RevlogEntry]) -> Vec<ReviewItem> {
+    let mut items = vec![];
+    let mut card_id = CardId(0);
+    // time and interval in days of the previous entry, if it had one
+    let mut last_review: Option<(RevlogId, i32)> = None;
+    let mut passes = 0;
+    let mut lapses = 0;
+
+    for entry in entries {
+        if entry.cid != card_id {
+            card_id = entry.cid;
+            last_review = None;
+            passes = 0;
+            lapses = 0;
+        }
+        if entry.review_kind == RevlogReviewKind::Manual {
+            continue;
+        }
+        if let (RevlogReviewKind::Review, Some((last_id, last_interval))) =
+            (entry.review_kind, last_review)
+        {
+            let recalled = entry.button_chosen > 1;
+            items.push(ReviewItem {
+                elapsed_days: (entry.id.0 - last_id.0) as f32 / 86_400_000.0,
+                scheduled_days: last_interval as f32,
+                passes,
+                lapses,
+                recalled,
+            });
+            if recalled {
+                passes += 1;
+            } else {
+                lapses += 1;
+            }
+        }
+        last_review = (entry.interval > 0).then(|| (entry.id, entry.interval));
+    }
+
+    items
+}
+
+fn recall_probability(elapsed_days: f32, stability: f32) -> f32 {
+    TARGET_RETENTION
+        .powf(elapsed_days.max(0.0) / stability.max(0.01))
+        .clamp(0.0001, 0.9999)
+}
+
+fn log_loss(items: &[ReviewItem], stability: impl Fn(&ReviewItem) -> f32) -> f32 {
+    let total: f32 = items
+        .iter()
+        .map(|item| {
+            let p = recall_probability(item.elapsed_days, stability(item));
+            if item.recalled {
+                -p.ln()
+            } else {
+                -(1.0 - p).ln()
+            }
+        })
+        .sum();
+    total / items.len() as f32
+}
+
+impl FitMetrics {
+    fn new(items: &[ReviewItem], stability: impl Fn(&ReviewItem) -> f32) -> Self {
+        let squared_error: f32 = items
+            .iter()
+            .map(|item| {
+                let p = recall_probability(item.elapsed_days, stability(item));
+                (p - if item.recalled { 1.0 } else { 0.0 }).powi(2)
+            })
+            .sum();
+        FitMetrics {
+            log_loss: log_loss(items, &stability),
+            rmse: (squared_error / items.len() as f32).sqrt(),
+        }
+    }
+}
+
+/// The factor the scheduled intervals need to be multiplied with so that
+/// recall matches the target retention.
+fn fit_interval_scale(items: &[ReviewItem]) -> f32 {
+    let log_scale = minimize(-3.0, 3.0, |log_scale| {
+        log_loss(items, |item| item.scheduled_days * log_scale.exp())
+    });
+    log_scale.exp()
+}
+
+impl Default for GrowthModel {
+    fn default() -> Self {
+        GrowthModel {
+            initial_stability: 1.0,
+            pass_factor: 2.5,
+            lapse_factor: 0.5,
+        }
+    }
+}
+
+impl GrowthModel {
+    fn stability(&self, item: &ReviewItem) -> f32 {
+        self.initial_stability
+            * self.pass_factor.powi(item.passes as i32)
+            * self.lapse_factor.powi(item.lapses as i32)
+    }
+
+    /// Fit each parameter in turn, keeping the others fixed.
+    fn improve(&mut self, items: &[ReviewItem]) {
+        let model = *self;
+        self.initial_stability = minimize(0.01f32.ln(), 365f32.ln(), |v| {
+            log_loss(items, |item| {
+                GrowthModel {
+                    initial_stability: v.exp(),
+                    ..model
+                }
+                .stability(item)
+            })
+        })
+        .exp();
+        let model = *self;
+        self.pass_factor = minimize(1.0f32.ln(), 10f32.ln(), |v| {
+            log_loss(items, |item| {
+                GrowthModel {
+                    pass_factor: v.exp(),
+                    ..model
+                }
+                .stability(item)
+            })
+        })
+        .exp();
+        let model = *self;
+        self.lapse_factor = minimize(0.01f32.ln(), 0.0, |v| {
+            log_loss(items, |item| {
+                GrowthModel {
+                    lapse_factor: v.exp(),
+                    ..model
+                }
+                .stability(item)
+            })
+        })
+        .exp();
+    }
+}
+
+/// The upper bound of the last bucket with acceptable retention before the
+/// first one that falls short. None if no bucket falls short, or if the first
+/// one already does.
+fn maximum_interval(items: &[ReviewItem]) -> Option<u32> {
+    let mut buckets: Vec<(usize, usize)> = vec![];
+    for item in items {
+        let bucket = item.scheduled_days.max(1.0).log2() as usize;
+        if buckets.len() <= bucket {
+            buckets.resize(bucket + 1, (0, 0));
+        }
+        buckets[bucket].0 += 1;
+        buckets[bucket].1 += item.recalled as usize;
+    }
+
+    let mut last_acceptable = None;
+    for (bucket, (reviews, recalled)) in buckets.into_iter().enumerate() {
+        if reviews < MINIMUM_BUCKET_REVIEWS {
+            continue;
+        }
+        if (recalled as f32 / reviews as f32) < MINIMUM_BUCKET_RETENTION {
+            return last_acceptable;
+        }
+        last_acceptable = Some((1 << (bucket + 1)) - 1);
+    }
+    None
+}
+
+/// Golden-section search for the minimum of a unimodal function.
+fn minimize(mut lower: f32, mut upper: f32, f: impl Fn(f32) -> f32) -> f32 {
+    const INVERSE_PHI: f32 = 0.618_034;
+    let mut a = upper - INVERSE_PHI * (upper - lower);
+    let mut b = lower + INVERSE_PHI * (upper - lower);
+    let mut fa = f(a);
+    let mut fb = f(b);
+    for _ in 0..40 {
+        if fa < fb {
+            upper = b;
+            b = a;
+            fb = fa;
+            a = upper - INVERSE_PHI * (upper - lower);
+            fa = f(a);
+        } else {
+            lower = a;
+            a = b;
+            fa = fb;
+            b = lower + INVERSE_PHI * (upper - lower);
+            fb = f(b);
+        }
+    }
+    (lower + upper) / 2.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(cid: i64, day: i64, button: u8, interval: i32, kind: RevlogReviewKind) -> RevlogEntry {
+        RevlogEntry {
+            id: RevlogId(day * 86_400_000),
+            cid: CardId(cid),
+            button_chosen: button,
+            interval,
+            review_kind: kind,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn extracting_reviews() {
+        let entries = [
+            entry(1, 0, 3, -600, RevlogReviewKind::Learning),
+            entry(1, 0, 3, 1, RevlogReviewKind::Learning),
+            entry(1, 2, 3, 3, RevlogReviewKind::Review),
+            entry(1, 5, 1, -600, RevlogReviewKind::Review),
+            entry(1, 5, 3, 1, RevlogReviewKind::Relearning),
+            entry(1, 6, 3, 2, RevlogReviewKind::Review),
+            entry(2, 10, 3, 4, RevlogReviewKind::Manual),
+            entry(2, 14, 3, 8, RevlogReviewKind::Review),
+        ];
+        let items = review_items(&entries);
+        assert_eq!(
+            items,
+            vec![
+                ReviewItem {
+                    elapsed_days: 2.0,
+                    scheduled_days: 1.0,
+                    passes: 0,
+                    lapses: 0,
+                    recalled: true,
+                },
+                ReviewItem {
+                    elapsed_days: 3.0,
+                    scheduled_days: 3.0,
+                    passes: 1,
+                    lapses: 0,
+                    recalled: false,
+                },
+                ReviewItem {
+                    elapsed_days: 1.0,
+                    scheduled_days: 1.0,
+                    passes: 1,
+                    lapses: 1,
+                    recalled: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fitting() {
+        // intervals are twice as long as they could be: recall after the
+        // scheduled interval is 0.9^0.5 (~95%)
+        let items: Vec<_> = (0..1000)
+            .map(|i| ReviewItem {
+                elapsed_days: 10.0,
+                scheduled_days: 10.0,
+                passes: 0,
+                lapses: 0,
+                recalled: i % 100 < 95,
+            })
+            .collect();
+        let scale = fit_interval_scale(&items);
+        assert!((scale - 2.0).abs() < 0.1, "{}", scale);
+        let current = FitMetrics::new(&items, |item| item.scheduled_days);
+        let optimized = FitMetrics::new(&items, |item| item.scheduled_days * scale);
+        assert!(optimized.log_loss < current.log_loss);
+        assert!(optimized.rmse <= current.rmse);
+
+        assert_eq!(minimize(-5.0, 5.0, |x| (x - 1.0).powi(2)).round(), 1.0);
+        assert_eq!(maximum_interval(&items), None);
+    }
+}
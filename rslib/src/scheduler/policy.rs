@@ -0,0 +1,163 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+//! Hooks for code embedding this crate to change how cards are scheduled,
+//! without touching the queues, the review log or undo.
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use super::states::{CardState, MemoryState, NextCardStates, StateContext};
+use crate::{prelude::*, revlog::RevlogEntry};
+
+/// Adjusts the states a card's answer buttons lead to. Registered for a
+/// preset with [Collection::set_scheduling_policy()], it applies to all cards
+/// whose home deck uses the preset.
+pub trait SchedulingPolicy: Send + Sync {
+    /// Return the states the answer buttons should lead to, given the ones
+    /// the built-in scheduler arrived at. The current state can't be changed.
+    fn next_states(&self, input: &PolicyInput, states: NextCardStates) -> NextCardStates;
+}
+
+/// What a policy can base its decision on.
+pub struct PolicyInput<'a> {
+    pub card: &'a Card,
+    /// The card's review history, oldest first.
+    pub revlog: &'a [RevlogEntry],
+    pub current: CardState,
+    pub memory_state: Option<MemoryState>,
+    pub options: SchedulingOptions<'a>,
+    /// The number of days since the collection was created; review cards are
+    /// due on this scale.
+    pub days_elapsed: u32,
+}
+
+/// The preset options the built-in scheduler used.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SchedulingOptions<'a> {
+    /// In minutes.
+    pub learn_steps: &'a [f32],
+    /// In minutes.
+    pub relearn_steps: &'a [f32],
+    pub graduating_interval_good: u32,
+    pub graduating_interval_easy: u32,
+    pub initial_ease_factor: f32,
+    pub hard_multiplier: f32,
+    pub easy_multiplier: f32,
+    pub interval_multiplier: f32,
+    pub maximum_review_interval: u32,
+    pub lapse_multiplier: f32,
+    pub minimum_lapse_interval: u32,
+    pub leech_threshold: u32,
+    /// In range `0.0..1.0`, if intervals are fuzzed.
+    pub fuzz_factor: Option<f32>,
+    pub in_filtered_deck: bool,
+}
+
+impl<'a> From<&StateContext<'a>> for SchedulingOptions<'a> {
+    fn from(ctx: &StateContext<'a>) -> Self {
+        SchedulingOptions {
+            learn_steps: ctx.steps.steps(),
+            relearn_steps: ctx.relearn_steps.steps(),
+            graduating_interval_good: ctx.graduating_interval_good,
+            graduating_interval_easy: ctx.graduating_interval_easy,
+            initial_ease_factor: ctx.initial_ease_factor,
+            hard_multiplier: ctx.hard_multiplier,
+            easy_multiplier: ctx.easy_multiplier,
+            interval_multiplier: ctx.interval_multiplier,
+            maximum_review_interval: ctx.maximum_review_interval,
+            lapse_multiplier: ctx.lapse_multiplier,
+            minimum_lapse_interval: ctx.minimum_lapse_interval,
+            leech_threshold: ctx.leech_threshold,
+            fuzz_factor: ctx.fuzz_factor,
+            in_filtered_deck: ctx.in_filtered_deck,
+        }
+    }
+}
+
+/// The policies registered on a collection, by preset.
+#[derive(Default)]
+pub(crate) struct SchedulingPolicies(HashMap<DeckConfigId, Arc<dyn SchedulingPolicy>>);
+
+impl fmt::Debug for SchedulingPolicies {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+impl SchedulingPolicies {
+    pub(crate) fn get(&self, config_id: DeckConfigId) -> Option<Arc<dyn SchedulingPolicy>> {
+        self.0.get(&config_id).cloned()
+    }
+}
+
+impl Collection {
+    /// Let `policy` adjust the scheduling of cards using the given preset,
+    /// until the collection is closed. Replaces any policy registered for the
+    /// preset before.
+    pub fn set_scheduling_policy(
+        &mut self,
+        config_id: DeckConfigId,
+        policy: Arc<dyn SchedulingPolicy>,
+    ) {
+        self.state.scheduling_policies.0.insert(config_id, policy);
+    }
+
+    /// Return to the built-in scheduling for cards using the given preset.
+    pub fn clear_scheduling_policy(&mut self, config_id: DeckConfigId) {
+        self.state.scheduling_policies.0.remove(&config_id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        collection::open_test_collection,
+        scheduler::states::{NormalState, ReviewState},
+    };
+
+    /// Schedules cards answered with Good a fixed number of days later.
+    struct FixedInterval(u32);
+
+    impl SchedulingPolicy for FixedInterval {
+        fn next_states(&self, input: &PolicyInput, mut states: NextCardStates) -> NextCardStates {
+            if let CardState::Normal(NormalState::Review(review)) = states.good {
+                states.good = ReviewState {
+                    scheduled_days: self.0 + input.card.lapses(),
+                    ..review
+                }
+                .into();
+            }
+            // ignored
+            states.current = NormalState::New(Default::default()).into();
+            states
+        }
+    }
+
+    #[test]
+    fn policy() -> Result<()> {
+        let mut col = open_test_collection();
+        let nt = col.get_notetype_by_name("Basic")?.unwrap();
+        let mut note = nt.new_note();
+        note.set_field(0, "front")?;
+        col.add_note(&mut note, DeckId(1))?;
+        let cid = col.storage.all_cards_of_note(note.id)?[0].id;
+        let config_id = DeckConfigId(1);
+
+        col.set_scheduling_policy(config_id, Arc::new(FixedInterval(7)));
+        // learning cards are left alone
+        col.answer_good();
+        // and the graduating interval is replaced
+        let states = col.get_next_card_states(cid)?;
+        assert_eq!(states.good.interval_kind().as_seconds(), 7 * 86_400);
+        col.answer_good();
+        let card = col.storage.get_card(cid)?.unwrap();
+        assert_eq!(card.interval, 7);
+
+        col.clear_scheduling_policy(config_id);
+        let states = col.get_next_card_states(cid)?;
+        assert_ne!(states.good.interval_kind().as_seconds(), 7 * 86_400);
+
+        Ok(())
+    }
+}
@@ -252,6 +252,22 @@ impl Collection {
                 })
                 .unwrap_or_default()
         };
+        let get_new_sibling_minimum_interval = |home_deck: DeckId| {
+            deck_map
+                .get(&home_deck)
+                .and_then(|deck| deck.config_id())
+                .and_then(|config_id| config.get(&config_id))
+                .map(|config| config.inner.new_sibling_minimum_interval)
+                .unwrap_or_default()
+        };
+        let studied_sibling_intervals = if config
+            .values()
+            .any(|config| config.inner.new_sibling_minimum_interval > 0)
+        {
+            self.storage.studied_sibling_intervals()?
+        } else {
+            HashMap::new()
+        };
 
         // intraday cards first, noting down any notes that will need burying
         self.storage
@@ -304,7 +320,18 @@ impl Collection {
             if limit.new > 0 {
                 self.storage
                     .for_each_new_card_in_deck(deck.id, reverse, |card| {
-                        let bury = get_bury_mode(card.original_deck_id.or(deck.id));
+                        let home_deck = card.original_deck_id.or(deck.id);
+                        let bury = get_bury_mode(home_deck);
+                        // hold back cards until their studied siblings are
+                        // sufficiently mature
+                        if studied_sibling_intervals
+                            .get(&card.note_id)
+                            .map_or(false, |&interval| {
+                                interval < get_new_sibling_minimum_interval(home_deck)
+                            })
+                        {
+                            return true;
+                        }
                         if limit.new != 0 {
                             if queues.add_new_card(card, bury) {
                                 limit.new -= 1;
@@ -174,11 +174,16 @@ impl Collection {
     /// transaction, you probably don't need this.
     pub(crate) fn clear_study_queues(&mut self) {
         self.state.card_queues = None;
+        self.state.load_balancer = None;
     }
 
     pub(crate) fn maybe_clear_study_queues_after_op(&mut self, op: &OpChanges) {
         if op.op != Op::AnswerCard && op.requires_study_queue_rebuild() {
-            self.state.card_queues = None;
+            self.clear_study_queues();
+        } else if self.undoing_or_redoing() {
+            // the queues undo answers themselves, but the load balancer only
+            // follows them forwards
+            self.state.load_balancer = None;
         }
     }
 
@@ -0,0 +1,199 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+//! Recomputing the intervals of review cards from their review history, after
+//! the options of their preset have changed.
+
+use super::{
+    answering::{get_fuzz_factor, get_fuzz_seed},
+    states::{CardState, NewState, NormalState, ReviewState, StateContext},
+    timing::SchedTimingToday,
+};
+use crate::{
+    card::CardType,
+    prelude::*,
+    revlog::{RevlogEntry, RevlogReviewKind},
+    search::{SearchNode, SortMode},
+};
+
+impl Collection {
+    /// Replay the review history of each review card in the deck with the
+    /// options of `config`, and make the card due the resulting interval after
+    /// its last review, taking on the replayed ease and memory state as well.
+    /// The final interval is fuzzed with the card's usual seed. Lapses are
+    /// never reduced, as the revlog may not cover the card's full history.
+    /// Cards whose replayed history does not end in review are left alone, as
+    /// are cards in filtered decks.
+    pub(crate) fn reschedule_cards_in_deck(
+        &mut self,
+        deck_id: DeckId,
+        config: &DeckConfig,
+        usn: Usn,
+    ) -> Result<()> {
+        let timing = self.timing_today()?;
+        self.search_cards_into_table(
+            SearchNode::DeckIdWithoutChildren(deck_id),
+            SortMode::NoOrder,
+        )?;
+        let cards = self.storage.all_searched_cards()?;
+        self.storage.clear_searched_cards_table()?;
+
+        let mut ctx = config.state_context();
+        for mut card in cards {
+            if card.ctype != CardType::Review || card.original_deck_id.0 != 0 {
+                continue;
+            }
+            let mut entries = self.storage.get_revlog_entries_for_card(card.id)?;
+            entries.sort_unstable_by_key(|entry| entry.id);
+            ctx.fuzz_factor = get_fuzz_factor(get_fuzz_seed(&card));
+            if let Some((review, last_review_day)) = replay_history(&entries, &mut ctx, timing) {
+                let original = card.clone();
+                card.interval = review.scheduled_days;
+                card.due = (last_review_day + review.scheduled_days) as i32;
+                card.ease_factor = (review.ease_factor * 1000.0).round() as u16;
+                card.lapses = card.lapses.max(review.lapses);
+                card.set_memory_state(review.memory_state);
+                if card == original {
+                    continue;
+                }
+                self.log_manually_scheduled_review(&card, &original, usn)?;
+                self.update_card_inner(&mut card, original, usn)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The review state the card would be in if its answers had been given with
+/// the options in `ctx`, and the day it was last answered on. Resetting a card
+/// starts its history over. The fuzz factor of `ctx` is only applied to the
+/// last answer.
+fn replay_history(
+    entries: &[RevlogEntry],
+    ctx: &mut StateContext,
+    timing: SchedTimingToday,
+) -> Option<(ReviewState, u32)> {
+    let fuzz_factor = ctx.fuzz_factor.take();
+    let last_answer = entries.iter().rposition(is_replayed_answer);
+    let mut state: NormalState = NewState::default().into();
+    let mut last_day = None;
+    for (idx, entry) in entries.iter().enumerate() {
+        if entry.review_kind == RevlogReviewKind::Manual && entry.interval == 0 {
+            state = NewState::default().into();
+            last_day = None;
+            continue;
+        }
+        if !is_replayed_answer(entry) {
+            continue;
+        }
+
+        let day = day_of_entry(entry, timing);
+        if let NormalState::Review(review) = &mut state {
+            review.elapsed_days = day.saturating_sub(last_day.unwrap_or(day));
+        }
+        ctx.fuzz_factor = if Some(idx) == last_answer {
+            fuzz_factor
+        } else {
+            None
+        };
+        let next = state.next_states(ctx);
+        let next = match entry.button_chosen {
+            1 => next.again,
+            2 => next.hard,
+            3 => next.good,
+            _ => next.easy,
+        };
+        if let CardState::Normal(next) = next {
+            state = next;
+        }
+        last_day = Some(day);
+    }
+    ctx.fuzz_factor = fuzz_factor;
+
+    match state {
+        NormalState::Review(review) => Some((review, last_day?)),
+        _ => None,
+    }
+}
+
+/// True if the entry is an answer given outside a filtered deck.
+fn is_replayed_answer(entry: &RevlogEntry) -> bool {
+    matches!(
+        entry.review_kind,
+        RevlogReviewKind::Learning | RevlogReviewKind::Review | RevlogReviewKind::Relearning
+    ) && (1..=4).contains(&entry.button_chosen)
+}
+
+/// The scheduler day the entry was logged on.
+fn day_of_entry(entry: &RevlogEntry, timing: SchedTimingToday) -> u32 {
+    let secs_before_next_day = (timing.next_day_at.0 - entry.id.as_secs().0).max(1);
+    let days_ago = ((secs_before_next_day - 1) / 86_400) as u32;
+    timing.days_elapsed.saturating_sub(days_ago)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{collection::open_test_collection, revlog::RevlogId};
+
+    fn entry(days_ago: i64, button_chosen: u8, review_kind: RevlogReviewKind) -> RevlogEntry {
+        RevlogEntry {
+            id: RevlogId((TimestampSecs::now().0 - days_ago * 86_400) * 1000),
+            button_chosen,
+            review_kind,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn replaying() {
+        let timing = SchedTimingToday {
+            days_elapsed: 100,
+            next_day_at: TimestampSecs(TimestampSecs::now().0 + 3600),
+            now: TimestampSecs::now(),
+        };
+        let mut ctx = StateContext::defaults_for_testing();
+        let mut entries = vec![
+            // graduated with an interval of 4 days, answered 5 days late
+            entry(9, 4, RevlogReviewKind::Learning),
+            entry(0, 3, RevlogReviewKind::Review),
+        ];
+        let (review, last_day) = replay_history(&entries, &mut ctx, timing).unwrap();
+        assert_eq!(last_day, 100);
+        // (4 + 5 / 2) * 2.5
+        assert_eq!(review.scheduled_days, 16);
+
+        // a reset starts over
+        entries.push(entry(0, 0, RevlogReviewKind::Manual));
+        assert_eq!(replay_history(&entries, &mut ctx, timing), None);
+        entries.push(entry(0, 3, RevlogReviewKind::Learning));
+        assert_eq!(replay_history(&entries, &mut ctx, timing), None);
+    }
+
+    #[test]
+    fn lapses_missing_from_revlog_are_kept() -> Result<()> {
+        let mut col = open_test_collection();
+        let mut card = col.add_review_card(0, 100)?;
+        card.lapses = 5;
+        col.storage.update_card(&card)?;
+
+        // only the last review survived in the revlog
+        let mut review = entry(0, 3, RevlogReviewKind::Review);
+        review.cid = card.id;
+        col.storage.add_revlog_entry(&review, true)?;
+        // which replays to a review card only after graduating
+        let mut learning = entry(9, 4, RevlogReviewKind::Learning);
+        learning.cid = card.id;
+        col.storage.add_revlog_entry(&learning, true)?;
+
+        let config = col.get_deck_config(DeckConfigId(1), false)?.unwrap();
+        let usn = col.usn()?;
+        col.transact_no_undo(|col| col.reschedule_cards_in_deck(DeckId(1), &config, usn))?;
+        let card = col.storage.get_card(card.id)?.unwrap();
+        assert_ne!(card.interval, 100);
+        assert_eq!(card.lapses, 5);
+
+        Ok(())
+    }
+}
@@ -21,7 +21,7 @@ impl Card {
     /// Review/relearning cards have their interval preserved unless
     /// `force_reset` is true.
     /// If the card has no ease factor (it's new), `ease_factor` is used.
-    fn set_due_date(
+    pub(super) fn set_due_date(
         &mut self,
         today: u32,
         days_from_today: u32,
@@ -0,0 +1,407 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+//! Forecasts the workload of a preset by replaying the scheduling states of
+//! cards with randomly simulated answers. Whether a review card is recalled is
+//! predicted with the forgetting curve of the memory model; the remaining
+//! answer behaviour is taken from the cards' review history.
+
+use rand::{prelude::*, rngs::StdRng};
+
+use super::states::{
+    CardState, LearnState, MemoryModel, MemoryState, NewState, NormalState, RelearnState,
+    ReviewState, StateContext,
+};
+use crate::{
+    card::{CardQueue, CardType},
+    prelude::*,
+    revlog::{RevlogEntry, RevlogReviewKind},
+    search::SortMode,
+};
+
+const SECS_PER_DAY: u32 = 86_400;
+/// Using the same seed for every run makes the forecasts of different presets
+/// comparable.
+const SEED: u64 = 0;
+/// A card still in same-day learning after this many answers is assumed to be
+/// put off until the next day.
+const MAX_ANSWERS_PER_DAY: usize = 20;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SimulatedDay {
+    /// Answers of cards in the review queue.
+    pub reviews: f32,
+    /// Answers of new, learning and relearning cards.
+    pub learning: f32,
+    /// Cards studied for the first time.
+    pub new_cards: f32,
+    pub study_secs: f32,
+}
+
+/// Answer behaviour taken from the review history, or reasonable defaults if
+/// there is none.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AnswerStats {
+    /// The probability of passing a learning step.
+    learn_pass_rate: f64,
+    /// The relative frequency of hard, good and easy when a card is recalled.
+    pass_ratings: [u32; 3],
+    learn_secs: f32,
+    review_secs: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SimulatedCard {
+    state: NormalState,
+    /// Relative to the first simulated day, so negative if the card is
+    /// overdue. Not used for new cards.
+    due_day: i32,
+}
+
+struct Simulation<'a> {
+    ctx: StateContext<'a>,
+    new_per_day: u32,
+    reviews_per_day: u32,
+    stats: AnswerStats,
+    rng: StdRng,
+}
+
+impl Collection {
+    /// Forecast the workload of the cards matching `search` for the next
+    /// `days` days, as if they were all using `config`. Suspended cards are
+    /// ignored. The result is the average of `iterations` runs.
+    pub fn simulate_workload(
+        &mut self,
+        config: &DeckConfig,
+        search: &str,
+        days: u32,
+        iterations: u32,
+    ) -> Result<Vec<SimulatedDay>> {
+        let today = self.timing_today()?.days_elapsed;
+        self.search_cards_into_table(search, SortMode::NoOrder)?;
+        let cards = self.storage.all_searched_cards();
+        let entries = self
+            .storage
+            .get_revlog_entries_for_searched_cards_in_card_order();
+        self.storage.clear_searched_cards_table()?;
+
+        let memory_model = config.memory_model();
+        let mut cards: Vec<_> = cards?
+            .iter()
+            .filter_map(|card| SimulatedCard::new(card, memory_model, today))
+            .collect();
+        // new cards go last, and are introduced in order of their position
+        cards.sort_by_key(|card| match card.state {
+            NormalState::New(NewState { position }) => Some(position),
+            _ => None,
+        });
+        let mut simulation = Simulation {
+            ctx: config.state_context(),
+            new_per_day: config.inner.new_per_day,
+            reviews_per_day: config.inner.reviews_per_day,
+            stats: AnswerStats::new(&entries?),
+            rng: StdRng::seed_from_u64(SEED),
+        };
+
+        let iterations = iterations.max(1);
+        let mut totals = vec![SimulatedDay::default(); days as usize];
+        for _ in 0..iterations {
+            simulation.run(cards.clone(), &mut totals);
+        }
+        for day in &mut totals {
+            day.reviews /= iterations as f32;
+            day.learning /= iterations as f32;
+            day.new_cards /= iterations as f32;
+            day.study_secs /= iterations as f32;
+        }
+
+        Ok(totals)
+    }
+}
+
+impl SimulatedCard {
+    fn new(card: &Card, memory_model: Option<MemoryModel>, today: u32) -> Option<Self> {
+        if card.queue == CardQueue::Suspended {
+            return None;
+        }
+        let due = if card.original_due != 0 {
+            card.original_due
+        } else {
+            card.due
+        };
+        let memory_state = memory_model.and_then(|_| card.memory_state());
+        let review = ReviewState {
+            scheduled_days: card.interval,
+            elapsed_days: 0,
+            ease_factor: card.ease_factor(),
+            lapses: card.lapses,
+            leeched: false,
+            memory_state,
+        };
+        let learning = LearnState {
+            remaining_steps: card.remaining_steps(),
+            scheduled_secs: 0,
+            memory_state,
+        };
+        let (state, due_day) = match card.ctype {
+            CardType::New => (
+                NewState {
+                    position: due.max(0) as u32,
+                }
+                .into(),
+                0,
+            ),
+            CardType::Learn => (learning.into(), 0),
+            CardType::Review => {
+                let due_day = due - today as i32;
+                (
+                    ReviewState {
+                        elapsed_days: card.interval + (-due_day).max(0) as u32,
+                        ..review
+                    }
+                    .into(),
+                    due_day,
+                )
+            }
+            CardType::Relearn => (
+                RelearnState {
+                    learning: LearnState {
+                        memory_state: None,
+                        ..learning
+                    },
+                    review,
+                }
+                .into(),
+                0,
+            ),
+        };
+        Some(SimulatedCard { state, due_day })
+    }
+
+    fn is_new(&self) -> bool {
+        matches!(self.state, NormalState::New(_))
+    }
+}
+
+impl AnswerStats {
+    fn new(entries: &[RevlogEntry]) -> Self {
+        let mut learn_answers = 0;
+        let mut learn_passes = 0;
+        let mut learn_millis = 0;
+        let mut review_answers = 0;
+        let mut review_millis = 0;
+        let mut pass_ratings = [0; 3];
+        for entry in entries {
+            match entry.review_kind {
+                RevlogReviewKind::Learning | RevlogReviewKind::Relearning => {
+                    learn_answers += 1;
+                    learn_passes += (entry.button_chosen > 1) as u32;
+                    learn_millis += entry.taken_millis as u64;
+                }
+                RevlogReviewKind::Review => {
+                    review_answers += 1;
+                    review_millis += entry.taken_millis as u64;
+                    if (2..=4).contains(&entry.button_chosen) {
+                        pass_ratings[entry.button_chosen as usize - 2] += 1;
+                    }
+                }
+                RevlogReviewKind::Filtered | RevlogReviewKind::Manual => (),
+            }
+        }
+
+        let average_secs = |millis: u64, count: u32, default: f32| {
+            if count == 0 {
+                default
+            } else {
+                millis as f32 / count as f32 / 1000.0
+            }
+        };
+        AnswerStats {
+            learn_pass_rate: if learn_answers == 0 {
+                0.8
+            } else {
+                learn_passes as f64 / learn_answers as f64
+            },
+            pass_ratings: if pass_ratings.iter().sum::<u32>() == 0 {
+                [1, 8, 1]
+            } else {
+                pass_ratings
+            },
+            learn_secs: average_secs(learn_millis, learn_answers, 10.0),
+            review_secs: average_secs(review_millis, review_answers, 8.0),
+        }
+    }
+}
+
+impl Simulation<'_> {
+    fn run(&mut self, mut cards: Vec<SimulatedCard>, totals: &mut [SimulatedDay]) {
+        let mut next_new = cards.iter().position(SimulatedCard::is_new);
+        for (day, totals) in totals.iter_mut().enumerate() {
+            let day = day as u32;
+            let mut due: Vec<_> = cards
+                .iter()
+                .enumerate()
+                .filter(|(_, card)| !card.is_new() && card.due_day <= day as i32)
+                .map(|(idx, card)| (card.due_day, idx))
+                .collect();
+            due.sort_unstable();
+            let mut reviews_left = self.reviews_per_day;
+            for (_, idx) in due {
+                if matches!(cards[idx].state, NormalState::Review(_)) {
+                    if reviews_left == 0 {
+                        continue;
+                    }
+                    reviews_left -= 1;
+                }
+                self.study(&mut cards[idx], day, totals);
+            }
+
+            for _ in 0..self.new_per_day {
+                match next_new {
+                    Some(idx) if cards[idx].is_new() => {
+                        totals.new_cards += 1.0;
+                        self.study(&mut cards[idx], day, totals);
+                        next_new = Some(idx + 1).filter(|&idx| idx < cards.len());
+                    }
+                    _ => break,
+                }
+            }
+        }
+    }
+
+    /// Answer the card until it is no longer due on `day`.
+    fn study(&mut self, card: &mut SimulatedCard, day: u32, totals: &mut SimulatedDay) {
+        for _ in 0..MAX_ANSWERS_PER_DAY {
+            if let NormalState::Review(review) = &mut card.state {
+                review.elapsed_days = review.scheduled_days + (day as i32 - card.due_day) as u32;
+                totals.reviews += 1.0;
+                totals.study_secs += self.stats.review_secs;
+            } else {
+                totals.learning += 1.0;
+                totals.study_secs += self.stats.learn_secs;
+            }
+
+            self.ctx.fuzz_factor = Some(self.rng.gen_range(0.0..1.0));
+            let next = card.state.next_states(&self.ctx);
+            let next = match self.rating(card.state) {
+                1 => next.again,
+                2 => next.hard,
+                3 => next.good,
+                _ => next.easy,
+            };
+            if let CardState::Normal(state) = next {
+                card.state = state;
+            }
+
+            match card.state {
+                NormalState::Learning(LearnState { scheduled_secs, .. })
+                | NormalState::Relearning(RelearnState {
+                    learning: LearnState { scheduled_secs, .. },
+                    ..
+                }) => {
+                    if scheduled_secs >= SECS_PER_DAY {
+                        card.due_day = (day + scheduled_secs / SECS_PER_DAY) as i32;
+                        return;
+                    }
+                }
+                NormalState::Review(review) => {
+                    card.due_day = (day + review.scheduled_days.max(1)) as i32;
+                    return;
+                }
+                NormalState::New(_) => return,
+            }
+        }
+        card.due_day = day as i32 + 1;
+    }
+
+    /// 1 (again) to 4 (easy).
+    fn rating(&mut self, state: NormalState) -> u8 {
+        let recall_probability = match state {
+            NormalState::Review(review) => {
+                let memory_state = review.memory_state.unwrap_or_else(|| {
+                    MemoryState::from_sm2(review.scheduled_days, review.ease_factor)
+                });
+                MemoryModel::retrievability(memory_state, review.elapsed_days as f32) as f64
+            }
+            _ => self.stats.learn_pass_rate,
+        };
+        if !self.rng.gen_bool(recall_probability.clamp(0.0, 1.0)) {
+            return 1;
+        }
+        let [hard, good, easy] = self.stats.pass_ratings;
+        let choice = self.rng.gen_range(0..hard + good + easy);
+        if choice < hard {
+            2
+        } else if choice < hard + good {
+            3
+        } else {
+            4
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::collection::open_test_collection;
+
+    #[test]
+    fn simulating() -> Result<()> {
+        let mut col = open_test_collection();
+        let nt = col.get_notetype_by_name("Basic")?.unwrap();
+        for _ in 0..30 {
+            let mut note = nt.new_note();
+            note.set_field(0, "front")?;
+            col.add_note(&mut note, DeckId(1))?;
+        }
+        let mut config = DeckConfig::default();
+        config.inner.new_per_day = 20;
+
+        let days = col.simulate_workload(&config, "", 30, 5)?;
+        assert_eq!(days.len(), 30);
+        assert_eq!(days[0].new_cards, 20.0);
+        assert_eq!(days[1].new_cards, 10.0);
+        assert_eq!(days.iter().map(|day| day.new_cards).sum::<f32>(), 30.0);
+        assert_eq!(days[0].reviews, 0.0);
+        // each new card goes through at least two learning steps
+        assert!(days[0].learning >= 40.0);
+        assert!(days[1..].iter().map(|day| day.reviews).sum::<f32>() > 0.0);
+        assert!(days[0].study_secs > 0.0);
+
+        // suspended cards and cards not matching the search are ignored
+        let days = col.simulate_workload(&config, "is:suspended", 30, 1)?;
+        assert!(days.iter().all(|day| *day == SimulatedDay::default()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn overdue_cards() {
+        let card = Card {
+            ctype: CardType::Review,
+            queue: CardQueue::Review,
+            due: 80,
+            interval: 10,
+            ..Default::default()
+        };
+        let simulated = SimulatedCard::new(&card, None, 100).unwrap();
+        // the days the card is overdue count towards the first review
+        assert_eq!(simulated.due_day, -20);
+        match simulated.state {
+            NormalState::Review(review) => assert_eq!(review.elapsed_days, 30),
+            state => panic!("unexpected state: {:?}", state),
+        }
+
+        // the recall chance of an overdue card is lower than that of a card
+        // due today
+        let recall = |card: SimulatedCard| match card.state {
+            NormalState::Review(review) => MemoryModel::retrievability(
+                MemoryState::from_sm2(review.scheduled_days, review.ease_factor),
+                review.elapsed_days as f32,
+            ),
+            _ => unreachable!(),
+        };
+        let due_today = SimulatedCard::new(&Card { due: 100, ..card }, None, 100).unwrap();
+        assert!(recall(simulated) < recall(due_today));
+    }
+}
@@ -0,0 +1,40 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use crate::{config::Weekday, deckconfig::EasyDay};
+
+/// Weekdays on which fewer or no reviews should be scheduled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct EasyDays {
+    /// Indexed by weekday, starting with Sunday.
+    days: [EasyDay; 7],
+    /// The weekday cards are answered on.
+    today: Weekday,
+}
+
+impl EasyDays {
+    /// `days` is as stored in the preset. None if all days are normal.
+    pub(crate) fn new(days: &[i32], today: Weekday) -> Option<Self> {
+        let mut easy_days = [EasyDay::Normal; 7];
+        for (easy_day, &day) in easy_days.iter_mut().zip(days) {
+            *easy_day = EasyDay::from_i32(day).unwrap_or(EasyDay::Normal);
+        }
+        easy_days
+            .iter()
+            .any(|&day| day != EasyDay::Normal)
+            .then(|| EasyDays {
+                days: easy_days,
+                today,
+            })
+    }
+
+    /// 1 if the day `interval` days from today is a normal day, 0.5 if it's a
+    /// light day and 0 if it's a day off.
+    pub(crate) fn weight(&self, interval: u32) -> f32 {
+        match self.days[self.today.adding_days(interval) as usize] {
+            EasyDay::Normal => 1.0,
+            EasyDay::Light => 0.5,
+            EasyDay::Off => 0.0,
+        }
+    }
+}
@@ -1,7 +1,7 @@
 // Copyright: Ankitects Pty Ltd and contributors
 // License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
 
-use super::StateContext;
+use super::{MemoryModel, MemoryState, StateContext};
 
 /// Describes a range of days for which a certain amount of fuzz is applied to
 /// the new interval.
@@ -32,15 +32,38 @@ static FUZZ_RANGES: [FuzzRange; 3] = [
 impl<'a> StateContext<'a> {
     /// Apply fuzz, respecting the passed bounds.
     /// Caller must ensure reasonable bounds.
+    /// Without load balancing and easy days, all days in the fuzz range are
+    /// equally likely. Without fuzz, the closest day that isn't a day off is
+    /// picked.
     pub(crate) fn with_review_fuzz(&self, interval: f32, minimum: u32, maximum: u32) -> u32 {
+        let (lower, upper) = constrained_fuzz_bounds(interval, minimum, maximum);
         if let Some(fuzz_factor) = self.fuzz_factor {
-            let (lower, upper) = constrained_fuzz_bounds(interval, minimum, maximum);
-            (lower as f32 + fuzz_factor * ((1 + upper - lower) as f32)).floor() as u32
+            weighted_interval(lower, upper, fuzz_factor, |interval| {
+                self.interval_weight(interval)
+            })
+            .unwrap_or_else(|| {
+                (lower as f32 + fuzz_factor * ((1 + upper - lower) as f32)).floor() as u32
+            })
         } else {
-            (interval.round() as u32).max(minimum).min(maximum)
+            let interval = (interval.round() as u32).max(minimum).min(maximum);
+            match self.easy_days {
+                Some(easy_days) => (lower..=upper)
+                    .filter(|&day| easy_days.weight(day) > 0.0)
+                    .min_by_key(|&day| (day as i64 - interval as i64).abs())
+                    .unwrap_or(interval),
+                None => interval,
+            }
         }
     }
 
+    /// The load balancer's weight for the day, times its easy day weight.
+    /// Either is 1 if not in use.
+    fn interval_weight(&self, interval: u32) -> f32 {
+        self.load_balancer
+            .map_or(1.0, |counts| counts.weight(interval))
+            * self.easy_days.map_or(1.0, |days| days.weight(interval))
+    }
+
     pub(crate) fn fuzzed_graduating_interval_good(&self) -> u32 {
         let (minimum, maximum) = self.min_and_max_review_intervals(1);
         self.with_review_fuzz(self.graduating_interval_good as f32, minimum, maximum)
@@ -50,6 +73,42 @@ impl<'a> StateContext<'a> {
         let (minimum, maximum) = self.min_and_max_review_intervals(1);
         self.with_review_fuzz(self.graduating_interval_easy as f32, minimum, maximum)
     }
+
+    /// The interval at which the card's probability of recall drops to the
+    /// desired retention, with fuzz applied. At least `minimum`.
+    pub(crate) fn fuzzed_memory_interval(
+        &self,
+        model: &MemoryModel,
+        state: MemoryState,
+        minimum: u32,
+    ) -> u32 {
+        let (minimum, maximum) = self.min_and_max_review_intervals(minimum);
+        self.with_review_fuzz(model.interval(state.stability), minimum, maximum)
+    }
+}
+
+/// Pick an interval in `lower..=upper`, with each interval's chance being
+/// proportional to its weight. `fuzz_factor` (in range `0.0..1.0`) selects
+/// from that distribution. None if all weights are zero.
+fn weighted_interval(
+    lower: u32,
+    upper: u32,
+    fuzz_factor: f32,
+    weight: impl Fn(u32) -> f32,
+) -> Option<u32> {
+    let total: f32 = (lower..=upper).map(&weight).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut remaining = fuzz_factor * total;
+    for interval in lower..=upper {
+        let weight = weight(interval);
+        if remaining < weight {
+            return Some(interval);
+        }
+        remaining -= weight;
+    }
+    Some(upper)
 }
 
 /// Return the bounds of the fuzz range, respecting `minimum` and `maximum`.
@@ -95,6 +154,11 @@ fn fuzz_delta(interval: f32) -> f32 {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{
+        config::Weekday,
+        deckconfig::EasyDay,
+        scheduler::states::{DueCounts, EasyDays},
+    };
 
     #[test]
     fn with_review_fuzz() {
@@ -147,4 +211,38 @@ mod test {
         assert_lower_middle_upper!(100.0, 1, 99, 93, 96, 99);
         assert_lower_middle_upper!(100.0, 97, 103, 97, 100, 103);
     }
+
+    #[test]
+    fn weighted_fuzz() {
+        // day 6 is empty and the others are busy, so most of the range picks 6
+        let counts = DueCounts::new([(5, 10), (7, 10), (8, 10), (9, 10)].into_iter().collect());
+        let mut ctx = StateContext::defaults_for_testing();
+        ctx.load_balancer = Some(&counts);
+        ctx.fuzz_factor = Some(0.0);
+        assert_eq!(ctx.with_review_fuzz(7.0, 1, 1000), 5);
+        ctx.fuzz_factor = Some(0.5);
+        assert_eq!(ctx.with_review_fuzz(7.0, 1, 1000), 6);
+        ctx.fuzz_factor = Some(0.95);
+        assert_eq!(ctx.with_review_fuzz(7.0, 1, 1000), 6);
+        ctx.fuzz_factor = Some(0.99);
+        assert_eq!(ctx.with_review_fuzz(7.0, 1, 1000), 8);
+
+        // today is a Friday, and the weekend is off
+        let off = EasyDay::Off as i32;
+        ctx.load_balancer = None;
+        ctx.easy_days = EasyDays::new(&[off, 0, 0, 0, 0, 0, off], Weekday::Friday);
+        // 1 day is not fuzzed, and 2 days can't be moved past the weekend
+        ctx.fuzz_factor = Some(0.5);
+        assert_eq!(ctx.with_review_fuzz(1.0, 1, 1000), 1);
+        assert_eq!(ctx.with_review_fuzz(2.0, 1, 1000), 2);
+        // 7 days (5-9) is not moved to the weekend
+        for fuzz_factor in [0.0, 0.3, 0.6, 0.99] {
+            ctx.fuzz_factor = Some(fuzz_factor);
+            assert!(![8, 9].contains(&ctx.with_review_fuzz(7.0, 1, 1000)));
+        }
+        // without fuzz, the closest day is picked
+        ctx.fuzz_factor = None;
+        assert_eq!(ctx.with_review_fuzz(8.0, 1, 1000), 7);
+        assert_eq!(ctx.with_review_fuzz(9.0, 1, 1000), 10);
+    }
 }
@@ -1,13 +1,17 @@
 // Copyright: Ankitects Pty Ltd and contributors
 // License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
 
-use super::{interval_kind::IntervalKind, CardState, NextCardStates, ReviewState, StateContext};
+use super::{
+    interval_kind::IntervalKind, CardState, MemoryState, NextCardStates, ReviewState, StateContext,
+};
 use crate::revlog::RevlogReviewKind;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct LearnState {
     pub remaining_steps: u32,
     pub scheduled_secs: u32,
+    /// Only tracked when the memory model is enabled.
+    pub memory_state: Option<MemoryState>,
 }
 
 impl LearnState {
@@ -20,62 +24,75 @@ impl LearnState {
     }
 
     pub(crate) fn next_states(self, ctx: &StateContext) -> NextCardStates {
+        let memory = ctx
+            .memory_model
+            .map(|model| model.next_memory_states(self.memory_state, 0));
+
         NextCardStates {
             current: self.into(),
-            again: self.answer_again(ctx).into(),
-            hard: self.answer_hard(ctx),
-            good: self.answer_good(ctx),
-            easy: self.answer_easy(ctx).into(),
+            again: self.answer_again(ctx, memory.map(|m| m.again)).into(),
+            hard: self.answer_hard(ctx, memory.map(|m| m.hard)),
+            good: self.answer_good(ctx, memory.map(|m| m.good)),
+            easy: self.answer_easy(ctx, memory.map(|m| m.easy)).into(),
         }
     }
 
-    fn answer_again(self, ctx: &StateContext) -> LearnState {
+    fn answer_again(self, ctx: &StateContext, memory_state: Option<MemoryState>) -> LearnState {
         LearnState {
             remaining_steps: ctx.steps.remaining_for_failed(),
             scheduled_secs: ctx.steps.again_delay_secs_learn(),
+            memory_state,
         }
     }
 
-    fn answer_hard(self, ctx: &StateContext) -> CardState {
+    fn answer_hard(self, ctx: &StateContext, memory_state: Option<MemoryState>) -> CardState {
         if let Some(hard_delay) = ctx.steps.hard_delay_secs(self.remaining_steps) {
             LearnState {
                 scheduled_secs: hard_delay,
+                memory_state,
                 ..self
             }
             .into()
         } else {
             // steps modified while card in learning
-            ReviewState {
-                scheduled_days: ctx.fuzzed_graduating_interval_good(),
-                ease_factor: ctx.initial_ease_factor,
-                ..Default::default()
-            }
-            .into()
+            graduated_review_state(ctx, memory_state, false).into()
         }
     }
 
-    fn answer_good(self, ctx: &StateContext) -> CardState {
+    fn answer_good(self, ctx: &StateContext, memory_state: Option<MemoryState>) -> CardState {
         if let Some(good_delay) = ctx.steps.good_delay_secs(self.remaining_steps) {
             LearnState {
                 remaining_steps: ctx.steps.remaining_for_good(self.remaining_steps),
                 scheduled_secs: good_delay,
+                memory_state,
             }
             .into()
         } else {
-            ReviewState {
-                scheduled_days: ctx.fuzzed_graduating_interval_good(),
-                ease_factor: ctx.initial_ease_factor,
-                ..Default::default()
-            }
-            .into()
+            graduated_review_state(ctx, memory_state, false).into()
         }
     }
 
-    fn answer_easy(self, ctx: &StateContext) -> ReviewState {
-        ReviewState {
-            scheduled_days: ctx.fuzzed_graduating_interval_easy(),
-            ease_factor: ctx.initial_ease_factor,
-            ..Default::default()
-        }
+    fn answer_easy(self, ctx: &StateContext, memory_state: Option<MemoryState>) -> ReviewState {
+        graduated_review_state(ctx, memory_state, true)
+    }
+}
+
+/// With the memory model, the interval is derived from the memory state
+/// instead of the configured graduating intervals.
+fn graduated_review_state(
+    ctx: &StateContext,
+    memory_state: Option<MemoryState>,
+    easy: bool,
+) -> ReviewState {
+    let scheduled_days = match (ctx.memory_model, memory_state) {
+        (Some(model), Some(state)) => ctx.fuzzed_memory_interval(&model, state, 1),
+        _ if easy => ctx.fuzzed_graduating_interval_easy(),
+        _ => ctx.fuzzed_graduating_interval_good(),
+    };
+    ReviewState {
+        scheduled_days,
+        ease_factor: ctx.initial_ease_factor,
+        memory_state,
+        ..Default::default()
     }
 }
@@ -0,0 +1,39 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::collections::HashMap;
+
+/// The number of review cards of a deck that are due on each upcoming day,
+/// keyed by the interval that would make a card due on that day.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct DueCounts(HashMap<u32, u32>);
+
+impl DueCounts {
+    pub(crate) fn new(counts: HashMap<u32, u32>) -> Self {
+        DueCounts(counts)
+    }
+
+    fn get(&self, interval: u32) -> u32 {
+        self.0.get(&interval).copied().unwrap_or_default()
+    }
+
+    /// The relative chance of a card being scheduled `interval` days from
+    /// today, which is inversely proportional to the square of the day's due
+    /// count (plus one).
+    pub(crate) fn weight(&self, interval: u32) -> f32 {
+        1.0 / ((self.get(interval) + 1) as f32).powi(2)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn weight() {
+        let counts = DueCounts::new([(5, 1), (7, 3)].into_iter().collect());
+        assert_eq!(counts.weight(4), 1.0);
+        assert_eq!(counts.weight(5), 0.25);
+        assert_eq!(counts.weight(7), 0.0625);
+    }
+}
@@ -0,0 +1,204 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+//! An alternative to the SM-2 ease factor, which models how well a card is
+//! remembered with its stability (the number of days until the probability of
+//! recall drops to 90%) and its difficulty (1-10). Intervals are chosen so that
+//! cards are due when their probability of recall drops to the desired
+//! retention.
+
+/// Shape of the forgetting curve.
+const DECAY: f32 = -0.5;
+/// Chosen so that the probability of recall is 90% after `stability` days.
+const FACTOR: f32 = 19.0 / 81.0;
+const MINIMUM_STABILITY: f32 = 0.1;
+const MAXIMUM_STABILITY: f32 = 36_500.0;
+const MINIMUM_DIFFICULTY: f32 = 1.0;
+const MAXIMUM_DIFFICULTY: f32 = 10.0;
+
+pub const DEFAULT_DESIRED_RETENTION: f32 = 0.9;
+pub const DEFAULT_MEMORY_WEIGHTS: [f32; 17] = [
+    0.4, 0.6, 2.4, 5.8, 4.93, 0.94, 0.86, 0.01, 1.49, 0.14, 0.94, 2.18, 0.05, 0.34, 1.26, 0.29,
+    2.61,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryState {
+    /// In days.
+    pub stability: f32,
+    /// In range `1.0..=10.0`.
+    pub difficulty: f32,
+}
+
+/// The memory state after answering with each of the buttons.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct NextMemoryStates {
+    pub again: MemoryState,
+    pub hard: MemoryState,
+    pub good: MemoryState,
+    pub easy: MemoryState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct MemoryModel {
+    weights: [f32; 17],
+    desired_retention: f32,
+}
+
+impl MemoryState {
+    /// Approximate the memory state of a card that was scheduled with SM-2,
+    /// assuming its interval was chosen for a retention of about 90%.
+    pub(crate) fn from_sm2(interval: u32, ease_factor: f32) -> Self {
+        MemoryState {
+            stability: (interval as f32).clamp(MINIMUM_STABILITY, MAXIMUM_STABILITY),
+            difficulty: (5.0 + (2.5 - ease_factor) * 5.0)
+                .clamp(MINIMUM_DIFFICULTY, MAXIMUM_DIFFICULTY),
+        }
+    }
+}
+
+impl MemoryModel {
+    /// Falls back on the defaults if `weights` doesn't have the expected
+    /// length, or if `desired_retention` is not a probability. The retention
+    /// is limited to a range that yields reasonable intervals.
+    pub(crate) fn new(weights: &[f32], desired_retention: f32) -> Self {
+        let weights = weights.try_into().unwrap_or(DEFAULT_MEMORY_WEIGHTS);
+        let desired_retention = if desired_retention > 0.0 && desired_retention < 1.0 {
+            desired_retention.clamp(0.7, 0.99)
+        } else {
+            DEFAULT_DESIRED_RETENTION
+        };
+        MemoryModel {
+            weights,
+            desired_retention,
+        }
+    }
+
+    /// The probability of recalling a card `elapsed_days` after its last
+    /// review.
+    pub(crate) fn retrievability(state: MemoryState, elapsed_days: f32) -> f32 {
+        (1.0 + FACTOR * elapsed_days.max(0.0) / state.stability).powf(DECAY)
+    }
+
+    /// The number of days until the probability of recall drops to the
+    /// desired retention.
+    pub(crate) fn interval(&self, stability: f32) -> f32 {
+        stability / FACTOR * (self.desired_retention.powf(1.0 / DECAY) - 1.0)
+    }
+
+    /// A card without a memory state gets the initial state for each
+    /// answer. Answering the same day as the last review doesn't change the
+    /// memory state.
+    pub(crate) fn next_memory_states(
+        &self,
+        current: Option<MemoryState>,
+        elapsed_days: u32,
+    ) -> NextMemoryStates {
+        let next = |rating| match current {
+            None => self.initial_state(rating),
+            Some(state) if elapsed_days == 0 => state,
+            Some(state) => self.state_after_review(state, elapsed_days as f32, rating),
+        };
+        NextMemoryStates {
+            again: next(1),
+            hard: next(2),
+            good: next(3),
+            easy: next(4),
+        }
+    }
+
+    /// `rating` is 1 (again) to 4 (easy).
+    fn initial_state(&self, rating: u8) -> MemoryState {
+        MemoryState {
+            stability: self.weights[rating as usize - 1]
+                .clamp(MINIMUM_STABILITY, MAXIMUM_STABILITY),
+            difficulty: self.initial_difficulty(rating),
+        }
+    }
+
+    fn initial_difficulty(&self, rating: u8) -> f32 {
+        (self.weights[4] - self.weights[5] * (rating as f32 - 3.0))
+            .clamp(MINIMUM_DIFFICULTY, MAXIMUM_DIFFICULTY)
+    }
+
+    fn state_after_review(&self, state: MemoryState, elapsed_days: f32, rating: u8) -> MemoryState {
+        let w = &self.weights;
+        let retrievability = Self::retrievability(state, elapsed_days);
+        let stability = if rating == 1 {
+            let stability = w[11]
+                * state.difficulty.powf(-w[12])
+                * ((state.stability + 1.0).powf(w[13]) - 1.0)
+                * (w[14] * (1.0 - retrievability)).exp();
+            stability.min(state.stability)
+        } else {
+            let hard_penalty = if rating == 2 { w[15] } else { 1.0 };
+            let easy_bonus = if rating == 4 { w[16] } else { 1.0 };
+            state.stability
+                * (1.0
+                    + w[8].exp()
+                        * (11.0 - state.difficulty)
+                        * state.stability.powf(-w[9])
+                        * ((w[10] * (1.0 - retrievability)).exp() - 1.0)
+                        * hard_penalty
+                        * easy_bonus)
+        };
+        // difficulty moves towards the initial difficulty of 'good'
+        let difficulty = state.difficulty - w[6] * (rating as f32 - 3.0);
+        let difficulty = w[7] * self.initial_difficulty(3) + (1.0 - w[7]) * difficulty;
+
+        MemoryState {
+            stability: stability.clamp(MINIMUM_STABILITY, MAXIMUM_STABILITY),
+            difficulty: difficulty.clamp(MINIMUM_DIFFICULTY, MAXIMUM_DIFFICULTY),
+        }
+    }
+}
+
+impl Default for MemoryModel {
+    fn default() -> Self {
+        MemoryModel::new(&DEFAULT_MEMORY_WEIGHTS, DEFAULT_DESIRED_RETENTION)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn intervals_match_retention() {
+        let state = MemoryState {
+            stability: 10.0,
+            difficulty: 5.0,
+        };
+        let model = MemoryModel::default();
+        assert!((model.interval(10.0) - 10.0).abs() < 0.01);
+        assert!((MemoryModel::retrievability(state, 10.0) - 0.9).abs() < 0.001);
+        // a higher retention requires shorter intervals
+        let model = MemoryModel::new(&[], 0.95);
+        let interval = model.interval(10.0);
+        assert!(interval < 10.0);
+        assert!((MemoryModel::retrievability(state, interval) - 0.95).abs() < 0.001);
+        // out of range values fall back on the default
+        assert_eq!(MemoryModel::new(&[1.0], 0.0), MemoryModel::default());
+    }
+
+    #[test]
+    fn state_updates() {
+        let model = MemoryModel::default();
+        let initial = model.next_memory_states(None, 0);
+        assert!(initial.again.stability < initial.good.stability);
+        assert!(initial.good.stability < initial.easy.stability);
+        assert!(initial.again.difficulty > initial.easy.difficulty);
+
+        // same day reviews don't change the state
+        let good = initial.good;
+        assert_eq!(model.next_memory_states(Some(good), 0).again, good);
+
+        let next = model.next_memory_states(Some(good), 3);
+        assert!(next.again.stability <= good.stability);
+        assert!(next.hard.stability > good.stability);
+        assert!(next.hard.stability < next.good.stability);
+        assert!(next.good.stability < next.easy.stability);
+        assert!(next.again.difficulty > good.difficulty);
+        assert!(next.easy.difficulty < good.difficulty);
+    }
+}
@@ -1,10 +1,13 @@
 // Copyright: Ankitects Pty Ltd and contributors
 // License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
 
+pub(crate) mod easy_days;
 pub(crate) mod filtered;
 pub(crate) mod fuzz;
 pub(crate) mod interval_kind;
 pub(crate) mod learning;
+pub(crate) mod load_balancer;
+pub(crate) mod memory;
 pub(crate) mod new;
 pub(crate) mod normal;
 pub(crate) mod preview_filter;
@@ -13,9 +16,13 @@ pub(crate) mod rescheduling_filter;
 pub(crate) mod review;
 pub(crate) mod steps;
 
+pub(crate) use easy_days::EasyDays;
 pub use filtered::FilteredState;
 pub(crate) use interval_kind::IntervalKind;
 pub use learning::LearnState;
+pub(crate) use load_balancer::DueCounts;
+pub(crate) use memory::MemoryModel;
+pub use memory::MemoryState;
 pub use new::NewState;
 pub use normal::NormalState;
 pub use preview_filter::PreviewState;
@@ -71,6 +78,10 @@ impl CardState {
 pub(crate) struct StateContext<'a> {
     /// In range `0.0..1.0`. Used to pick the final interval from the fuzz range.
     pub fuzz_factor: Option<f32>,
+    /// If set, days with fewer due cards are preferred when applying fuzz.
+    pub load_balancer: Option<&'a DueCounts>,
+    /// If set, days off are avoided and light days are picked less often.
+    pub easy_days: Option<EasyDays>,
 
     // learning
     pub steps: LearningSteps<'a>,
@@ -84,6 +95,9 @@ pub(crate) struct StateContext<'a> {
     pub interval_multiplier: f32,
     pub maximum_review_interval: u32,
     pub leech_threshold: u32,
+    /// If set, intervals are derived from the memory state instead of the ease
+    /// factor.
+    pub memory_model: Option<MemoryModel>,
 
     // relearning
     pub relearn_steps: LearningSteps<'a>,
@@ -109,6 +123,8 @@ impl<'a> StateContext<'a> {
     pub(crate) fn defaults_for_testing() -> Self {
         Self {
             fuzz_factor: None,
+            load_balancer: None,
+            easy_days: None,
             steps: LearningSteps::new(&[1.0, 10.0]),
             graduating_interval_good: 1,
             graduating_interval_easy: 4,
@@ -118,6 +134,7 @@ impl<'a> StateContext<'a> {
             interval_multiplier: 1.0,
             maximum_review_interval: 36500,
             leech_threshold: 8,
+            memory_model: None,
             relearn_steps: LearningSteps::new(&[10.0]),
             lapse_multiplier: 0.0,
             minimum_lapse_interval: 1,
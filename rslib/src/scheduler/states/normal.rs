@@ -41,6 +41,7 @@ impl NormalState {
                 let next_states = LearnState {
                     remaining_steps: ctx.steps.remaining_for_failed(),
                     scheduled_secs: 0,
+                    memory_state: None,
                 }
                 .next_states(ctx);
                 // .. but with current as New, not Learning
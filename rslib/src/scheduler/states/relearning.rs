@@ -6,6 +6,7 @@ use super::{
 };
 use crate::revlog::RevlogReviewKind;
 
+/// The memory state, if any, is tracked in `review`.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct RelearnState {
     pub learning: LearnState,
@@ -37,6 +38,7 @@ impl RelearnState {
                 learning: LearnState {
                     remaining_steps: ctx.relearn_steps.remaining_for_failed(),
                     scheduled_secs: again_delay,
+                    memory_state: None,
                 },
                 review: ReviewState {
                     scheduled_days: self.review.failing_review_interval(ctx),
@@ -82,6 +84,7 @@ impl RelearnState {
                     remaining_steps: ctx
                         .relearn_steps
                         .remaining_for_good(self.learning.remaining_steps),
+                    memory_state: None,
                 },
                 review: ReviewState {
                     elapsed_days: 0,
@@ -2,7 +2,8 @@
 // License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
 
 use super::{
-    interval_kind::IntervalKind, CardState, LearnState, NextCardStates, RelearnState, StateContext,
+    interval_kind::IntervalKind, memory::NextMemoryStates, CardState, LearnState, MemoryModel,
+    MemoryState, NextCardStates, RelearnState, StateContext,
 };
 use crate::revlog::RevlogReviewKind;
 
@@ -19,6 +20,8 @@ pub struct ReviewState {
     pub ease_factor: f32,
     pub lapses: u32,
     pub leeched: bool,
+    /// Only tracked when the memory model is enabled.
+    pub memory_state: Option<MemoryState>,
 }
 
 impl Default for ReviewState {
@@ -29,6 +32,7 @@ impl Default for ReviewState {
             ease_factor: INITIAL_EASE_FACTOR,
             lapses: 0,
             leeched: false,
+            memory_state: None,
         }
     }
 }
@@ -53,32 +57,71 @@ impl ReviewState {
     }
 
     pub(crate) fn next_states(self, ctx: &StateContext) -> NextCardStates {
-        let (hard_interval, good_interval, easy_interval) = self.passing_review_intervals(ctx);
+        let memory = ctx.memory_model.map(|model| {
+            (
+                model,
+                model.next_memory_states(Some(self.memory_state_or_estimate()), self.elapsed_days),
+            )
+        });
+        let (hard_interval, good_interval, easy_interval) = match &memory {
+            Some((model, states)) => memory_review_intervals(ctx, model, states),
+            None => self.passing_review_intervals(ctx),
+        };
+        let memory = memory.map(|(_, states)| states);
 
         NextCardStates {
             current: self.into(),
-            again: self.answer_again(ctx),
-            hard: self.answer_hard(hard_interval).into(),
-            good: self.answer_good(good_interval).into(),
-            easy: self.answer_easy(easy_interval).into(),
+            again: self.answer_again(ctx, memory.map(|m| m.again)),
+            hard: self
+                .answer_hard(hard_interval, memory.map(|m| m.hard))
+                .into(),
+            good: self
+                .answer_good(good_interval, memory.map(|m| m.good))
+                .into(),
+            easy: self
+                .answer_easy(easy_interval, memory.map(|m| m.easy))
+                .into(),
         }
     }
 
+    /// With the memory model, the interval that reaches the desired
+    /// retention. Otherwise the current interval times the lapse multiplier.
+    /// Either way it is at least the minimum lapse interval.
     pub(crate) fn failing_review_interval(self, ctx: &StateContext) -> u32 {
-        (((self.scheduled_days as f32) * ctx.lapse_multiplier) as u32)
-            .max(ctx.minimum_lapse_interval)
-            .max(1)
+        if let Some(model) = &ctx.memory_model {
+            let state = self.memory_state_or_estimate();
+            let (minimum, maximum) = ctx.min_and_max_review_intervals(ctx.minimum_lapse_interval);
+            (model.interval(state.stability).round() as u32)
+                .max(minimum)
+                .min(maximum)
+        } else {
+            (((self.scheduled_days as f32) * ctx.lapse_multiplier) as u32)
+                .max(ctx.minimum_lapse_interval)
+                .max(1)
+        }
     }
 
-    fn answer_again(self, ctx: &StateContext) -> CardState {
+    /// Cards reviewed before the memory model was enabled don't have a memory
+    /// state yet.
+    fn memory_state_or_estimate(self) -> MemoryState {
+        self.memory_state
+            .unwrap_or_else(|| MemoryState::from_sm2(self.scheduled_days, self.ease_factor))
+    }
+
+    fn answer_again(self, ctx: &StateContext, memory_state: Option<MemoryState>) -> CardState {
         let lapses = self.lapses + 1;
         let leeched = leech_threshold_met(lapses, ctx.leech_threshold);
         let again_review = ReviewState {
-            scheduled_days: self.failing_review_interval(ctx),
+            scheduled_days: ReviewState {
+                memory_state,
+                ..self
+            }
+            .failing_review_interval(ctx),
             elapsed_days: 0,
             ease_factor: (self.ease_factor + EASE_FACTOR_AGAIN_DELTA).max(MINIMUM_EASE_FACTOR),
             lapses,
             leeched,
+            memory_state,
         };
 
         if let Some(again_delay) = ctx.relearn_steps.again_delay_secs_relearn() {
@@ -86,6 +129,7 @@ impl ReviewState {
                 learning: LearnState {
                     remaining_steps: ctx.relearn_steps.remaining_for_failed(),
                     scheduled_secs: again_delay,
+                    memory_state: None,
                 },
                 review: again_review,
             }
@@ -95,28 +139,31 @@ impl ReviewState {
         }
     }
 
-    fn answer_hard(self, scheduled_days: u32) -> ReviewState {
+    fn answer_hard(self, scheduled_days: u32, memory_state: Option<MemoryState>) -> ReviewState {
         ReviewState {
             scheduled_days,
             elapsed_days: 0,
             ease_factor: (self.ease_factor + EASE_FACTOR_HARD_DELTA).max(MINIMUM_EASE_FACTOR),
+            memory_state,
             ..self
         }
     }
 
-    fn answer_good(self, scheduled_days: u32) -> ReviewState {
+    fn answer_good(self, scheduled_days: u32, memory_state: Option<MemoryState>) -> ReviewState {
         ReviewState {
             scheduled_days,
             elapsed_days: 0,
+            memory_state,
             ..self
         }
     }
 
-    fn answer_easy(self, scheduled_days: u32) -> ReviewState {
+    fn answer_easy(self, scheduled_days: u32, memory_state: Option<MemoryState>) -> ReviewState {
         ReviewState {
             scheduled_days,
             elapsed_days: 0,
             ease_factor: self.ease_factor + EASE_FACTOR_EASY_DELTA,
+            memory_state,
             ..self
         }
     }
@@ -194,6 +241,20 @@ impl ReviewState {
     }
 }
 
+/// The intervals for hard, good and easy that reach the desired retention,
+/// each longer than the previous one if possible. The interval multiplier
+/// does not apply.
+fn memory_review_intervals(
+    ctx: &StateContext,
+    model: &MemoryModel,
+    states: &NextMemoryStates,
+) -> (u32, u32, u32) {
+    let hard_interval = ctx.fuzzed_memory_interval(model, states.hard, 1);
+    let good_interval = ctx.fuzzed_memory_interval(model, states.good, hard_interval + 1);
+    let easy_interval = ctx.fuzzed_memory_interval(model, states.easy, good_interval + 1);
+    (hard_interval, good_interval, easy_interval)
+}
+
 /// True when lapses is at threshold, or every half threshold after that.
 /// Non-even thresholds round up the half threshold.
 fn leech_threshold_met(lapses: u32, threshold: u32) -> bool {
@@ -264,6 +325,7 @@ mod test {
             ease_factor: 1.3,
             lapses: 0,
             leeched: false,
+            memory_state: None,
         };
         ctx.fuzz_factor = Some(0.0);
         assert_eq!(state.passing_review_intervals(&ctx), (2, 3, 4));
@@ -19,6 +19,10 @@ impl<'a> LearningSteps<'a> {
         LearningSteps { steps }
     }
 
+    pub(crate) fn steps(self) -> &'a [f32] {
+        self.steps
+    }
+
     /// Strip off 'learning today', and ensure index is in bounds.
     fn get_index(self, remaining: u32) -> usize {
         let total = self.steps.len();
@@ -0,0 +1,103 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+use std::collections::HashMap;
+
+use crate::prelude::*;
+
+impl Collection {
+    /// Move reviews due from `first_day` to `last_day` days from today
+    /// (inclusive) out of that range. The periods before and after it, each as
+    /// long as the range, are filled up evenly: every card goes to the least
+    /// busy of those days, preferring the one closest to its due date, but
+    /// never to today or a day before it was last reviewed. Intervals are left
+    /// alone. Returns the number of cards moved.
+    pub fn reschedule_for_vacation(
+        &mut self,
+        first_day: u32,
+        last_day: u32,
+    ) -> Result<OpOutput<usize>> {
+        if first_day > last_day {
+            return Err(AnkiError::invalid_input("vacation ends before it starts"));
+        }
+        let usn = self.usn()?;
+        let today = self.timing_today()?.days_elapsed;
+        let (start, end) = (today + first_day, today + last_day);
+        let length = end - start + 1;
+        let days_before = start.saturating_sub(length).max(today + 1)..start;
+        let days_after = end + 1..=end + length;
+        self.transact(Op::RescheduleForVacation, |col| {
+            let mut counts: HashMap<u32, u32> = HashMap::new();
+            for days in col.storage.review_due_counts_by_deck(today)?.into_values() {
+                for (day, count) in days {
+                    *counts.entry(day).or_default() += count;
+                }
+            }
+
+            let cards = col.storage.review_cards_due_between(start, end)?;
+            let moved = cards.len();
+            for mut card in cards {
+                let last_review = card.due - card.interval as i32;
+                let day = days_before
+                    .clone()
+                    .filter(|&day| day as i32 > last_review)
+                    .chain(days_after.clone())
+                    .min_by_key(|&day| {
+                        (
+                            counts.get(&day).copied().unwrap_or_default(),
+                            (day as i32 - card.due).abs(),
+                        )
+                    })
+                    .unwrap_or(end + 1);
+                *counts.entry(day).or_default() += 1;
+                let original = card.clone();
+                card.due = day as i32;
+                col.log_manually_scheduled_review(&card, &original, usn)?;
+                col.update_card_inner(&mut card, original, usn)?;
+            }
+
+            Ok(moved)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{collection::open_test_collection, revlog::RevlogReviewKind};
+
+    #[test]
+    fn vacation() -> Result<()> {
+        let mut col = open_test_collection();
+        let today = col.timing_today()?.days_elapsed;
+        let add_review = |col: &mut Collection, due: u32| -> Result<CardId> {
+            Ok(col.add_review_card((today + due) as i32, 10)?.id)
+        };
+        // a vacation from day 3 to day 4 leaves days 1 and 2 before it, and 5
+        // and 6 after it; day 2 is already busy
+        add_review(&mut col, 2)?;
+        let cards = [
+            add_review(&mut col, 3)?,
+            add_review(&mut col, 3)?,
+            add_review(&mut col, 4)?,
+            add_review(&mut col, 4)?,
+        ];
+        // not affected
+        add_review(&mut col, 7)?;
+
+        let moved = col.reschedule_for_vacation(3, 4)?.output;
+        assert_eq!(moved, 4);
+        let due: Vec<_> = cards
+            .iter()
+            .map(|&cid| col.storage.get_card(cid).unwrap().unwrap().due - today as i32)
+            .collect();
+        assert_eq!(due, [1, 5, 6, 5]);
+        let revlog = col.storage.get_revlog_entries_for_card(cards[0])?;
+        assert_eq!(revlog.len(), 1);
+        assert_eq!(revlog[0].review_kind, RevlogReviewKind::Manual);
+
+        assert!(col.reschedule_for_vacation(4, 3).is_err());
+
+        Ok(())
+    }
+}
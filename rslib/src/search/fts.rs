@@ -0,0 +1,128 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+//! An optional FTS5 index of note fields, which unqualified searches for
+//! plain words use instead of scanning every note.
+//!
+//! The index is kept up to date by temporary triggers this client adds when
+//! it opens the collection. Notes changed by a client without them, such as
+//! an older version, aren't reindexed until the database is checked.
+
+use crate::prelude::*;
+
+impl Collection {
+    /// Create or remove the full text index. Creating it indexes all existing
+    /// notes, which can take a while on large collections.
+    pub fn set_full_text_index_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.transact_no_undo(|col| {
+            if enabled {
+                col.storage.create_full_text_index()
+            } else {
+                col.storage.drop_full_text_index()
+            }
+        })
+    }
+
+    pub fn full_text_index_enabled(&self) -> Result<bool> {
+        self.storage.full_text_index_exists()
+    }
+}
+
+/// The FTS5 query matching `text` anywhere in a note's fields, if it consists
+/// of at least three letters, digits or spaces. Shorter text, wildcards and
+/// escapes need LIKE. Results can differ from LIKE's: the index ignores case
+/// outside ASCII too, and holds the fields without HTML, so text split by a
+/// tag matches while text inside a tag doesn't. Like LIKE, it only matches
+/// combining characters literally.
+pub(super) fn full_text_query(text: &str) -> Option<String> {
+    if text.chars().count() >= 3 && text.chars().all(|c| c.is_alphanumeric() || c == ' ') {
+        Some(format!("\"{}\"", text))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::collection::CollectionBuilder;
+
+    #[test]
+    fn query() {
+        assert_eq!(full_text_query("café"), Some(r#""café""#.into()));
+        assert_eq!(full_text_query("two words"), Some(r#""two words""#.into()));
+        assert_eq!(full_text_query("ab"), None);
+        assert_eq!(full_text_query("ca*t"), None);
+        assert_eq!(full_text_query(r"ca\_t"), None);
+    }
+
+    #[test]
+    fn full_text_index() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let col_path = dir.path().join("col.anki2");
+        let mut col = CollectionBuilder::new(&col_path).build()?;
+        let nt = col.get_notetype_by_name("Basic")?.unwrap();
+        let mut note = nt.new_note();
+        note.set_field(0, "<b>café</b> au lait")?;
+        col.add_note(&mut note, DeckId(1))?;
+        let nid = note.id;
+
+        col.set_full_text_index_enabled(true)?;
+        assert!(col.full_text_index_enabled()?);
+        // existing notes are indexed, without HTML
+        assert_eq!(col.search_notes_unordered("café")?, vec![nid]);
+        assert_eq!(col.search_notes_unordered(r#""fé au""#)?, vec![nid]);
+        // and case is ignored outside ASCII
+        assert_eq!(col.search_notes_unordered("CAFÉ")?, vec![nid]);
+        // combining characters matter, like without the index
+        assert!(col.search_notes_unordered("cafe")?.is_empty());
+
+        // and new and changed notes too
+        let mut note = nt.new_note();
+        note.set_field(0, "concatenate")?;
+        col.add_note(&mut note, DeckId(1))?;
+        assert_eq!(col.search_notes_unordered("cat")?, vec![note.id]);
+        note.set_field(0, "join")?;
+        col.update_note(&mut note)?;
+        assert!(col.search_notes_unordered("cat")?.is_empty());
+        // wildcards still work without the index
+        assert_eq!(col.search_notes_unordered("j*n")?, vec![note.id]);
+        // including by raw SQL, as legacy code does
+        col.storage
+            .db
+            .execute("update notes set flds = 'legacy' where id = ?", [note.id])?;
+        assert_eq!(col.search_notes_unordered("legacy")?, vec![note.id]);
+        col.remove_notes(&[note.id])?;
+        assert!(col.search_notes_unordered("legacy")?.is_empty());
+
+        // and after reopening the collection
+        col.close(false)?;
+        let mut col = CollectionBuilder::new(&col_path).build()?;
+        col.storage.db.execute(
+            "update notes set flds = 'café reopened' where id = ?",
+            [nid],
+        )?;
+        assert_eq!(col.search_notes_unordered("reopened")?, vec![nid]);
+
+        // the database check rebuilds the index
+        col.storage.db.execute_batch("delete from notes_fts")?;
+        assert!(col.search_notes_unordered("café")?.is_empty());
+        col.check_database(|_, _| ())?;
+        assert_eq!(col.search_notes_unordered("café")?, vec![nid]);
+
+        col.set_full_text_index_enabled(false)?;
+        assert!(!col.full_text_index_enabled()?);
+        assert_eq!(col.search_notes_unordered("café")?, vec![nid]);
+        assert!(col.search_notes_unordered("cafe")?.is_empty());
+        // without it, searches see the HTML, and case only folds in ASCII
+        col.storage.db.execute(
+            "update notes set flds = '<b>café</b> au lait' where id = ?",
+            [nid],
+        )?;
+        assert!(col.search_notes_unordered(r#""fé au""#)?.is_empty());
+        assert!(col.search_notes_unordered("CAFÉ")?.is_empty());
+        assert_eq!(col.search_notes_unordered("CAF")?, vec![nid]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright: Ankitects Pty Ltd and contributors
+// License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
+
+//! Finding the media a note's fields use, for the has:, media: and
+//! missing-media: searches.
+
+use std::{collections::HashSet, path::Path};
+
+use super::MediaKind;
+use crate::{
+    card_rendering::sound_tag_filenames,
+    latex::contains_latex,
+    text::{extract_media_refs, normalize_to_nfc, REMOTE_FILENAME},
+};
+
+/// Sound tags can also play these.
+const VIDEO_EXTENSIONS: &[&str] = &[
+    "3gp", "avi", "flv", "m4v", "mkv", "mov", "mp4", "mpeg", "mpg", "ogv", "webm", "wmv",
+];
+
+pub(crate) fn fields_have_media(fields: &str, kind: MediaKind) -> bool {
+    match kind {
+        MediaKind::Audio => {
+            sound_tag_filenames(fields)
+                .into_iter()
+                .any(|fname| !is_video(fname))
+                || html_media_tags(fields, "<audio")
+        }
+        MediaKind::Image => html_media_tags(fields, "<img"),
+        MediaKind::Latex => contains_latex(fields),
+    }
+}
+
+/// The local files the fields refer to, with their names in NFC form.
+pub(crate) fn referenced_media_files(fields: &str) -> Vec<String> {
+    extract_media_refs(fields)
+        .into_iter()
+        .filter(|media_ref| !REMOTE_FILENAME.is_match(media_ref.fname))
+        .map(|media_ref| normalize_to_nfc(&media_ref.fname_decoded).into_owned())
+        .collect()
+}
+
+/// The names of the files in the media folder, in NFC form. An unreadable
+/// folder is treated as empty.
+pub(crate) fn files_in_media_folder(folder: &Path) -> HashSet<String> {
+    folder
+        .read_dir()
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    entry
+                        .file_name()
+                        .to_str()
+                        .map(|fname| normalize_to_nfc(fname).into_owned())
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn is_video(fname: &str) -> bool {
+    fname
+        .rsplit_once('.')
+        .map(|(_, ext)| VIDEO_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        .unwrap_or_default()
+}
+
+/// True if a media reference in the fields starts with `tag`.
+fn html_media_tags(fields: &str, tag: &str) -> bool {
+    extract_media_refs(fields).into_iter().any(|media_ref| {
+        media_ref
+            .full_ref
+            .get(..tag.len())
+            .map(|start| start.eq_ignore_ascii_case(tag))
+            .unwrap_or_default()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::{collection::CollectionBuilder, prelude::*};
+
+    #[test]
+    fn media_kinds() {
+        let fields = "[sound:clip.MP4]\x1f<img src='a.jpg'>";
+        assert!(!fields_have_media(fields, MediaKind::Audio));
+        assert!(fields_have_media(fields, MediaKind::Image));
+        assert!(!fields_have_media(fields, MediaKind::Latex));
+
+        let fields = "[sound:word.mp3]\x1f[$]x^2[/$]";
+        assert!(fields_have_media(fields, MediaKind::Audio));
+        assert!(!fields_have_media(fields, MediaKind::Image));
+        assert!(fields_have_media(fields, MediaKind::Latex));
+        assert!(fields_have_media(
+            r#"<audio src="a.ogg">"#,
+            MediaKind::Audio
+        ));
+
+        assert_eq!(
+            referenced_media_files(
+                r#"<img src="a&amp;b.jpg"><img src="https://example.com/c.jpg">[sound:d.mp3]"#
+            ),
+            vec!["a&b.jpg", "d.mp3"]
+        );
+    }
+
+    #[test]
+    fn searching() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut col = CollectionBuilder::default()
+            .set_media_paths(dir.path().to_path_buf(), dir.path().join("media.db"))
+            .build()?;
+        fs::write(dir.path().join("a.jpg"), "image")?;
+        let nt = col.get_notetype_by_name("Basic")?.unwrap();
+        let mut add_note = |text: &str| -> Result<NoteId> {
+            let mut note = nt.new_note();
+            note.set_field(0, text)?;
+            col.add_note(&mut note, DeckId(1))?;
+            Ok(note.id)
+        };
+        let image = add_note("<img src=a.jpg>")?;
+        let audio = add_note("[sound:b.mp3]")?;
+
+        let mut search = |text: &str| -> Result<Vec<NoteId>> {
+            let mut nids = col.search_notes_unordered(text)?;
+            nids.sort();
+            Ok(nids)
+        };
+        assert_eq!(search("has:image")?, vec![image]);
+        assert_eq!(search("has:audio")?, vec![audio]);
+        assert_eq!(search("media:A.JPG")?, vec![image]);
+        assert_eq!(search("media:*.mp3")?, vec![audio]);
+        assert_eq!(search("missing-media:")?, vec![audio]);
+        assert_eq!(search("missing-media:*.jpg")?, vec![]);
+        assert_eq!(search("has:image OR missing-media:")?, vec![image, audio]);
+
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 // Copyright: Ankitects Pty Ltd and contributors
 // License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
 
+mod fts;
+pub(crate) mod media;
 mod parser;
 mod sqlwriter;
 pub(crate) mod writer;
@@ -8,7 +10,8 @@ pub(crate) mod writer;
 use std::borrow::Cow;
 
 pub use parser::{
-    parse as parse_search, Node, PropertyKind, RatingKind, SearchNode, StateKind, TemplateKind,
+    parse as parse_search, ComparisonOperator, DateBound, DateRange, FieldValue, MediaKind, Node,
+    PropertyKind, RatingKind, SearchNode, StateKind, TemplateKind,
 };
 use rusqlite::{params_from_iter, types::FromSql};
 use sqlwriter::{RequiredTable, SqlWriter};
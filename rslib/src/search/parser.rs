@@ -1,6 +1,7 @@
 // Copyright: Ankitects Pty Ltd and contributors
 // License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
 
+use chrono::{Datelike, NaiveDate};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use nom::{
@@ -17,6 +18,7 @@ use regex::{Captures, Regex};
 use crate::{
     error::{ParseError, Result, SearchErrorKind as FailKind},
     prelude::*,
+    revlog::RevlogReviewKind,
 };
 
 type IResult<'a, O> = std::result::Result<(&'a str, O), nom::Err<ParseError<'a>>>;
@@ -91,8 +93,24 @@ pub enum SearchNode {
         text: String,
         is_re: bool,
     },
+    /// eg price>10 or due_date<2026-12-01
+    FieldComparison {
+        field: String,
+        operator: ComparisonOperator,
+        value: FieldValue,
+        /// The whole term, searched for as text if no field has the name.
+        text: String,
+    },
+    /// eg year:1900..1950, with both ends included
+    FieldRange {
+        field: String,
+        start: Option<FieldValue>,
+        end: Option<FieldValue>,
+    },
     AddedInDays(u32),
+    AddedInRange(DateRange),
     EditedInDays(u32),
+    EditedInRange(DateRange),
     CardTemplate(TemplateKind),
     Deck(String),
     /// Matches cards in a single deck (original_deck_id is not checked).
@@ -101,12 +119,23 @@ pub enum SearchNode {
     /// checked).
     DeckIdWithChildren(DeckId),
     IntroducedInDays(u32),
+    IntroducedInRange(DateRange),
     NotetypeId(NotetypeId),
     Notetype(String),
     Rated {
         days: u32,
         ease: RatingKind,
     },
+    RatedInRange {
+        range: DateRange,
+        ease: RatingKind,
+    },
+    /// Cards with a review log entry of the given kind, optionally within the
+    /// last days.
+    ReviewKind {
+        kind: RevlogReviewKind,
+        days: Option<u32>,
+    },
     Tag(String),
     Duplicates {
         notetype_id: NotetypeId,
@@ -124,6 +153,12 @@ pub enum SearchNode {
     Regex(String),
     NoCombining(String),
     WordBoundary(String),
+    HasMedia(MediaKind),
+    /// Notes referencing a media file whose name matches the glob.
+    Media(String),
+    /// Like [SearchNode::Media], but only counting files absent from the media
+    /// folder. An empty glob matches any file.
+    MissingMedia(String),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -135,6 +170,131 @@ pub enum PropertyKind {
     Ease(f32),
     Position(u32),
     Rated(i32, RatingKind),
+    /// The average time taken to answer, in seconds.
+    AnswerTime(f32),
+    /// The number of answers, optionally within the last days.
+    Reviews(u32, Option<u32>),
+    /// The number of times "again" was chosen, optionally within the last
+    /// answers.
+    Again(u32, Option<u32>),
+    /// The day of the first answer.
+    FirstReview(DateBound),
+}
+
+/// How the contents of a field are compared with a [FieldValue].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ComparisonOperator {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl ComparisonOperator {
+    fn parse(text: &str) -> Option<Self> {
+        Some(match text {
+            "<" => Self::Less,
+            "<=" => Self::LessOrEqual,
+            "=" => Self::Equal,
+            ">=" => Self::GreaterOrEqual,
+            ">" => Self::Greater,
+            _ => return None,
+        })
+    }
+
+    /// As written in searches, which is also valid SQL.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Equal => "=",
+            Self::GreaterOrEqual => ">=",
+            Self::Greater => ">",
+        }
+    }
+}
+
+/// A number or date the contents of a field can be compared with.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FieldValue {
+    Number(f64),
+    /// eg 2026-12-01
+    Date(NaiveDate),
+}
+
+impl FieldValue {
+    /// Leading and trailing whitespace is ignored.
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Ok(number) = text.parse::<f64>() {
+            number.is_finite().then(|| FieldValue::Number(number))
+        } else {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .map(FieldValue::Date)
+        }
+    }
+
+    /// The value as compared in SQL; dates become a count of days.
+    pub(crate) fn sql_value(self) -> f64 {
+        match self {
+            FieldValue::Number(number) => number,
+            FieldValue::Date(date) => date.num_days_from_ce() as f64,
+        }
+    }
+
+    pub(crate) fn is_date(self) -> bool {
+        matches!(self, FieldValue::Date(_))
+    }
+}
+
+/// Counting back further than this is rejected, as chrono can't represent
+/// dates that far back.
+const MAX_DAYS_AGO: u32 = 1_000_000;
+
+/// The days from the first day of `start` up to and including the last day of
+/// `end`. At least one of them is set.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DateRange {
+    pub start: Option<DateBound>,
+    pub end: Option<DateBound>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DateBound {
+    /// eg 2026-09-30
+    Day(NaiveDate),
+    /// eg 2026-09
+    Month { year: i32, month: u32 },
+    /// A single day, counting back from today, which is 1, as in `added:1`.
+    DaysAgo(u32),
+}
+
+impl DateBound {
+    pub(crate) fn first_day(self, today: NaiveDate) -> NaiveDate {
+        match self {
+            DateBound::Day(day) => day,
+            DateBound::Month { year, month } => NaiveDate::from_ymd(year, month, 1),
+            DateBound::DaysAgo(days) => today
+                .checked_sub_signed(chrono::Duration::days(days as i64 - 1))
+                .unwrap_or(chrono::naive::MIN_DATE),
+        }
+    }
+
+    pub(crate) fn last_day(self, today: NaiveDate) -> NaiveDate {
+        match self {
+            DateBound::Month { year, month } => {
+                let (year, month) = if month == 12 {
+                    (year + 1, 1)
+                } else {
+                    (year, month + 1)
+                };
+                NaiveDate::from_ymd(year, month, 1).pred()
+            }
+            _ => self.first_day(today),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -149,6 +309,32 @@ pub enum StateKind {
     Suspended,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MediaKind {
+    Audio,
+    Image,
+    Latex,
+}
+
+impl MediaKind {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "audio" => Some(MediaKind::Audio),
+            "image" => Some(MediaKind::Image),
+            "latex" => Some(MediaKind::Latex),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            MediaKind::Audio => "audio",
+            MediaKind::Image => "image",
+            MediaKind::Latex => "latex",
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum TemplateKind {
     Ordinal(u16),
@@ -292,7 +478,7 @@ fn text(s: &str) -> IResult<Node> {
 /// Quoted text, including the outer double quotes.
 fn quoted_term(s: &str) -> IResult<Node> {
     let (remaining, term) = quoted_term_str(s)?;
-    Ok((remaining, Node::Search(search_node_for_text(term)?)))
+    Ok((remaining, Node::Search(search_node_for_quoted_text(term)?)))
 }
 
 /// eg deck:"foo bar" - quotes must come after the :
@@ -319,7 +505,7 @@ fn unquoted_term(s: &str) -> IResult<Node> {
             } else if term.eq_ignore_ascii_case("or") {
                 Ok((tail, Node::Or))
             } else {
-                Ok((tail, Node::Search(search_node_for_text(term)?)))
+                Ok((tail, Node::Search(search_node_for_unquoted_text(term)?)))
             }
         }
         Err(err) => {
@@ -361,6 +547,34 @@ fn quoted_term_str(s: &str) -> IResult<&str> {
     }
 }
 
+/// Like [search_node_for_text], but text without a colon may also compare the
+/// contents of a field. Quoted text is always searched for as is.
+fn search_node_for_unquoted_text(s: &str) -> ParseResult<SearchNode> {
+    match parse_field_comparison(s) {
+        Some((field, operator, value)) => Ok(SearchNode::FieldComparison {
+            field: unescape(field)?,
+            operator,
+            value,
+            text: unescape(s)?,
+        }),
+        None => search_node_for_text(s),
+    }
+}
+
+/// Like [search_node_for_text], but quoted `has:` and `media:` search fields of
+/// those names, so that such fields can still be searched.
+fn search_node_for_quoted_text(s: &str) -> ParseResult<SearchNode> {
+    match s.split_once(':') {
+        Some((key, val)) if is_media_key(key) => parse_single_field(key, val),
+        _ => search_node_for_text(s),
+    }
+}
+
+/// True for the keys of media searches that are likely to be field names too.
+pub(super) fn is_media_key(key: &str) -> bool {
+    key.eq_ignore_ascii_case("has") || key.eq_ignore_ascii_case("media")
+}
+
 /// Determine if text is a qualified search, and handle escaped chars.
 /// Expect well-formed input: unempty and no trailing \.
 fn search_node_for_text(s: &str) -> ParseResult<SearchNode> {
@@ -402,6 +616,10 @@ fn search_node_for_text_with_argument<'a>(
         "nc" => SearchNode::NoCombining(unescape(val)?),
         "w" => SearchNode::WordBoundary(unescape(val)?),
         "dupe" => parse_dupe(val)?,
+        "revlog-kind" => parse_review_kind(val)?,
+        "has" => parse_has_media(val)?,
+        "media" => SearchNode::Media(unescape(val)?),
+        "missing-media" => SearchNode::MissingMedia(unescape(val)?),
         // anything else is a field search
         _ => parse_single_field(key, val)?,
     })
@@ -427,12 +645,19 @@ fn parse_flag(s: &str) -> ParseResult<SearchNode> {
     }
 }
 
-/// eg resched:3
+/// eg resched:3 or resched:2026-09
 fn parse_resched(s: &str) -> ParseResult<SearchNode> {
-    parse_u32(s, "resched:").map(|days| SearchNode::Rated {
-        days,
-        ease: RatingKind::ManualReschedule,
-    })
+    if let Some(range) = parse_date_range(s, "resched:")? {
+        Ok(SearchNode::RatedInRange {
+            range,
+            ease: RatingKind::ManualReschedule,
+        })
+    } else {
+        parse_u32(s, "resched:").map(|days| SearchNode::Rated {
+            days,
+            ease: RatingKind::ManualReschedule,
+        })
+    }
 }
 
 /// eg prop:ivl>3, prop:ease!=2.5
@@ -446,6 +671,10 @@ fn parse_prop(prop_clause: &str) -> ParseResult<SearchNode> {
         tag("pos"),
         tag("rated"),
         tag("resched"),
+        tag("time"),
+        tag("reviews"),
+        tag("again"),
+        tag("first"),
     ))(prop_clause)
     .map_err(|_| {
         parse_failure(
@@ -476,6 +705,16 @@ fn parse_prop(prop_clause: &str) -> ParseResult<SearchNode> {
         "reps" => PropertyKind::Reps(parse_u32(num, prop_clause)?),
         "lapses" => PropertyKind::Lapses(parse_u32(num, prop_clause)?),
         "pos" => PropertyKind::Position(parse_u32(num, prop_clause)?),
+        "time" => PropertyKind::AnswerTime(parse_answer_time(num, prop_clause)?),
+        "reviews" => {
+            let (count, days) = parse_count_in_window(num, prop_clause)?;
+            PropertyKind::Reviews(count, days.map(|days| days.max(1)))
+        }
+        "again" => {
+            let (count, reviews) = parse_count_in_window(num, prop_clause)?;
+            PropertyKind::Again(count, reviews)
+        }
+        "first" => PropertyKind::FirstReview(parse_day(num, prop_clause)?),
         _ => unreachable!(),
     };
 
@@ -569,6 +808,54 @@ fn parse_answer_button<'a>(num: Option<&str>, context: &'a str) -> ParseResult<'
     })
 }
 
+/// eg 30, 30s or 1.5m, in seconds
+fn parse_answer_time<'a>(num: &str, context: &'a str) -> ParseResult<'a, f32> {
+    if let Some(minutes) = num.strip_suffix('m') {
+        parse_f32(minutes, context).map(|minutes| minutes * 60.0)
+    } else {
+        parse_f32(num.strip_suffix('s').unwrap_or(num), context)
+    }
+}
+
+/// eg 5 or 5:10
+fn parse_count_in_window<'a>(num: &str, context: &'a str) -> ParseResult<'a, (u32, Option<u32>)> {
+    let mut it = num.splitn(2, ':');
+    let count = parse_u32(it.next().unwrap(), context)?;
+    let window = it.next().map(|n| parse_u32(n, context)).transpose()?;
+    Ok((count, window))
+}
+
+/// eg 0 for today, -7 or 2026-09-30
+fn parse_day<'a>(num: &str, context: &'a str) -> ParseResult<'a, DateBound> {
+    if num.contains("..") {
+        return Err(parse_failure(
+            context,
+            FailKind::InvalidDate {
+                context: context.into(),
+                provided: num.into(),
+            },
+        ));
+    }
+    if let Some(range) = parse_date_range(num, context)? {
+        Ok(range.start.unwrap())
+    } else {
+        let days = parse_negative_i32(num, context)?;
+        u32::try_from(1 - days as i64)
+            .ok()
+            .filter(|&days| days <= MAX_DAYS_AGO)
+            .map(DateBound::DaysAgo)
+            .ok_or_else(|| {
+                parse_failure(
+                    context,
+                    FailKind::InvalidDate {
+                        context: context.into(),
+                        provided: num.into(),
+                    },
+                )
+            })
+    }
+}
+
 fn parse_prop_rated<'a>(num: &str, context: &'a str) -> ParseResult<'a, PropertyKind> {
     let mut it = num.splitn(2, ':');
     let days = parse_negative_i32(it.next().unwrap(), context)?;
@@ -576,28 +863,111 @@ fn parse_prop_rated<'a>(num: &str, context: &'a str) -> ParseResult<'a, Property
     Ok(PropertyKind::Rated(days, button))
 }
 
-/// eg added:1
+/// eg added:1, added:2026-01-01..2026-03-31 or added:..2025-12
 fn parse_added(s: &str) -> ParseResult<SearchNode> {
-    parse_u32(s, "added:").map(|n| SearchNode::AddedInDays(n.max(1)))
+    if let Some(range) = parse_date_range(s, "added:")? {
+        Ok(SearchNode::AddedInRange(range))
+    } else {
+        parse_u32(s, "added:").map(|n| SearchNode::AddedInDays(n.max(1)))
+    }
 }
 
-/// eg edited:1
+/// eg edited:1 or edited:2026-09
 fn parse_edited(s: &str) -> ParseResult<SearchNode> {
-    parse_u32(s, "edited:").map(|n| SearchNode::EditedInDays(n.max(1)))
+    if let Some(range) = parse_date_range(s, "edited:")? {
+        Ok(SearchNode::EditedInRange(range))
+    } else {
+        parse_u32(s, "edited:").map(|n| SearchNode::EditedInDays(n.max(1)))
+    }
 }
 
-/// eg introduced:1
+/// eg introduced:1 or introduced:2026-09-01..
 fn parse_introduced(s: &str) -> ParseResult<SearchNode> {
-    parse_u32(s, "introduced:").map(|n| SearchNode::IntroducedInDays(n.max(1)))
+    if let Some(range) = parse_date_range(s, "introduced:")? {
+        Ok(SearchNode::IntroducedInRange(range))
+    } else {
+        parse_u32(s, "introduced:").map(|n| SearchNode::IntroducedInDays(n.max(1)))
+    }
 }
 
-/// eg rated:3 or rated:10:2
+/// eg rated:3, rated:10:2 or rated:2026-09:1
 /// second arg must be between 1-4
 fn parse_rated(s: &str) -> ParseResult<SearchNode> {
     let mut it = s.splitn(2, ':');
-    let days = parse_u32(it.next().unwrap(), "rated:")?.max(1);
+    let days = it.next().unwrap();
     let button = parse_answer_button(it.next(), s)?;
-    Ok(SearchNode::Rated { days, ease: button })
+    if let Some(range) = parse_date_range(days, "rated:")? {
+        Ok(SearchNode::RatedInRange {
+            range,
+            ease: button,
+        })
+    } else {
+        let days = parse_u32(days, "rated:")?.max(1);
+        Ok(SearchNode::Rated { days, ease: button })
+    }
+}
+
+/// A range of dates or of days back, if `s` contains one or a single date.
+/// Either end of a range may be left open, eg `2026-01-01..2026-03-31`, `7..3`
+/// or `..2025-12`.
+fn parse_date_range<'a>(s: &str, context: &'a str) -> ParseResult<'a, Option<DateRange>> {
+    lazy_static! {
+        static ref DATE: Regex = Regex::new(r"^(\d{4})-(\d{1,2})(?:-(\d{1,2}))?$").unwrap();
+    }
+    let failure = || {
+        parse_failure(
+            context,
+            FailKind::InvalidDate {
+                context: context.into(),
+                provided: s.into(),
+            },
+        )
+    };
+    let parse_bound = |bound: &str| -> ParseResult<'a, Option<DateBound>> {
+        if bound.is_empty() {
+            return Ok(None);
+        }
+        if let Ok(days) = bound.parse::<u32>() {
+            return if days > MAX_DAYS_AGO {
+                Err(failure())
+            } else {
+                Ok(Some(DateBound::DaysAgo(days.max(1))))
+            };
+        }
+        let caps = DATE.captures(bound).ok_or_else(failure)?;
+        let year: i32 = caps[1].parse().map_err(|_| failure())?;
+        let month: u32 = caps[2].parse().map_err(|_| failure())?;
+        if let Some(day) = caps.get(3) {
+            let day: u32 = day.as_str().parse().map_err(|_| failure())?;
+            NaiveDate::from_ymd_opt(year, month, day)
+                .map(|date| Some(DateBound::Day(date)))
+                .ok_or_else(failure)
+        } else if (1..=12).contains(&month) {
+            Ok(Some(DateBound::Month { year, month }))
+        } else {
+            Err(failure())
+        }
+    };
+
+    let range = if let Some((start, end)) = s.split_once("..") {
+        DateRange {
+            start: parse_bound(start)?,
+            end: parse_bound(end)?,
+        }
+    } else if DATE.is_match(s) {
+        let bound = parse_bound(s)?;
+        DateRange {
+            start: bound,
+            end: bound,
+        }
+    } else {
+        return Ok(None);
+    };
+    if range.start.is_none() && range.end.is_none() {
+        Err(failure())
+    } else {
+        Ok(Some(range))
+    }
 }
 
 /// eg is:due
@@ -616,6 +986,34 @@ fn parse_state(s: &str) -> ParseResult<SearchNode> {
     }))
 }
 
+/// eg has:audio
+fn parse_has_media(s: &str) -> ParseResult<SearchNode> {
+    MediaKind::from_name(s)
+        .map(SearchNode::HasMedia)
+        .ok_or_else(|| parse_failure(s, FailKind::InvalidMediaKind(s.into())))
+}
+
+/// eg revlog-kind:relearn or revlog-kind:manual:30
+fn parse_review_kind(s: &str) -> ParseResult<SearchNode> {
+    let mut it = s.splitn(2, ':');
+    let kind = match it.next().unwrap() {
+        "learn" => RevlogReviewKind::Learning,
+        "review" => RevlogReviewKind::Review,
+        "relearn" => RevlogReviewKind::Relearning,
+        "filtered" => RevlogReviewKind::Filtered,
+        "manual" => RevlogReviewKind::Manual,
+        _ => return Err(parse_failure(s, FailKind::InvalidReviewKind(s.into()))),
+    };
+    let days = it
+        .next()
+        .map(|days| parse_u32(days, "revlog-kind:"))
+        .transpose()?;
+    Ok(SearchNode::ReviewKind {
+        kind,
+        days: days.map(|days| days.max(1)),
+    })
+}
+
 fn parse_did(s: &str) -> ParseResult<SearchNode> {
     parse_i64(s, "did:").map(|n| SearchNode::DeckIdWithoutChildren(n.into()))
 }
@@ -662,13 +1060,60 @@ fn parse_dupe(s: &str) -> ParseResult<SearchNode> {
     }
 }
 
+/// eg price>10 or due_date<=2026-12-01, if the value is a number or date.
+/// The field name can't contain an unescaped colon. Other text with a
+/// comparison operator is left to be searched for as is.
+fn parse_field_comparison(s: &str) -> Option<(&str, ComparisonOperator, FieldValue)> {
+    lazy_static! {
+        static ref COMPARISON: Regex =
+            Regex::new(r"^((?:[^\\:<>=]|\\.)+)(<=|>=|=|<|>)([^<>=]+)$").unwrap();
+    }
+    let caps = COMPARISON.captures(s)?;
+    let field = caps.get(1).unwrap().as_str();
+    let operator = ComparisonOperator::parse(caps.get(2).unwrap().as_str()).unwrap();
+    let value = FieldValue::parse(caps.get(3).unwrap().as_str())?;
+    Some((field, operator, value))
+}
+
+/// True if unquoted, `text` would compare the contents of a field.
+pub(super) fn is_field_comparison(text: &str) -> bool {
+    parse_field_comparison(text).is_some()
+}
+
+/// The ends of a range like 1900..1950 or ..2026-12-01, if they are numbers
+/// or dates of the same kind.
+fn parse_field_range(start: &str, end: &str) -> Option<(Option<FieldValue>, Option<FieldValue>)> {
+    let parse_end = |text: &str| -> Option<Option<FieldValue>> {
+        if text.is_empty() {
+            Some(None)
+        } else {
+            FieldValue::parse(text).map(Some)
+        }
+    };
+    match (parse_end(start)?, parse_end(end)?) {
+        (None, None) => None,
+        (Some(start), Some(end)) if start.is_date() != end.is_date() => None,
+        range => Some(range),
+    }
+}
+
+/// eg front:foo, front:re:fo+ or year:1900..1950
 fn parse_single_field<'a>(key: &'a str, val: &'a str) -> ParseResult<'a, SearchNode> {
+    let range = val
+        .split_once("..")
+        .and_then(|(start, end)| parse_field_range(start, end));
     Ok(if let Some(stripped) = val.strip_prefix("re:") {
         SearchNode::SingleField {
             field: unescape(key)?,
             text: unescape_quotes(stripped),
             is_re: true,
         }
+    } else if let Some((start, end)) = range {
+        SearchNode::FieldRange {
+            field: unescape(key)?,
+            start,
+            end,
+        }
     } else {
         SearchNode::SingleField {
             field: unescape(key)?,
@@ -873,6 +1318,26 @@ mod test {
         assert_eq!(parse(r#"a"b"(c)"#)?, parse("a b (c)")?);
 
         assert_eq!(parse("added:3")?, vec![Search(AddedInDays(3))]);
+        assert_eq!(
+            parse("added:2026-01-01..2026-03")?,
+            vec![Search(AddedInRange(DateRange {
+                start: Some(DateBound::Day(NaiveDate::from_ymd(2026, 1, 1))),
+                end: Some(DateBound::Month {
+                    year: 2026,
+                    month: 3
+                }),
+            }))]
+        );
+        assert_eq!(
+            parse("rated:..7:4")?,
+            vec![Search(RatedInRange {
+                range: DateRange {
+                    start: None,
+                    end: Some(DateBound::DaysAgo(7)),
+                },
+                ease: RatingKind::AnswerButton(4),
+            })]
+        );
         assert_eq!(
             parse("card:front")?,
             vec![Search(CardTemplate(TemplateKind::Name("front".into())))]
@@ -916,6 +1381,166 @@ mod test {
             })]
         );
 
+        // review history
+        assert_eq!(
+            parse("prop:time>30s")?,
+            vec![Search(Property {
+                operator: ">".into(),
+                kind: PropertyKind::AnswerTime(30.0)
+            })]
+        );
+        assert_eq!(
+            parse("prop:time<=2m")?,
+            vec![Search(Property {
+                operator: "<=".into(),
+                kind: PropertyKind::AnswerTime(120.0)
+            })]
+        );
+        assert_eq!(
+            parse("prop:reviews>5:7")?,
+            vec![Search(Property {
+                operator: ">".into(),
+                kind: PropertyKind::Reviews(5, Some(7))
+            })]
+        );
+        assert_eq!(
+            parse("prop:again>=3")?,
+            vec![Search(Property {
+                operator: ">=".into(),
+                kind: PropertyKind::Again(3, None)
+            })]
+        );
+        assert_eq!(
+            parse("prop:first=-6")?,
+            vec![Search(Property {
+                operator: "=".into(),
+                kind: PropertyKind::FirstReview(DateBound::DaysAgo(7))
+            })]
+        );
+        assert_eq!(
+            parse("prop:first<2026-01-01")?,
+            vec![Search(Property {
+                operator: "<".into(),
+                kind: PropertyKind::FirstReview(DateBound::Day(NaiveDate::from_ymd(2026, 1, 1)))
+            })]
+        );
+        assert_eq!(
+            parse("revlog-kind:relearn:30")?,
+            vec![Search(ReviewKind {
+                kind: RevlogReviewKind::Relearning,
+                days: Some(30)
+            })]
+        );
+
+        // media
+        assert_eq!(
+            parse("has:latex")?,
+            vec![Search(HasMedia(MediaKind::Latex))]
+        );
+        assert_eq!(parse("media:*.jpg")?, vec![Search(Media("*.jpg".into()))]);
+        assert_eq!(
+            parse("missing-media:")?,
+            vec![Search(MissingMedia("".into()))]
+        );
+        // fields named like the revlog and media keys can still be searched
+        assert_eq!(
+            parse("Revlog:foo")?,
+            vec![Search(SingleField {
+                field: "Revlog".into(),
+                text: "foo".into(),
+                is_re: false
+            })]
+        );
+        assert_eq!(
+            parse(r#""Media:foo""#)?,
+            vec![Search(SingleField {
+                field: "Media".into(),
+                text: "foo".into(),
+                is_re: false
+            })]
+        );
+        assert_eq!(
+            parse(r#""has:audio""#)?,
+            vec![Search(SingleField {
+                field: "has".into(),
+                text: "audio".into(),
+                is_re: false
+            })]
+        );
+        // a quoted value doesn't make it a field search
+        assert_eq!(
+            parse(r#"media:"a b.jpg""#)?,
+            vec![Search(Media("a b.jpg".into()))]
+        );
+
+        // comparisons with field contents
+        assert_eq!(
+            parse("price>10.5")?,
+            vec![Search(FieldComparison {
+                field: "price".into(),
+                operator: ComparisonOperator::Greater,
+                value: FieldValue::Number(10.5),
+                text: "price>10.5".into(),
+            })]
+        );
+        assert_eq!(
+            parse("due_date<=2026-12-01")?,
+            vec![Search(FieldComparison {
+                field: "due_date".into(),
+                operator: ComparisonOperator::LessOrEqual,
+                value: FieldValue::Date(NaiveDate::from_ymd(2026, 12, 1)),
+                text: "due_date<=2026-12-01".into(),
+            })]
+        );
+        assert_eq!(
+            parse("year:1900..1950")?,
+            vec![Search(FieldRange {
+                field: "year".into(),
+                start: Some(FieldValue::Number(1900.0)),
+                end: Some(FieldValue::Number(1950.0)),
+            })]
+        );
+        assert_eq!(
+            parse(r#""due:..2026-12-01""#)?,
+            vec![Search(FieldRange {
+                field: "due".into(),
+                start: None,
+                end: Some(FieldValue::Date(NaiveDate::from_ymd(2026, 12, 1))),
+            })]
+        );
+        // other values are searched for as text, and so is quoted text
+        assert_eq!(parse("a<b")?, vec![Search(UnqualifiedText("a<b".into()))]);
+        assert_eq!(parse("<10")?, vec![Search(UnqualifiedText("<10".into()))]);
+        assert_eq!(
+            parse("year=1900..1950")?,
+            vec![Search(UnqualifiedText("year=1900..1950".into()))]
+        );
+        assert_eq!(
+            parse(r#""x>5""#)?,
+            vec![Search(UnqualifiedText("x>5".into()))]
+        );
+        assert_eq!(
+            parse(r#""n >= 0""#)?,
+            vec![Search(UnqualifiedText("n >= 0".into()))]
+        );
+        // field searches don't compare, so existing searches keep working
+        assert_eq!(
+            parse("price:>10")?,
+            vec![Search(SingleField {
+                field: "price".into(),
+                text: ">10".into(),
+                is_re: false
+            })]
+        );
+        assert_eq!(
+            parse("year:1900..2026-12-01")?,
+            vec![Search(SingleField {
+                field: "year".into(),
+                text: "1900..2026-12-01".into(),
+                is_re: false
+            })]
+        );
+
         Ok(())
     }
 
@@ -998,6 +1623,35 @@ mod test {
 
         assert_err_kind(r#""flag: ""#, InvalidFlag);
         assert_err_kind("flag:-0", InvalidFlag);
+
+        assert_err_kind("revlog-kind:cram", InvalidReviewKind("cram".into()));
+        assert_err_kind("has:video", InvalidMediaKind("video".into()));
+        assert!(matches!(
+            failkind("revlog-kind:learn:-1"),
+            SearchErrorKind::InvalidPositiveWholeNumber { .. }
+        ));
+        assert!(matches!(
+            failkind("prop:time>3h"),
+            SearchErrorKind::InvalidNumber { .. }
+        ));
+        assert!(matches!(
+            failkind("prop:again>1:x"),
+            SearchErrorKind::InvalidPositiveWholeNumber { .. }
+        ));
+        assert!(matches!(
+            failkind("prop:first>1"),
+            SearchErrorKind::InvalidNegativeWholeNumber { .. }
+        ));
+        assert!(matches!(
+            failkind("prop:first>2026-01..2026-02"),
+            SearchErrorKind::InvalidDate { .. }
+        ));
+        for days in &["-100000000", "-2147483648"] {
+            assert!(matches!(
+                failkind(&format!("prop:first={}", days)),
+                SearchErrorKind::InvalidDate { .. }
+            ));
+        }
         assert_err_kind("flag:", InvalidFlag);
         assert_err_kind("flag:8", InvalidFlag);
         assert_err_kind("flag:1.1", InvalidFlag);
@@ -1021,6 +1675,22 @@ mod test {
             ));
         }
 
+        for term in &["added", "edited", "introduced", "rated", "resched"] {
+            for date in &[
+                "2026-13",
+                "2026-02-30",
+                "..",
+                "2026-09..foo",
+                "..100000000",
+                "100000000..",
+            ] {
+                assert!(matches!(
+                    failkind(&format!("{}:{}", term, date)),
+                    SearchErrorKind::InvalidDate { .. }
+                ));
+            }
+        }
+
         assert!(matches!(
             failkind("rated:1:"),
             SearchErrorKind::InvalidAnswerButton { .. }
@@ -3,8 +3,15 @@
 
 use std::{borrow::Cow, fmt::Write};
 
+use chrono::NaiveDate;
+use itertools::Itertools;
+
 use super::{
-    parser::{Node, PropertyKind, RatingKind, SearchNode, StateKind, TemplateKind},
+    fts::full_text_query,
+    parser::{
+        ComparisonOperator, DateBound, DateRange, FieldValue, Node, PropertyKind, RatingKind,
+        SearchNode, StateKind, TemplateKind,
+    },
     ReturnItemType,
 };
 use crate::{
@@ -14,6 +21,7 @@ use crate::{
     notes::field_checksum,
     notetype::NotetypeId,
     prelude::*,
+    revlog::RevlogReviewKind,
     storage::ids_to_string,
     text::{
         is_glob, matches_glob, normalize_to_nfc, strip_html_preserving_media_filenames,
@@ -115,21 +123,38 @@ impl SqlWriter<'_> {
         use normalize_to_nfc as norm;
         match node {
             // note fields related
-            SearchNode::UnqualifiedText(text) => self.write_unqualified(&self.norm_note(text)),
+            SearchNode::UnqualifiedText(text) => self.write_unqualified(&self.norm_note(text))?,
             SearchNode::SingleField { field, text, is_re } => {
                 self.write_single_field(&norm(field), &self.norm_note(text), *is_re)?
             }
+            SearchNode::FieldComparison {
+                field,
+                operator,
+                value,
+                text,
+            } => self.write_field_comparison(&norm(field), *operator, *value, text)?,
+            SearchNode::FieldRange { field, start, end } => {
+                self.write_field_range(&norm(field), *start, *end)?
+            }
             SearchNode::Duplicates { notetype_id, text } => {
                 self.write_dupe(*notetype_id, &self.norm_note(text))?
             }
             SearchNode::Regex(re) => self.write_regex(&self.norm_note(re)),
             SearchNode::NoCombining(text) => self.write_no_combining(&self.norm_note(text)),
             SearchNode::WordBoundary(text) => self.write_word_boundary(&self.norm_note(text)),
+            SearchNode::HasMedia(kind) => {
+                write!(self.sql, "has_media(n.flds, '{}')", kind.name()).unwrap()
+            }
+            SearchNode::Media(glob) => self.write_media(&norm(glob), false),
+            SearchNode::MissingMedia(glob) => self.write_media(&norm(glob), true),
 
             // other
             SearchNode::AddedInDays(days) => self.write_added(*days)?,
+            SearchNode::AddedInRange(range) => self.write_date_range("c.id", range, true)?,
             SearchNode::EditedInDays(days) => self.write_edited(*days)?,
+            SearchNode::EditedInRange(range) => self.write_date_range("n.mod", range, false)?,
             SearchNode::IntroducedInDays(days) => self.write_introduced(*days)?,
+            SearchNode::IntroducedInRange(range) => self.write_introduced_in_range(range)?,
             SearchNode::CardTemplate(template) => match template {
                 TemplateKind::Ordinal(_) => self.write_template(template),
                 TemplateKind::Name(name) => {
@@ -146,6 +171,8 @@ impl SqlWriter<'_> {
             SearchNode::DeckIdWithChildren(did) => self.write_deck_id_with_children(*did)?,
             SearchNode::Notetype(notetype) => self.write_notetype(&norm(notetype)),
             SearchNode::Rated { days, ease } => self.write_rated(">", -i64::from(*days), ease)?,
+            SearchNode::RatedInRange { range, ease } => self.write_rated_in_range(range, ease)?,
+            SearchNode::ReviewKind { kind, days } => self.write_review_kind(*kind, *days)?,
 
             SearchNode::Tag(tag) => self.write_tag(&norm(tag)),
             SearchNode::State(state) => self.write_state(state)?,
@@ -164,7 +191,20 @@ impl SqlWriter<'_> {
         Ok(())
     }
 
-    fn write_unqualified(&mut self, text: &str) {
+    fn write_unqualified(&mut self, text: &str) -> Result<()> {
+        if let Some(query) = full_text_query(text) {
+            if self.col.storage.full_text_index_exists()? {
+                self.args.push(query);
+                write!(
+                    self.sql,
+                    "n.id in (select rowid from notes_fts where notes_fts match ?{})",
+                    self.args.len()
+                )
+                .unwrap();
+                return Ok(());
+            }
+        }
+
         // implicitly wrap in %
         let text = format!("%{}%", &to_sql(text));
         self.args.push(text);
@@ -174,6 +214,7 @@ impl SqlWriter<'_> {
             n = self.args.len(),
         )
         .unwrap();
+        Ok(())
     }
 
     fn write_no_combining(&mut self, text: &str) {
@@ -237,15 +278,26 @@ impl SqlWriter<'_> {
             _ => unreachable!("unexpected op"),
         }
         .unwrap();
+        self.write_rating_kind(ease);
+
+        Ok(())
+    }
 
+    fn write_rated_in_range(&mut self, range: &DateRange, ease: &RatingKind) -> Result<()> {
+        self.sql.push_str("c.id in (select cid from revlog where ");
+        self.write_date_range("id", range, true)?;
+        self.write_rating_kind(ease);
+        Ok(())
+    }
+
+    /// Also closes the revlog subquery.
+    fn write_rating_kind(&mut self, ease: &RatingKind) {
         match ease {
             RatingKind::AnswerButton(u) => write!(self.sql, " and ease = {})", u),
             RatingKind::AnyAnswerButton => write!(self.sql, " and ease > 0)"),
             RatingKind::ManualReschedule => write!(self.sql, " and ease = 0)"),
         }
         .unwrap();
-
-        Ok(())
     }
 
     fn write_prop(&mut self, op: &str, kind: &PropertyKind) -> Result<()> {
@@ -286,8 +338,82 @@ impl SqlWriter<'_> {
                 write!(self.sql, "factor {} {}", op, (ease * 1000.0) as u32).unwrap()
             }
             PropertyKind::Rated(days, ease) => self.write_rated(op, i64::from(*days), ease)?,
+            PropertyKind::AnswerTime(secs) => write!(
+                self.sql,
+                "(select avg(time) from revlog where cid = c.id and ease > 0) {} {}",
+                op,
+                (secs * 1000.0) as u32
+            )
+            .unwrap(),
+            PropertyKind::Reviews(count, days) => {
+                let since = match days {
+                    Some(days) => {
+                        format!(" and id > {}", self.previous_day_cutoff(*days)?.as_millis())
+                    }
+                    None => String::new(),
+                };
+                write!(
+                    self.sql,
+                    "(select count() from revlog where cid = c.id and ease > 0{}) {} {}",
+                    since, op, count
+                )
+                .unwrap()
+            }
+            PropertyKind::Again(count, reviews) => write!(
+                self.sql,
+                concat!(
+                    "(select count() from (select ease from revlog where cid = c.id and ease > 0 ",
+                    "order by id desc limit {}) where ease = 1) {} {}"
+                ),
+                // a negative limit means no limit
+                reviews.map(i64::from).unwrap_or(-1),
+                op,
+                count
+            )
+            .unwrap(),
+            PropertyKind::FirstReview(day) => self.write_first_review(op, *day)?,
+        }
+
+        Ok(())
+    }
+
+    /// Compare the day of the card's first answer with `day`. Cards that
+    /// haven't been answered never match.
+    fn write_first_review(&mut self, op: &str, day: DateBound) -> Result<()> {
+        let (start, end, negated) = match op {
+            "=" => (Some(day), Some(day), false),
+            "!=" => (Some(day), Some(day), true),
+            ">=" => (Some(day), None, false),
+            "<" => (Some(day), None, true),
+            "<=" => (None, Some(day), false),
+            ">" => (None, Some(day), true),
+            _ => unreachable!("unexpected op"),
+        };
+        if negated {
+            self.sql.push_str("not ");
         }
+        self.sql.push('(');
+        self.write_date_range(
+            "(select min(id) from revlog where cid = c.id and ease > 0)",
+            &DateRange { start, end },
+            true,
+        )?;
+        self.sql.push(')');
+        Ok(())
+    }
 
+    fn write_review_kind(&mut self, kind: RevlogReviewKind, days: Option<u32>) -> Result<()> {
+        write!(
+            self.sql,
+            "c.id in (select cid from revlog where type = {}",
+            kind as u8
+        )
+        .unwrap();
+        if let Some(days) = days {
+            let cutoff = self.previous_day_cutoff(days)?.as_millis();
+            write!(self.sql, " and id > {}", cutoff).unwrap();
+        }
+        self.sql.push(')');
         Ok(())
     }
 
@@ -419,14 +545,15 @@ impl SqlWriter<'_> {
         }
     }
 
-    fn write_single_field(&mut self, field_name: &str, val: &str, is_re: bool) -> Result<()> {
+    /// The notetype and index of each field whose name matches `field_name`.
+    fn fields_matching(&mut self, field_name: &str) -> Result<Vec<(NotetypeId, u32)>> {
         let notetypes = self.col.get_all_notetypes()?;
 
         let mut field_map = vec![];
         for nt in notetypes.values() {
             for field in &nt.fields {
                 if matches_glob(&field.name, field_name) {
-                    field_map.push((nt.id, field.ord));
+                    field_map.push((nt.id, field.ord.unwrap_or_default()));
                 }
             }
         }
@@ -434,6 +561,11 @@ impl SqlWriter<'_> {
         // for now, sort the map for the benefit of unit tests
         field_map.sort();
 
+        Ok(field_map)
+    }
+
+    fn write_single_field(&mut self, field_name: &str, val: &str, is_re: bool) -> Result<()> {
+        let field_map = self.fields_matching(field_name)?;
         if field_map.is_empty() {
             write!(self.sql, "false").unwrap();
             return Ok(());
@@ -458,7 +590,7 @@ impl SqlWriter<'_> {
                 format!(
                     "(n.mid = {mid} and field_at_index(n.flds, {ord}) {cmp} ?{n} {cmp_trailer})",
                     mid = ntid,
-                    ord = ord.unwrap_or_default(),
+                    ord = ord,
                     cmp = cmp,
                     cmp_trailer = cmp_trailer,
                     n = arg_idx
@@ -470,6 +602,72 @@ impl SqlWriter<'_> {
         Ok(())
     }
 
+    fn write_field_comparison(
+        &mut self,
+        field_name: &str,
+        operator: ComparisonOperator,
+        value: FieldValue,
+        text: &str,
+    ) -> Result<()> {
+        let field_map = self.fields_matching(field_name)?;
+        if field_map.is_empty() {
+            // eg 1+1=2 or h2o<5, which weren't meant as comparisons
+            return self.write_unqualified(&self.norm_note(text));
+        }
+        self.write_field_value_conditions(&field_map, value.is_date(), |column| {
+            format!("{} {} {}", column, operator.as_str(), value.sql_value())
+        });
+        Ok(())
+    }
+
+    fn write_field_range(
+        &mut self,
+        field_name: &str,
+        start: Option<FieldValue>,
+        end: Option<FieldValue>,
+    ) -> Result<()> {
+        let field_map = self.fields_matching(field_name)?;
+        if field_map.is_empty() {
+            write!(self.sql, "false").unwrap();
+            return Ok(());
+        }
+        let is_date = start.or(end).map(FieldValue::is_date).unwrap_or_default();
+        self.write_field_value_conditions(&field_map, is_date, |column| match (start, end) {
+            (Some(start), Some(end)) => format!(
+                "{} between {} and {}",
+                column,
+                start.sql_value(),
+                end.sql_value()
+            ),
+            (Some(start), None) => format!("{} >= {}", column, start.sql_value()),
+            (None, Some(end)) => format!("{} <= {}", column, end.sql_value()),
+            (None, None) => "true".into(),
+        });
+        Ok(())
+    }
+
+    /// Match notes where the number or date in one of the fields meets the
+    /// condition `cond` builds for it. Fields holding something else never
+    /// match.
+    fn write_field_value_conditions(
+        &mut self,
+        field_map: &[(NotetypeId, u32)],
+        is_date: bool,
+        cond: impl Fn(&str) -> String,
+    ) {
+        let searches: Vec<_> = field_map
+            .iter()
+            .map(|(ntid, ord)| {
+                let column = format!(
+                    "comparable_field_at_index(n.flds, {}, {})",
+                    ord, is_date as u8
+                );
+                format!("(n.mid = {} and {})", ntid, cond(&column))
+            })
+            .collect();
+        write!(self.sql, "({})", searches.join(" or ")).unwrap();
+    }
+
     fn write_dupe(&mut self, ntid: NotetypeId, text: &str) -> Result<()> {
         let text_nohtml = strip_html_preserving_media_filenames(text);
         let csum = field_checksum(text_nohtml.as_ref());
@@ -525,6 +723,64 @@ impl SqlWriter<'_> {
         Ok(())
     }
 
+    fn write_introduced_in_range(&mut self, range: &DateRange) -> Result<()> {
+        self.write_date_range("(select min(id) from revlog where cid = c.id)", range, true)?;
+        self.sql
+            .push_str(" and c.id in (select cid from revlog where ");
+        self.write_date_range("id", range, true)?;
+        self.sql.push(')');
+        Ok(())
+    }
+
+    /// Require `column` to be a time from the start of the first day of the
+    /// range up to the end of its last one, in seconds or milliseconds. Days
+    /// start at the rollover hour.
+    fn write_date_range(&mut self, column: &str, range: &DateRange, millis: bool) -> Result<()> {
+        let timing = self.col.timing_today()?;
+        let today_start = timing.next_day_at.adding_secs(-86_400);
+        let today = today_start
+            .datetime(self.col.local_utc_offset_for_user()?)
+            .naive_local()
+            .date();
+        let day_start = |day: NaiveDate| {
+            let stamp = today_start.adding_secs(86_400 * (day - today).num_days());
+            if millis {
+                stamp.as_millis().0
+            } else {
+                stamp.0
+            }
+        };
+
+        let start = range
+            .start
+            .map(|bound| format!("{} >= {}", column, day_start(bound.first_day(today))));
+        let end = range
+            .end
+            .map(|bound| format!("{} < {}", column, day_start(bound.last_day(today).succ())));
+        self.sql
+            .push_str(&start.into_iter().chain(end).join(" and "));
+        Ok(())
+    }
+
+    /// Notes referencing files whose name matches the glob, or any file if it is
+    /// empty, optionally only counting files missing from the media folder.
+    fn write_media(&mut self, glob: &str, missing: bool) {
+        let re = if glob.is_empty() {
+            "null".to_string()
+        } else {
+            self.args.push(format!("(?i)^{}$", to_re(glob)));
+            format!("?{}", self.args.len())
+        };
+        let folder = if missing {
+            self.args
+                .push(self.col.media_folder.to_string_lossy().into_owned());
+            format!("?{}", self.args.len())
+        } else {
+            "null".to_string()
+        };
+        write!(self.sql, "references_media(n.flds, {}, {})", re, folder).unwrap();
+    }
+
     fn write_regex(&mut self, word: &str) {
         self.sql.push_str("n.flds regexp ?");
         self.args.push(format!(r"(?i){}", word));
@@ -579,11 +835,15 @@ impl SearchNode {
     fn required_table(&self) -> RequiredTable {
         match self {
             SearchNode::AddedInDays(_) => RequiredTable::Cards,
+            SearchNode::AddedInRange(_) => RequiredTable::Cards,
             SearchNode::IntroducedInDays(_) => RequiredTable::Cards,
+            SearchNode::IntroducedInRange(_) => RequiredTable::Cards,
             SearchNode::Deck(_) => RequiredTable::Cards,
             SearchNode::DeckIdWithoutChildren(_) => RequiredTable::Cards,
             SearchNode::DeckIdWithChildren(_) => RequiredTable::Cards,
             SearchNode::Rated { .. } => RequiredTable::Cards,
+            SearchNode::RatedInRange { .. } => RequiredTable::Cards,
+            SearchNode::ReviewKind { .. } => RequiredTable::Cards,
             SearchNode::State(_) => RequiredTable::Cards,
             SearchNode::Flag(_) => RequiredTable::Cards,
             SearchNode::CardIds(_) => RequiredTable::Cards,
@@ -591,14 +851,20 @@ impl SearchNode {
 
             SearchNode::UnqualifiedText(_) => RequiredTable::Notes,
             SearchNode::SingleField { .. } => RequiredTable::Notes,
+            SearchNode::FieldComparison { .. } => RequiredTable::Notes,
+            SearchNode::FieldRange { .. } => RequiredTable::Notes,
             SearchNode::Tag(_) => RequiredTable::Notes,
             SearchNode::Duplicates { .. } => RequiredTable::Notes,
             SearchNode::Regex(_) => RequiredTable::Notes,
             SearchNode::NoCombining(_) => RequiredTable::Notes,
             SearchNode::WordBoundary(_) => RequiredTable::Notes,
+            SearchNode::HasMedia(_) => RequiredTable::Notes,
+            SearchNode::Media(_) => RequiredTable::Notes,
+            SearchNode::MissingMedia(_) => RequiredTable::Notes,
             SearchNode::NotetypeId(_) => RequiredTable::Notes,
             SearchNode::Notetype(_) => RequiredTable::Notes,
             SearchNode::EditedInDays(_) => RequiredTable::Notes,
+            SearchNode::EditedInRange(_) => RequiredTable::Notes,
 
             SearchNode::NoteIds(_) => RequiredTable::CardsOrNotes,
             SearchNode::WholeCollection => RequiredTable::CardsOrNotes,
@@ -664,6 +930,53 @@ mod test {
             )
         );
 
+        // field comparisons
+        assert_eq!(
+            s(ctx, "front>1.5").0,
+            concat!(
+                "(((n.mid = 1581236385344 and comparable_field_at_index(n.flds, 0, 0) > 1.5) or ",
+                "(n.mid = 1581236385345 and comparable_field_at_index(n.flds, 0, 0) > 1.5) or ",
+                "(n.mid = 1581236385346 and comparable_field_at_index(n.flds, 0, 0) > 1.5) or ",
+                "(n.mid = 1581236385347 and comparable_field_at_index(n.flds, 0, 0) > 1.5)))"
+            )
+        );
+        let date = |day| FieldValue::Date(NaiveDate::from_ymd(2026, 12, day)).sql_value();
+        assert_eq!(
+            s(ctx, "front:2026-12-01..2026-12-31").0,
+            format!(
+                concat!(
+                    "(((n.mid = 1581236385344 and comparable_field_at_index(n.flds, 0, 1) ",
+                    "between {start} and {end}) or ",
+                    "(n.mid = 1581236385345 and comparable_field_at_index(n.flds, 0, 1) ",
+                    "between {start} and {end}) or ",
+                    "(n.mid = 1581236385346 and comparable_field_at_index(n.flds, 0, 1) ",
+                    "between {start} and {end}) or ",
+                    "(n.mid = 1581236385347 and comparable_field_at_index(n.flds, 0, 1) ",
+                    "between {start} and {end})))"
+                ),
+                start = date(1),
+                end = date(31),
+            )
+        );
+        assert_eq!(s(ctx, "nosuchfield:1..3").0, "(false)");
+        // comparisons on fields that don't exist are searched for as text
+        assert_eq!(
+            s(ctx, "1+1=2"),
+            (
+                "((n.sfld like ?1 escape '\\' or n.flds like ?1 escape '\\'))".into(),
+                vec!["%1+1=2%".into()]
+            )
+        );
+        assert_eq!(s(ctx, "h2o<5").1, vec!["%h2o<5%".to_string()]);
+        // quoted text with comparison operators is still searched for as text
+        assert_eq!(
+            s(ctx, r#""front>1.5""#),
+            (
+                "((n.sfld like ?1 escape '\\' or n.flds like ?1 escape '\\'))".into(),
+                vec!["%front>1.5%".into()]
+            )
+        );
+
         // added
         let timing = ctx.timing_today().unwrap();
         assert_eq!(
@@ -685,6 +998,28 @@ mod test {
         );
         assert_eq!(s(ctx, "introduced:0").0, s(ctx, "introduced:1").0,);
 
+        // date ranges
+        let today_start = timing.next_day_at.0 - 86_400;
+        assert_eq!(
+            s(ctx, "added:3..2").0,
+            format!(
+                "(c.id >= {} and c.id < {})",
+                (today_start - 86_400 * 2) * 1_000,
+                (today_start) * 1_000
+            )
+        );
+        assert_eq!(
+            s(ctx, "edited:..1").0,
+            format!("(n.mod < {})", timing.next_day_at.0)
+        );
+        assert_eq!(
+            s(ctx, "rated:2..:1").0,
+            format!(
+                "(c.id in (select cid from revlog where id >= {} and ease = 1))",
+                (today_start - 86_400) * 1_000
+            )
+        );
+
         // deck
         assert_eq!(
             s(ctx, "deck:default"),
@@ -799,6 +1134,55 @@ mod test {
         );
         assert_eq!(s(ctx, "prop:rated>-5:3").0, s(ctx, "rated:5:3").0);
 
+        // media
+        assert_eq!(s(ctx, "has:audio").0, "(has_media(n.flds, 'audio'))");
+        assert_eq!(
+            s(ctx, "media:*.jpg"),
+            (
+                "(references_media(n.flds, ?1, null))".into(),
+                vec![r"(?i)^.*\.jpg$".into()]
+            )
+        );
+        assert_eq!(
+            s(ctx, "missing-media:").0,
+            "(references_media(n.flds, null, ?1))"
+        );
+
+        // review history
+        assert_eq!(
+            s(ctx, "prop:time>1.5m").0,
+            "((select avg(time) from revlog where cid = c.id and ease > 0) > 90000)"
+        );
+        assert_eq!(
+            s(ctx, "prop:reviews>=5:7").0,
+            format!(
+                "((select count() from revlog where cid = c.id and ease > 0 and id > {}) >= 5)",
+                (timing.next_day_at.0 - (86_400 * 7)) * 1_000
+            )
+        );
+        assert_eq!(
+            s(ctx, "prop:again>2:10").0,
+            concat!(
+                "((select count() from (select ease from revlog where cid = c.id and ease > 0 ",
+                "order by id desc limit 10) where ease = 1) > 2)"
+            )
+        );
+        assert!(s(ctx, "prop:again>2").0.contains("limit -1"));
+        assert!(s(ctx, "prop:first<2026-01-01")
+            .0
+            .starts_with("(not ((select min(id) from revlog where cid = c.id and ease > 0) >= "));
+        assert_eq!(
+            s(ctx, "revlog-kind:manual").0,
+            "(c.id in (select cid from revlog where type = 4))"
+        );
+        assert_eq!(
+            s(ctx, "revlog-kind:relearn:3").0,
+            format!(
+                "(c.id in (select cid from revlog where type = 2 and id > {}))",
+                (timing.next_day_at.0 - (86_400 * 3)) * 1_000
+            )
+        );
+
         // note types by name
         assert_eq!(
             s(ctx, "note:basic"),
@@ -1,7 +1,7 @@
 // Copyright: Ankitects Pty Ltd and contributors
 // License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
 
-use std::mem;
+use std::{fmt::Display, mem};
 
 use lazy_static::lazy_static;
 use regex::Regex;
@@ -10,7 +10,11 @@ use crate::{
     decks::DeckId as DeckIdType,
     notetype::NotetypeId as NotetypeIdType,
     prelude::*,
-    search::parser::{parse, Node, PropertyKind, RatingKind, SearchNode, StateKind, TemplateKind},
+    revlog::RevlogReviewKind,
+    search::parser::{
+        is_field_comparison, is_media_key, parse, DateBound, DateRange, FieldValue, Node,
+        PropertyKind, RatingKind, SearchNode, StateKind, TemplateKind,
+    },
     text::escape_anki_wildcards,
 };
 
@@ -83,11 +87,31 @@ fn write_node(node: &Node) -> String {
 fn write_search_node(node: &SearchNode) -> String {
     use SearchNode::*;
     match node {
-        UnqualifiedText(s) => maybe_quote(&s.replace(":", "\\:")),
+        UnqualifiedText(s) => write_unqualified(s),
         SingleField { field, text, is_re } => write_single_field(field, text, *is_re),
+        FieldComparison {
+            field,
+            operator,
+            value,
+            ..
+        } => format!(
+            "{}{}{}",
+            field.replace(":", "\\:"),
+            operator.as_str(),
+            write_field_value(value)
+        ),
+        FieldRange { field, start, end } => maybe_quote(&format!(
+            "{}:{}..{}",
+            field.replace(":", "\\:"),
+            start.as_ref().map(write_field_value).unwrap_or_default(),
+            end.as_ref().map(write_field_value).unwrap_or_default()
+        )),
         AddedInDays(u) => format!("added:{}", u),
+        AddedInRange(range) => format!("added:{}", write_date_range(range)),
         EditedInDays(u) => format!("edited:{}", u),
+        EditedInRange(range) => format!("edited:{}", write_date_range(range)),
         IntroducedInDays(u) => format!("introduced:{}", u),
+        IntroducedInRange(range) => format!("introduced:{}", write_date_range(range)),
         CardTemplate(t) => write_template(t),
         Deck(s) => maybe_quote(&format!("deck:{}", s)),
         DeckIdWithoutChildren(DeckIdType(i)) => format!("did:{}", i),
@@ -96,6 +120,8 @@ fn write_search_node(node: &SearchNode) -> String {
         NotetypeId(NotetypeIdType(i)) => format!("mid:{}", i),
         Notetype(s) => maybe_quote(&format!("note:{}", s)),
         Rated { days, ease } => write_rated(days, ease),
+        RatedInRange { range, ease } => write_rated(&write_date_range(range), ease),
+        ReviewKind { kind, days } => write_review_kind(*kind, *days),
         Tag(s) => maybe_quote(&format!("tag:{}", s)),
         Duplicates { notetype_id, text } => write_dupe(notetype_id, text),
         State(k) => write_state(k),
@@ -107,6 +133,9 @@ fn write_search_node(node: &SearchNode) -> String {
         Regex(s) => maybe_quote(&format!("re:{}", s)),
         NoCombining(s) => maybe_quote(&format!("nc:{}", s)),
         WordBoundary(s) => maybe_quote(&format!("w:{}", s)),
+        HasMedia(kind) => format!("has:{}", kind.name()),
+        Media(s) => write_media(s),
+        MissingMedia(s) => maybe_quote(&format!("missing-media:{}", s)),
     }
 }
 
@@ -135,7 +164,39 @@ fn write_single_field(field: &str, text: &str, is_re: bool) -> String {
     } else {
         text.to_string()
     };
-    maybe_quote(&format!("{}:{}{}", field.replace(":", "\\:"), re, &text))
+    let text = format!("{}:{}{}", field.replace(":", "\\:"), re, &text);
+    if is_media_key(field) {
+        // unquoted, it would be a media search
+        format!("\"{}\"", text.replace("\"", "\\\""))
+    } else {
+        maybe_quote(&text)
+    }
+}
+
+/// Only the value is quoted, as a quoted `"media:..."` searches a field.
+fn write_media(text: &str) -> String {
+    if needs_quotation(text) {
+        format!("media:\"{}\"", text.replace("\"", "\\\""))
+    } else {
+        format!("media:{}", text.replace("\"", "\\\""))
+    }
+}
+
+/// Text that would compare field contents if unquoted is quoted.
+fn write_unqualified(text: &str) -> String {
+    let escaped = text.replace(":", "\\:");
+    if is_field_comparison(&escaped) {
+        format!("\"{}\"", escaped.replace("\"", "\\\""))
+    } else {
+        maybe_quote(&escaped)
+    }
+}
+
+fn write_field_value(value: &FieldValue) -> String {
+    match value {
+        FieldValue::Number(number) => number.to_string(),
+        FieldValue::Date(date) => date.format("%Y-%m-%d").to_string(),
+    }
 }
 
 fn write_template(template: &TemplateKind) -> String {
@@ -145,7 +206,7 @@ fn write_template(template: &TemplateKind) -> String {
     }
 }
 
-fn write_rated(days: &u32, ease: &RatingKind) -> String {
+fn write_rated(days: &impl Display, ease: &RatingKind) -> String {
     use RatingKind::*;
     match ease {
         AnswerButton(n) => format!("rated:{}:{}", days, n),
@@ -154,6 +215,25 @@ fn write_rated(days: &u32, ease: &RatingKind) -> String {
     }
 }
 
+fn write_date_bound(bound: &DateBound) -> String {
+    match bound {
+        DateBound::Day(day) => day.format("%Y-%m-%d").to_string(),
+        DateBound::Month { year, month } => format!("{}-{:02}", year, month),
+        DateBound::DaysAgo(days) => days.to_string(),
+    }
+}
+
+fn write_date_range(range: &DateRange) -> String {
+    let write_bound =
+        |bound: &Option<DateBound>| bound.as_ref().map(write_date_bound).unwrap_or_default();
+    // a single number is a search within the last days
+    if range.start == range.end && !matches!(range.start, Some(DateBound::DaysAgo(_))) {
+        write_bound(&range.start)
+    } else {
+        format!("{}..{}", write_bound(&range.start), write_bound(&range.end))
+    }
+}
+
 /// Escape double quotes and backslashes: \"
 fn write_dupe(notetype_id: &NotetypeId, text: &str) -> String {
     let esc = text.replace(r"\", r"\\");
@@ -191,9 +271,31 @@ fn write_property(operator: &str, kind: &PropertyKind) -> String {
             RatingKind::AnyAnswerButton => format!("prop:rated{}{}", operator, u),
             RatingKind::ManualReschedule => format!("prop:resched{}{}", operator, u),
         },
+        AnswerTime(secs) => format!("prop:time{}{}s", operator, secs),
+        Reviews(u, days) => format!("prop:reviews{}{}{}", operator, u, write_window(days)),
+        Again(u, reviews) => format!("prop:again{}{}{}", operator, u, write_window(reviews)),
+        FirstReview(DateBound::DaysAgo(days)) => {
+            format!("prop:first{}{}", operator, 1 - *days as i64)
+        }
+        FirstReview(day) => format!("prop:first{}{}", operator, write_date_bound(day)),
     }
 }
 
+fn write_window(window: &Option<u32>) -> String {
+    window.map(|n| format!(":{}", n)).unwrap_or_default()
+}
+
+fn write_review_kind(kind: RevlogReviewKind, days: Option<u32>) -> String {
+    let kind = match kind {
+        RevlogReviewKind::Learning => "learn",
+        RevlogReviewKind::Review => "review",
+        RevlogReviewKind::Relearning => "relearn",
+        RevlogReviewKind::Filtered => "filtered",
+        RevlogReviewKind::Manual => "manual",
+    };
+    format!("revlog-kind:{}{}", kind, write_window(&days))
+}
+
 pub(crate) fn deck_search(name: &str) -> String {
     write_nodes(&[Node::Search(SearchNode::Deck(escape_anki_wildcards(name)))])
 }
@@ -232,6 +334,65 @@ mod test {
         assert_eq!(r#""aNd" "oR""#, normalize_search(r#""aNd" "oR""#).unwrap());
         // normalize numbers
         assert_eq!("prop:ease>1", normalize_search("prop:ease>1.0").unwrap());
+        assert_eq!(
+            "prop:time<=90s",
+            normalize_search("prop:time<=1.5m").unwrap()
+        );
+        assert_eq!(
+            "prop:again>=2:10",
+            normalize_search("prop:again>=2:10").unwrap()
+        );
+        assert_eq!(
+            "prop:reviews>3",
+            normalize_search("prop:reviews>3").unwrap()
+        );
+        assert_eq!("prop:first>-7", normalize_search("prop:first>-7").unwrap());
+        assert_eq!(
+            "prop:first<2026-01",
+            normalize_search("prop:first<2026-1").unwrap()
+        );
+        assert_eq!(
+            "revlog-kind:learn:7",
+            normalize_search("revlog-kind:learn:7").unwrap()
+        );
+        // media
+        assert_eq!(
+            r#"media:"a b.jpg" has:audio missing-media:"#,
+            normalize_search(r#"media:"a b.jpg" has:audio missing-media:"#).unwrap()
+        );
+        // and fields with the same names
+        assert_eq!(
+            r#""media:a b.jpg" "has:audio""#,
+            normalize_search(r#""media:a b.jpg" "has:audio""#).unwrap()
+        );
+        // and dates
+        assert_eq!(
+            "added:2026-01-01..2026-03",
+            normalize_search("added:2026-1-1..2026-3").unwrap()
+        );
+        assert_eq!(
+            "rated:2026-09:2",
+            normalize_search("rated:2026-09:2").unwrap()
+        );
+        assert_eq!("edited:..7", normalize_search("edited:..7").unwrap());
+        // and field comparisons
+        assert_eq!("price>=10", normalize_search("price>=10.0").unwrap());
+        assert_eq!(
+            "due_date<2026-12-01",
+            normalize_search("due_date<2026-12-1").unwrap()
+        );
+        assert_eq!("year:1900..", normalize_search("year:1900.0..").unwrap());
+        // quoted text with comparison operators stays text
+        assert_eq!(r#""x>5""#, normalize_search(r#""x>5""#).unwrap());
+        assert_eq!(r#""n >= 0""#, normalize_search(r#""n >= 0""#).unwrap());
+        assert_eq!(
+            "year:1900..1950",
+            normalize_search("year:1900..1950").unwrap()
+        );
+        assert_eq!(
+            "resched:2025-12-31..",
+            normalize_search("resched:2025-12-31..").unwrap()
+        );
     }
 
     #[test]
@@ -0,0 +1,18 @@
+SELECT (
+    CASE
+      WHEN odid = 0 THEN did
+      ELSE odid
+    END
+  ) AS home_deck,
+  (
+    CASE
+      WHEN odid = 0 THEN due
+      ELSE odue
+    END
+  ) AS day,
+  count()
+FROM cards
+WHERE queue = ?
+GROUP BY home_deck,
+  day
+HAVING day > ?
@@ -3,7 +3,11 @@
 
 pub(crate) mod filtered;
 
-use std::{collections::HashSet, convert::TryFrom, result};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    result,
+};
 
 use rusqlite::{
     named_params, params,
@@ -229,6 +233,39 @@ impl super::SqliteStorage {
         Ok(())
     }
 
+    /// The number of cards in the review queue due on each day after `today`,
+    /// by home deck.
+    pub(crate) fn review_due_counts_by_deck(
+        &self,
+        today: u32,
+    ) -> Result<HashMap<DeckId, HashMap<u32, u32>>> {
+        let mut counts: HashMap<DeckId, HashMap<u32, u32>> = HashMap::new();
+        let mut stmt = self.db.prepare_cached(include_str!("due_counts.sql"))?;
+        let mut rows = stmt.query(params![CardQueue::Review as i8, today])?;
+        while let Some(row) = rows.next()? {
+            counts
+                .entry(row.get(0)?)
+                .or_default()
+                .insert(row.get(1)?, row.get(2)?);
+        }
+
+        Ok(counts)
+    }
+
+    /// The shortest interval of the studied, unsuspended siblings of each note
+    /// that has new cards.
+    pub(crate) fn studied_sibling_intervals(&self) -> Result<HashMap<NoteId, u32>> {
+        let params = named_params! {
+            ":new_type": CardType::New as i8,
+            ":suspended_queue": CardQueue::Suspended as i8,
+            ":new_queue": CardQueue::New as i8,
+        };
+        self.db
+            .prepare_cached(include_str!("studied_sibling_intervals.sql"))?
+            .query_and_then(params, |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect()
+    }
+
     /// Call func() for each new card, stopping when it returns false
     /// or no more cards found.
     pub(crate) fn for_each_new_card_in_deck<F>(
@@ -437,6 +474,24 @@ impl super::SqliteStorage {
             .collect()
     }
 
+    /// Cards in the review queue due between `first_day` and `last_day`
+    /// (inclusive), in due order. Cards in filtered decks are not included.
+    pub(crate) fn review_cards_due_between(
+        &self,
+        first_day: u32,
+        last_day: u32,
+    ) -> Result<Vec<Card>> {
+        self.db
+            .prepare_cached(concat!(
+                include_str!("get_card.sql"),
+                " where queue = ? and odid = 0 and due between ? and ? order by due, id"
+            ))?
+            .query_and_then(params![CardQueue::Review as i8, first_day, last_day], |r| {
+                row_to_card(r).map_err(Into::into)
+            })?
+            .collect()
+    }
+
     pub(crate) fn all_searched_cards_in_search_order(&self) -> Result<Vec<Card>> {
         self.db
             .prepare_cached(concat!(
@@ -584,6 +639,8 @@ enum ReviewOrderSubclause {
     IntervalsDescending,
     EaseAscending,
     EaseDescending,
+    RelativeOverdueness,
+    RetrievabilityAscending,
 }
 
 impl ReviewOrderSubclause {
@@ -596,6 +653,13 @@ impl ReviewOrderSubclause {
             ReviewOrderSubclause::IntervalsDescending => "ivl desc",
             ReviewOrderSubclause::EaseAscending => "factor asc",
             ReviewOrderSubclause::EaseDescending => "factor desc",
+            // ?2 is the current day
+            ReviewOrderSubclause::RelativeOverdueness => "(?2 - due) * 1.0 / max(ivl, 1) desc",
+            // the last review is taken to be `ivl` days before the due date,
+            // which is wrong for cards whose due date was changed by hand
+            ReviewOrderSubclause::RetrievabilityAscending => {
+                "predicted_recall(ivl, factor, data, ?2 - due + ivl) asc"
+            }
         }
     }
 }
@@ -609,6 +673,10 @@ fn review_order_sql(order: ReviewCardOrder) -> String {
         ReviewCardOrder::IntervalsDescending => vec![ReviewOrderSubclause::IntervalsDescending],
         ReviewCardOrder::EaseAscending => vec![ReviewOrderSubclause::EaseAscending],
         ReviewCardOrder::EaseDescending => vec![ReviewOrderSubclause::EaseDescending],
+        ReviewCardOrder::RelativeOverdueness => vec![ReviewOrderSubclause::RelativeOverdueness],
+        ReviewCardOrder::RetrievabilityAscending => {
+            vec![ReviewOrderSubclause::RetrievabilityAscending]
+        }
     };
     subclauses.push(ReviewOrderSubclause::Random);
 
@@ -623,7 +691,8 @@ fn review_order_sql(order: ReviewCardOrder) -> String {
 mod test {
     use std::path::Path;
 
-    use crate::{card::Card, i18n::I18n, storage::SqliteStorage};
+    use super::*;
+    use crate::{collection::open_test_collection, i18n::I18n, storage::SqliteStorage};
 
     #[test]
     fn add_card() {
@@ -635,4 +704,50 @@ mod test {
         storage.add_card(&mut card).unwrap();
         assert_ne!(id1, card.id);
     }
+
+    #[test]
+    fn review_order() -> Result<()> {
+        let mut col = open_test_collection();
+        let today = col.timing_today()?.days_elapsed;
+        // (days overdue, interval, card data)
+        let mut card_ids = vec![];
+        for (overdue, interval, data) in [
+            (1, 1, ""),
+            (10, 100, ""),
+            (4, 2, ""),
+            (3, 1, r#"{"s":100,"d":5}"#),
+        ] {
+            let mut card = col.add_review_card(today as i32 - overdue, interval)?;
+            card.data = data.into();
+            col.storage.update_card(&card)?;
+            card_ids.push(card.id);
+        }
+        let deck = col.get_deck(DeckId(1))?.unwrap();
+        col.storage.update_active_decks(&deck)?;
+
+        let gathered_order = |order| -> Result<Vec<usize>> {
+            let mut positions = vec![];
+            col.storage.for_each_due_card_in_active_decks(
+                today,
+                order,
+                DueCardKind::Review,
+                |card| {
+                    positions.push(card_ids.iter().position(|&id| id == card.id).unwrap());
+                    true
+                },
+            )?;
+            Ok(positions)
+        };
+        assert_eq!(
+            gathered_order(ReviewCardOrder::RelativeOverdueness)?,
+            [3, 2, 0, 1]
+        );
+        // the last card has a high stability, so is likely to be recalled
+        assert_eq!(
+            gathered_order(ReviewCardOrder::RetrievabilityAscending)?,
+            [2, 0, 1, 3]
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,11 @@
+SELECT nid,
+  min(ivl)
+FROM cards
+WHERE type != :new_type
+  AND queue != :suspended_queue
+  AND nid IN (
+    SELECT nid
+    FROM cards
+    WHERE queue = :new_queue
+  )
+GROUP BY nid
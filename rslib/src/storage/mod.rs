@@ -18,6 +18,7 @@ mod upgrades;
 
 use std::fmt::Write;
 
+pub(crate) use revlog::RevlogCardIds;
 pub(crate) use sqlite::SqliteStorage;
 pub(crate) use sync::open_and_check_sqlite_file;
 
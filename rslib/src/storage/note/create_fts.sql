@@ -0,0 +1,2 @@
+-- trigram tokens allow matching any substring of three or more characters
+CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(text, tokenize = 'trigram');
\ No newline at end of file
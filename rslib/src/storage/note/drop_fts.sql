@@ -0,0 +1,4 @@
+DROP TRIGGER IF EXISTS temp.notes_fts_insert;
+DROP TRIGGER IF EXISTS temp.notes_fts_update;
+DROP TRIGGER IF EXISTS temp.notes_fts_delete;
+DROP TABLE IF EXISTS notes_fts;
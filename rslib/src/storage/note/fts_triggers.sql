@@ -0,0 +1,25 @@
+-- Temporary triggers keep the index in sync with changes made through this
+-- connection, including raw SQL from legacy code. As they are not stored in
+-- the file, other clients can still modify notes without the index.
+CREATE TEMP TRIGGER IF NOT EXISTS notes_fts_insert
+AFTER
+INSERT ON main.notes BEGIN
+DELETE FROM notes_fts
+WHERE rowid = new.id;
+INSERT INTO notes_fts (rowid, text)
+VALUES (new.id, full_text_index_text(new.flds));
+END;
+CREATE TEMP TRIGGER IF NOT EXISTS notes_fts_update
+AFTER
+UPDATE OF id,
+  flds ON main.notes BEGIN
+DELETE FROM notes_fts
+WHERE rowid = old.id;
+INSERT INTO notes_fts (rowid, text)
+VALUES (new.id, full_text_index_text(new.flds));
+END;
+CREATE TEMP TRIGGER IF NOT EXISTS notes_fts_delete
+AFTER DELETE ON main.notes BEGIN
+DELETE FROM notes_fts
+WHERE rowid = old.id;
+END;
@@ -1,15 +1,17 @@
 // Copyright: Ankitects Pty Ltd and contributors
 // License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use rusqlite::{params, Row};
 
 use crate::{
     error::Result,
+    import_export::NoteMeta,
     notes::{Note, NoteId, NoteTags},
     notetype::NotetypeId,
     tags::{join_tags, split_tags},
+    text::{normalize_to_nfc, strip_html_preserving_media_filenames},
     timestamp::TimestampMillis,
 };
 
@@ -21,6 +23,16 @@ pub(crate) fn join_fields(fields: &[String]) -> String {
     fields.join("\x1f")
 }
 
+/// The fields as stored in the full text index: without HTML, in NFC form like
+/// searches, and separated like in the notes table.
+pub(super) fn full_text_index_text(fields: &str) -> String {
+    fields
+        .split('\x1f')
+        .map(|field| normalize_to_nfc(&strip_html_preserving_media_filenames(field)).into_owned())
+        .collect::<Vec<_>>()
+        .join("\x1f")
+}
+
 impl super::SqliteStorage {
     pub fn get_note(&self, nid: NoteId) -> Result<Option<Note>> {
         self.db
@@ -101,6 +113,53 @@ impl super::SqliteStorage {
         Ok(())
     }
 
+    /// True if the optional full text index of note fields has been created.
+    pub(crate) fn full_text_index_exists(&self) -> Result<bool> {
+        self.db
+            .prepare_cached(concat!(
+                "select exists(select 1 from sqlite_master ",
+                "where type = 'table' and name = 'notes_fts')"
+            ))?
+            .query_row([], |r| r.get(0))
+            .map_err(Into::into)
+    }
+
+    /// Create the full text index, and fill it with the existing notes.
+    pub(crate) fn create_full_text_index(&self) -> Result<()> {
+        self.db.execute_batch(include_str!("create_fts.sql"))?;
+        self.add_full_text_index_triggers()?;
+        self.rebuild_full_text_index()
+    }
+
+    pub(crate) fn drop_full_text_index(&self) -> Result<()> {
+        self.db.execute_batch(include_str!("drop_fts.sql"))?;
+        Ok(())
+    }
+
+    /// Must be called whenever the collection is opened with an existing
+    /// index, as the triggers that update it only last for the connection.
+    pub(crate) fn add_full_text_index_triggers(&self) -> Result<()> {
+        self.db.execute_batch(include_str!("fts_triggers.sql"))?;
+        Ok(())
+    }
+
+    /// Replace the contents of the full text index with the current fields of
+    /// all notes.
+    pub(crate) fn rebuild_full_text_index(&self) -> Result<()> {
+        self.db.execute_batch("delete from notes_fts")?;
+        let mut insert = self
+            .db
+            .prepare_cached("insert into notes_fts (rowid, text) values (?, ?)")?;
+        let mut stmt = self.db.prepare("select id, flds from notes")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let nid: NoteId = row.get(0)?;
+            let fields: String = row.get(1)?;
+            insert.execute(params![nid, full_text_index_text(&fields)])?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn note_is_orphaned(&self, nid: NoteId) -> Result<bool> {
         self.db
             .prepare_cached(include_str!("is_orphaned.sql"))?
@@ -147,6 +206,22 @@ impl super::SqliteStorage {
             .collect()
     }
 
+    pub(crate) fn note_metas_by_guid(&self) -> Result<HashMap<String, NoteMeta>> {
+        self.db
+            .prepare("select guid, id, mid, mod from notes")?
+            .query_and_then([], |r| {
+                Ok((
+                    r.get(0)?,
+                    NoteMeta {
+                        id: r.get(1)?,
+                        notetype_id: r.get(2)?,
+                        mtime: r.get(3)?,
+                    },
+                ))
+            })?
+            .collect()
+    }
+
     /// Return total number of notes. Slow.
     pub(crate) fn total_notes(&self) -> Result<u32> {
         self.db
@@ -0,0 +1,26 @@
+SELECT r.id,
+  r.cid,
+  r.usn,
+  r.ease,
+  cast(r.ivl AS integer),
+  cast(r.lastIvl AS integer),
+  r.factor,
+  r.time,
+  r.type,
+  c.nid,
+  c.ord,
+  (
+    CASE
+      WHEN c.odid = 0 THEN c.did
+      ELSE c.odid
+    END
+  ),
+  n.mid
+FROM revlog r
+  JOIN cards c ON c.id = r.cid
+  JOIN notes n ON n.id = c.nid
+WHERE r.cid IN (
+    SELECT cid
+    FROM search_cids
+  )
+ORDER BY r.id
@@ -22,6 +22,15 @@ pub(crate) struct StudiedToday {
     pub seconds: f64,
 }
 
+/// Identifiers of the card a revlog entry belongs to.
+pub(crate) struct RevlogCardIds {
+    pub note_id: NoteId,
+    /// The home deck if the card is in a filtered deck.
+    pub deck_id: DeckId,
+    pub notetype_id: NotetypeId,
+    pub template_idx: u16,
+}
+
 impl FromSql for RevlogReviewKind {
     fn column_result(value: ValueRef<'_>) -> std::result::Result<Self, FromSqlError> {
         if let ValueRef::Integer(i) = value {
@@ -120,6 +129,38 @@ impl SqliteStorage {
             .collect()
     }
 
+    pub(crate) fn get_revlog_entries_for_searched_cards_in_card_order(
+        &self,
+    ) -> Result<Vec<RevlogEntry>> {
+        self.db
+            .prepare_cached(concat!(
+                include_str!("get.sql"),
+                " where cid in (select cid from search_cids) order by cid, id"
+            ))?
+            .query_and_then([], row_to_revlog_entry)?
+            .collect()
+    }
+
+    /// Entries of the searched cards in chronological order.
+    pub(crate) fn get_revlog_entries_with_card_ids_for_searched_cards(
+        &self,
+    ) -> Result<Vec<(RevlogEntry, RevlogCardIds)>> {
+        self.db
+            .prepare_cached(include_str!("export.sql"))?
+            .query_and_then([], |row| {
+                Ok((
+                    row_to_revlog_entry(row)?,
+                    RevlogCardIds {
+                        note_id: row.get(9)?,
+                        template_idx: row.get(10)?,
+                        deck_id: row.get(11)?,
+                        notetype_id: row.get(12)?,
+                    },
+                ))
+            })?
+            .collect()
+    }
+
     /// This includes entries from deleted cards.
     pub(crate) fn get_all_revlog_entries(
         &self,
@@ -1,20 +1,28 @@
 // Copyright: Ankitects Pty Ltd and contributors
 // License: GNU AGPL, version 3 or later; http://www.gnu.org/licenses/agpl.html
 
-use std::{borrow::Cow, cmp::Ordering, hash::Hasher, path::Path, sync::Arc};
+use std::{borrow::Cow, cmp::Ordering, collections::HashSet, hash::Hasher, path::Path, sync::Arc};
 
 use fnv::FnvHasher;
 use regex::Regex;
-use rusqlite::{functions::FunctionFlags, params, Connection};
+use rusqlite::{functions::FunctionFlags, params, types::ValueRef, Connection};
 use unicase::UniCase;
 
-use super::upgrades::{SCHEMA_MAX_VERSION, SCHEMA_MIN_VERSION, SCHEMA_STARTING_VERSION};
+use super::{
+    note::full_text_index_text,
+    upgrades::{SCHEMA_MAX_VERSION, SCHEMA_MIN_VERSION, SCHEMA_STARTING_VERSION},
+};
 use crate::{
+    card::predicted_recall,
     config::schema11::schema11_config_as_string,
     error::{AnkiError, DbErrorKind, Result},
     i18n::I18n,
     scheduler::timing::{local_minutes_west_for_stamp, v1_creation_date},
-    text::without_combining,
+    search::{
+        media::{fields_have_media, files_in_media_folder, referenced_media_files},
+        FieldValue, MediaKind,
+    },
+    text::{strip_html, without_combining},
     timestamp::TimestampMillis,
 };
 
@@ -50,9 +58,14 @@ fn open_or_create_collection_db(path: &Path) -> Result<Connection> {
     db.set_prepared_statement_cache_capacity(50);
 
     add_field_index_function(&db)?;
+    add_comparable_field_index_function(&db)?;
     add_regexp_function(&db)?;
+    add_has_media_function(&db)?;
+    add_references_media_function(&db)?;
     add_without_combining_function(&db)?;
+    add_full_text_index_text_function(&db)?;
     add_fnvhash_function(&db)?;
+    add_predicted_recall_function(&db)?;
 
     db.create_collation("unicase", unicase_compare)?;
 
@@ -75,6 +88,28 @@ fn add_field_index_function(db: &Connection) -> rusqlite::Result<()> {
     )
 }
 
+/// Adds sql function comparable_field_at_index(flds, index, is_date), which
+/// returns the number in the field at zero-based index, or if is_date is 1,
+/// its date as a count of days. HTML is ignored. If the field holds something
+/// else, returns null.
+fn add_comparable_field_index_function(db: &Connection) -> rusqlite::Result<()> {
+    db.create_scalar_function(
+        "comparable_field_at_index",
+        3,
+        FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let mut fields = ctx.get_raw(0).as_str()?.split('\x1f');
+            let idx: u16 = ctx.get(1)?;
+            let is_date: bool = ctx.get(2)?;
+            Ok(fields
+                .nth(idx as usize)
+                .and_then(|field| FieldValue::parse(&strip_html(field)))
+                .filter(|value| value.is_date() == is_date)
+                .map(FieldValue::sql_value))
+        },
+    )
+}
+
 fn add_without_combining_function(db: &Connection) -> rusqlite::Result<()> {
     db.create_scalar_function(
         "without_combining",
@@ -90,6 +125,17 @@ fn add_without_combining_function(db: &Connection) -> rusqlite::Result<()> {
     )
 }
 
+/// Adds sql function full_text_index_text(flds), which returns the text the
+/// full text index holds for the provided fields.
+fn add_full_text_index_text_function(db: &Connection) -> rusqlite::Result<()> {
+    db.create_scalar_function(
+        "full_text_index_text",
+        1,
+        FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| Ok(full_text_index_text(ctx.get_raw(0).as_str()?)),
+    )
+}
+
 fn add_fnvhash_function(db: &Connection) -> rusqlite::Result<()> {
     db.create_scalar_function("fnvhash", -1, FunctionFlags::SQLITE_DETERMINISTIC, |ctx| {
         let mut hasher = FnvHasher::default();
@@ -100,6 +146,28 @@ fn add_fnvhash_function(db: &Connection) -> rusqlite::Result<()> {
     })
 }
 
+/// Adds sql function predicted_recall(ivl, factor, data, elapsed_days), which
+/// returns the probability of recalling a review card.
+fn add_predicted_recall_function(db: &Connection) -> rusqlite::Result<()> {
+    db.create_scalar_function(
+        "predicted_recall",
+        4,
+        FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let interval: i64 = ctx.get(0)?;
+            let ease_factor: i64 = ctx.get(1)?;
+            let data = ctx.get_raw(2).as_str().unwrap_or_default();
+            let elapsed_days: f64 = ctx.get(3)?;
+            Ok(predicted_recall(
+                data,
+                interval.max(0) as u32,
+                ease_factor as f32 / 1000.0,
+                elapsed_days as f32,
+            ) as f64)
+        },
+    )
+}
+
 /// Adds sql function regexp(regex, string) -> is_match
 /// Taken from the rusqlite docs
 type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
@@ -130,6 +198,58 @@ fn add_regexp_function(db: &Connection) -> rusqlite::Result<()> {
     )
 }
 
+/// Adds sql function has_media(flds, kind), which is true if the fields
+/// contain audio, images or LaTeX, depending on kind.
+fn add_has_media_function(db: &Connection) -> rusqlite::Result<()> {
+    db.create_scalar_function("has_media", 2, FunctionFlags::SQLITE_DETERMINISTIC, |ctx| {
+        let fields = ctx.get_raw(0).as_str()?;
+        let kind = ctx.get_raw(1).as_str()?;
+        Ok(MediaKind::from_name(kind)
+            .map(|kind| fields_have_media(fields, kind))
+            .unwrap_or_default())
+    })
+}
+
+/// Adds sql function references_media(flds, regex, folder), which is true if
+/// the fields reference a media file whose name matches regex, or any file if
+/// regex is null. If folder is not null, files in it are not counted. As the
+/// result then depends on the folder's contents, it is not deterministic.
+fn add_references_media_function(db: &Connection) -> rusqlite::Result<()> {
+    db.create_scalar_function(
+        "references_media",
+        3,
+        FunctionFlags::SQLITE_UTF8,
+        |ctx| {
+            let re: Arc<Option<Regex>> =
+                ctx.get_or_create_aux(1, |vr| -> std::result::Result<_, BoxError> {
+                    Ok(match vr {
+                        ValueRef::Null => None,
+                        _ => Some(Regex::new(vr.as_str()?)?),
+                    })
+                })?;
+            let present: Arc<Option<HashSet<String>>> =
+                ctx.get_or_create_aux(2, |vr| -> std::result::Result<_, BoxError> {
+                    Ok(match vr {
+                        ValueRef::Null => None,
+                        _ => Some(files_in_media_folder(Path::new(vr.as_str()?))),
+                    })
+                })?;
+            let fields = ctx.get_raw(0).as_str()?;
+            Ok(referenced_media_files(fields).iter().any(|fname| {
+                let matches = match re.as_ref() {
+                    Some(re) => re.is_match(fname),
+                    None => true,
+                };
+                let missing = match present.as_ref() {
+                    Some(present) => !present.contains(fname),
+                    None => true,
+                };
+                matches && missing
+            }))
+        },
+    )
+}
+
 /// Fetch schema version from database.
 /// Return (must_create, version)
 fn schema_version(db: &Connection) -> Result<(bool, u8)> {
@@ -210,6 +330,10 @@ impl SqliteStorage {
             storage.commit_trx()?;
         }
 
+        if storage.full_text_index_exists()? {
+            storage.add_full_text_index_triggers()?;
+        }
+
         Ok(storage)
     }
 
@@ -286,6 +410,16 @@ impl SqliteStorage {
         Ok(())
     }
 
+    /// Write a consistent copy of the database to `path`, which must be empty
+    /// or not exist. Cannot be used inside a transaction.
+    pub(crate) fn copy_into(&self, path: &Path) -> Result<()> {
+        let path = path
+            .to_str()
+            .ok_or_else(|| AnkiError::invalid_input("invalid copy path"))?;
+        self.db.execute("vacuum into ?", [path])?;
+        Ok(())
+    }
+
     #[cfg(test)]
     pub(crate) fn db_scalar<T: rusqlite::types::FromSql>(&self, sql: &str) -> Result<T> {
         self.db.query_row(sql, [], |r| r.get(0)).map_err(Into::into)
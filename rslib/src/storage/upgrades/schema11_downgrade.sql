@@ -7,5 +7,9 @@ DROP TABLE notetypes;
 DROP TABLE decks;
 DROP INDEX idx_cards_odid;
 DROP INDEX idx_notes_mid;
+DROP TRIGGER IF EXISTS temp.notes_fts_insert;
+DROP TRIGGER IF EXISTS temp.notes_fts_update;
+DROP TRIGGER IF EXISTS temp.notes_fts_delete;
+DROP TABLE IF EXISTS notes_fts;
 UPDATE col
 SET ver = 11;
\ No newline at end of file
@@ -51,6 +51,7 @@ export class DeckOptionsState {
     readonly configList: Readable<ConfigListEntry[]>;
     readonly parentLimits: Readable<ParentLimits>;
     readonly cardStateCustomizer: Writable<string>;
+    readonly rescheduleCards: Writable<boolean> = writable(false);
     readonly currentDeck: DeckConfig.DeckConfigsForUpdate.CurrentDeck;
     readonly defaults: ConfigInner;
     readonly addonComponents: Writable<DynamicSvelteComponent[]>;
@@ -205,6 +206,7 @@ export class DeckOptionsState {
             configs,
             applyToChildren,
             cardStateCustomizer: get(this.cardStateCustomizer),
+            rescheduleCards: get(this.rescheduleCards),
         });
     }
 